@@ -0,0 +1,70 @@
+use std::env;
+use std::fs;
+use chess::pgn::pgn::read_pgn;
+use chess::polyglot::polyglot::BookBuilder;
+
+const USAGE: &str = "usage: chess-book [--plies N] [--min-games N] [--min-rating ELO] [--output FILE] PGN...";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut builder = BookBuilder::new();
+    let mut output = String::from("book.bin");
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut number = || -> u32 {
+            match args.next().map(|value| value.parse()) {
+                Some(Ok(number)) => number,
+                _ => {
+                    eprintln!("{} needs a number\n{}", arg, USAGE);
+                    std::process::exit(2);
+                }
+            }
+        };
+        match arg.as_str() {
+            "--plies" => builder = builder.max_plies(number() as usize),
+            "--min-games" => builder = builder.min_games(number()),
+            "--min-rating" => builder = builder.min_rating(number()),
+            "--output" => match args.next() {
+                Some(file) => output = file.clone(),
+                None => {
+                    eprintln!("--output needs a file\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            file => files.push(file.to_string()),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    }
+
+    let mut read = 0;
+    for file in &files {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("cannot read {}: {}", file, error);
+                std::process::exit(1);
+            }
+        };
+        for (i, game) in read_pgn(&text).into_iter().enumerate() {
+            read += 1;
+            match game {
+                Ok(game) => {
+                    builder.add_game(&game);
+                }
+                Err(reason) => eprintln!("skipping game {} of {}: {:?}", i + 1, file, reason),
+            }
+        }
+    }
+    let book = builder.build();
+    match book.save(&output) {
+        Ok(()) => println!("counted {} of {} games, wrote {} moves to {}", builder.games(), read, book.len(), output),
+        Err(error) => {
+            eprintln!("cannot write {}: {}", output, error);
+            std::process::exit(1);
+        }
+    }
+}
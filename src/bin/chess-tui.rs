@@ -0,0 +1,303 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use chess::board::board::Board;
+use chess::chess::Color;
+use chess::chess_move::chess_move::Move;
+use chess::clock::clock::{Control, TimedGame};
+use chess::engine::engine::{SearchLimits, Score, analyse_parallel, search_parallel};
+use chess::pgn::pgn::to_san;
+use chess::time_manager::time_manager::SystemClock;
+use chess::transposition::transposition::{TranspositionTable, DEFAULT_HASH_MB};
+use chess::variant::variant::Outcome;
+use chess::tui::tui::{Tui, Input, parse_input};
+
+const USAGE: &str = "usage: chess-tui [--engine white|black] [--movetime SECONDS] [--time MINUTES] [--increment SECONDS] [--fen FEN]";
+
+enum Event {
+    Input(Vec<u8>),
+    /// a line of analysis for the position with this number
+    Analysis(u64, usize, String),
+    EngineMove(u64, Move),
+}
+
+struct Options {
+    engine: Option<Color>,
+    movetime: Duration,
+    time: Option<Duration>,
+    increment: Duration,
+    fen: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { engine: None, movetime: Duration::from_secs(2), time: None, increment: Duration::from_secs(0), fen: None };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        let mut seconds = |scale: f64| -> Result<Duration, String> {
+            let number: f64 = value()?.parse().map_err(|_| format!("{} needs a number", arg))?;
+            Ok(Duration::from_millis((number.max(0.0) * scale * 1000.0) as u64))
+        };
+        match arg.as_str() {
+            "--engine" => options.engine = match value()?.as_str() {
+                "white" => Some(Color::White),
+                "black" => Some(Color::Black),
+                other => return Err(format!("the engine plays white or black, not {}", other)),
+            },
+            "--movetime" => options.movetime = seconds(1.0)?,
+            "--time" => options.time = Some(seconds(60.0)?),
+            "--increment" => options.increment = seconds(1.0)?,
+            "--fen" => options.fen = Some(value()?.clone()),
+            other => return Err(format!("unknown argument {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+/// puts the terminal into raw mode with mouse reporting on an alternate screen, and back again
+/// when dropped
+struct Terminal {
+    saved: String,
+    /// the height last asked for, stty is only run again when there is input
+    rows: usize,
+}
+
+impl Terminal {
+    fn stty(args: &[&str]) -> io::Result<String> {
+        let output = Command::new("stty").args(args).stdin(Stdio::from(File::open("/dev/tty")?)).output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn open() -> io::Result<Terminal> {
+        let saved = Terminal::stty(&["-g"])?;
+        Terminal::stty(&["raw", "-echo"])?;
+        print!("\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1006h");
+        io::stdout().flush()?;
+        Ok(Terminal { saved, rows: Terminal::size() })
+    }
+
+    fn size() -> usize {
+        Terminal::stty(&["size"]).ok()
+            .and_then(|size| size.split_whitespace().next().and_then(|rows| rows.parse().ok()))
+            .unwrap_or(24)
+    }
+
+    /// asks for the height again, which is worth doing when the user has done something as they
+    /// may have just resized the window
+    fn refresh(&mut self) {
+        self.rows = Terminal::size();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?1006l\x1b[?1000l\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = Terminal::stty(&[&self.saved]);
+    }
+}
+
+/// analyses the position until stopped, sending the best few lines as they come in
+fn start_analysis(board: &Board, position: u64, table: &Arc<TranspositionTable>, events: &Sender<Event>) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (board, table, events, search_stop) = (board.clone(), Arc::clone(table), events.clone(), Arc::clone(&stop));
+    thread::spawn(move || {
+        let limits = SearchLimits { infinite: true, multi_pv: 3, ..SearchLimits::default() };
+        analyse_parallel(&board, limits, 1, table, search_stop, |info| {
+            let score = match info.score {
+                Score::Centipawns(cp) => {
+                    let cp = if board.turn() == Color::White { cp } else { -cp };
+                    format!("{:+.2}", cp as f64 / 100.0)
+                }
+                Score::Mate(moves) => format!("#{}", if board.turn() == Color::White { moves } else { -moves }),
+            };
+            let mut line = board.clone();
+            let mut pv = Vec::new();
+            for m in info.pv.iter().take(8) {
+                pv.push(to_san(&line, *m));
+                if line.make_move(*m).is_err() {
+                    break;
+                }
+            }
+            let text = format!(" {:>6}  depth {:<3} {}", score, info.depth, pv.join(" "));
+            let _ = events.send(Event::Analysis(position, info.multipv, text));
+        });
+    });
+    stop
+}
+
+fn start_engine(board: &Board, position: u64, movetime: Duration, table: &Arc<TranspositionTable>, events: &Sender<Event>) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (board, table, events, search_stop) = (board.clone(), Arc::clone(table), events.clone(), Arc::clone(&stop));
+    thread::spawn(move || {
+        let limits = SearchLimits { movetime: Some(movetime), ..SearchLimits::default() };
+        if let Some(m) = search_parallel(&board, limits, 1, table, search_stop, |_| {}) {
+            let _ = events.send(Event::EngineMove(position, m));
+        }
+    });
+    stop
+}
+
+fn game_over(board: &Board) -> Option<&'static str> {
+    if board.is_checkmate() {
+        Some("checkmate")
+    } else if board.variant_outcome().is_some() {
+        Some("the game is over")
+    } else if board.is_stalemate() {
+        Some("stalemate")
+    } else {
+        None
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(reason) => {
+            eprintln!("{}\n{}", reason, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let board = match options.fen.as_ref().map_or_else(|| Ok(Board::starting_position()), |fen| Board::from_fen(fen)) {
+        Ok(board) => board,
+        Err(reason) => {
+            eprintln!("cannot set up the board: {:?}", reason);
+            std::process::exit(2);
+        }
+    };
+    let mut terminal = match Terminal::open() {
+        Ok(terminal) => terminal,
+        Err(error) => {
+            eprintln!("chess-tui needs a terminal: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let (events, received) = mpsc::channel();
+    let input_events = events.clone();
+    thread::spawn(move || {
+        let mut buffer = [0; 64];
+        let stdin = io::stdin();
+        let mut stdin = stdin.lock();
+        while let Ok(read) = stdin.read(&mut buffer) {
+            if read == 0 || input_events.send(Event::Input(buffer[..read].to_vec())).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut tui = Tui::new(board);
+    let table = Arc::new(TranspositionTable::new(DEFAULT_HASH_MB));
+    // searches tag what they send with the position they were started on, so anything that
+    // arrives after a move has been played can be told apart and thrown away
+    let mut position = 0;
+    let mut searches: Vec<Arc<AtomicBool>> = Vec::new();
+    let mut analysis: Vec<String> = Vec::new();
+    let mut analysing = true;
+    // the clock, when there is one, keeps its own copy of the game so it can rule on flags
+    let mut timed = options.time.map(|time| TimedGame::new(tui.board().clone(), Control::fischer(time, options.increment), Arc::new(SystemClock::new())));
+    let mut over = false;
+    let mut changed = true;
+
+    loop {
+        if changed {
+            for stop in searches.drain(..) {
+                stop.store(true, Ordering::SeqCst);
+            }
+            position += 1;
+            analysis.clear();
+            tui.set_analysis(Vec::new());
+            if let Some(reason) = game_over(tui.board()) {
+                tui.set_message(reason);
+                over = true;
+            }
+            if !over {
+                if options.engine == Some(tui.board().turn()) {
+                    searches.push(start_engine(tui.board(), position, options.movetime, &table, &events));
+                }
+                if analysing {
+                    searches.push(start_analysis(tui.board(), position, &table, &events));
+                }
+            }
+            changed = false;
+        }
+
+        if let Some(game) = timed.as_mut() {
+            let outcome = game.outcome();
+            tui.set_clocks(Some([game.clock().remaining(Color::White), game.clock().remaining(Color::Black)]));
+            if let (false, Some(flagged)) = (over, game.flagged()) {
+                let side = if flagged == Color::White { "white" } else { "black" };
+                if outcome == Some(Outcome::Draw) {
+                    tui.set_message(&format!("{} ran out of time, but cannot be mated so it is a draw", side));
+                } else {
+                    tui.set_message(&format!("{} ran out of time", side));
+                }
+                over = true;
+                for stop in searches.drain(..) {
+                    stop.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+        print!("\x1b[H\x1b[2J{}", tui.draw(terminal.rows));
+        let _ = io::stdout().flush();
+
+        let played: Vec<Move> = match received.recv_timeout(Duration::from_millis(200)) {
+            Ok(Event::Input(bytes)) => {
+                terminal.refresh();
+                // raw mode stops the terminal turning ctrl-c into a signal, so it quits like q
+                let inputs = parse_input(&bytes);
+                if bytes.contains(&3) || inputs.contains(&Input::Char('q')) {
+                    break;
+                }
+                let mut played = Vec::new();
+                for input in inputs {
+                    match input {
+                        Input::Char('a') => {
+                            analysing = !analysing;
+                            changed = true;
+                        }
+                        // taking a move back hands the clock back too, but not once someone has flagged
+                        Input::Char('u') if timed.as_mut().is_none_or(|game| game.take_back()) => {
+                            tui.handle(input);
+                            over = false;
+                            changed = true;
+                        }
+                        Input::Char('u') => {}
+                        _ if over || options.engine == Some(tui.board().turn()) => {}
+                        _ => played.extend(tui.handle(input)),
+                    }
+                }
+                played
+            }
+            Ok(Event::Analysis(from, multipv, text)) if from == position => {
+                analysis.resize(analysis.len().max(multipv), String::new());
+                analysis[multipv - 1] = text;
+                tui.set_analysis(analysis.clone());
+                Vec::new()
+            }
+            Ok(Event::EngineMove(from, m)) if from == position && tui.play(m) => vec!(m),
+            Ok(_) | Err(RecvTimeoutError::Timeout) => Vec::new(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        for m in played {
+            // the clock has the last word, a move made after the flag fell is taken back and the
+            // flag reported next time round
+            if let Some(Err(_)) = timed.as_mut().map(|game| game.make_move(m)) {
+                tui.undo();
+            }
+            changed = true;
+        }
+    }
+
+    for stop in searches {
+        stop.store(true, Ordering::SeqCst);
+    }
+    drop(terminal);
+}
@@ -0,0 +1,29 @@
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc;
+use std::thread;
+use chess::uci::uci::Uci;
+
+fn main() {
+    let (sender, receiver) = mpsc::channel::<String>();
+    let printer = thread::spawn(move || {
+        let stdout = io::stdout();
+        for line in receiver {
+            let mut out = stdout.lock();
+            if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut uci = Uci::new(sender);
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        match line {
+            Ok(line) if uci.handle(&line) => continue,
+            _ => break,
+        }
+    }
+    uci.handle("quit");
+    drop(uci);
+    printer.join().expect("the printer thread panicked");
+}
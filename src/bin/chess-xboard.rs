@@ -0,0 +1,29 @@
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc;
+use std::thread;
+use chess::xboard::xboard::XBoard;
+
+fn main() {
+    let (sender, receiver) = mpsc::channel::<String>();
+    let printer = thread::spawn(move || {
+        let stdout = io::stdout();
+        for line in receiver {
+            let mut out = stdout.lock();
+            if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut xboard = XBoard::new(sender);
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        match line {
+            Ok(line) if xboard.handle(&line) => continue,
+            _ => break,
+        }
+    }
+    xboard.handle("quit");
+    drop(xboard);
+    printer.join().expect("the printer thread panicked");
+}
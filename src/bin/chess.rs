@@ -0,0 +1,308 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chess::board::board::Board;
+use chess::chess::{Color, FailReason};
+use chess::chess_move::chess_move::Move;
+use chess::eco::eco::Openings;
+use chess::engine::engine::{SearchLimits, search_parallel};
+use chess::pgn::pgn::{parse_san, to_san, write_pgn};
+use chess::polyglot::polyglot::Book;
+use chess::render::render::{render, TextStyle};
+use chess::syzygy::syzygy::Tablebase;
+use chess::transposition::transposition::{TranspositionTable, DEFAULT_HASH_MB};
+use chess::variant::variant::{Variant, Outcome};
+
+const USAGE: &str = "usage: chess [--engine white|black] [--movetime SECONDS] [--variant NAME] [--fen FEN] [--pgn FILE] [--book FILE] [--syzygy DIR] [--unicode]";
+
+const HELP: &str = "enter moves as e4, Nf3, O-O or e2e4, or one of
+  undo    take back the last move
+  flip    turn the board around
+  fen     show the position as FEN
+  pgn     show the game so far as PGN
+  resign  give up the game
+  quit    stop without a result";
+
+struct Options {
+    engine: Option<Color>,
+    movetime: Duration,
+    variant: Variant,
+    fen: Option<String>,
+    pgn: String,
+    /// a polyglot book for the engine to play its openings from
+    book: Option<String>,
+    /// a directory of syzygy tables for the engine to play endgames from
+    syzygy: Option<String>,
+    unicode: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        engine: None,
+        movetime: Duration::from_secs(2),
+        variant: Variant::Standard,
+        fen: None,
+        pgn: String::from("game.pgn"),
+        book: None,
+        syzygy: None,
+        unicode: false,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--engine" => options.engine = match value()?.as_str() {
+                "white" => Some(Color::White),
+                "black" => Some(Color::Black),
+                other => return Err(format!("the engine plays white or black, not {}", other)),
+            },
+            "--movetime" => {
+                let seconds: f64 = value()?.parse().map_err(|_| String::from("--movetime is a number of seconds"))?;
+                options.movetime = Duration::from_millis((seconds.max(0.0) * 1000.0) as u64);
+            }
+            "--variant" => options.variant = Variant::from_name(value()?).map_err(|reason| format!("{:?}", reason))?,
+            "--fen" => options.fen = Some(value()?.clone()),
+            "--pgn" => options.pgn = value()?.clone(),
+            "--book" => options.book = Some(value()?.clone()),
+            "--syzygy" => options.syzygy = Some(value()?.clone()),
+            "--unicode" => options.unicode = true,
+            other => return Err(format!("unknown argument {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+/// the result and why, once the game is over
+fn game_over(board: &Board) -> Option<(&'static str, &'static str)> {
+    if board.is_checkmate() {
+        Some(match board.turn() {
+            Color::White => ("0-1", "black mates"),
+            Color::Black => ("1-0", "white mates"),
+        })
+    } else if let Some(outcome) = board.variant_outcome() {
+        Some(match outcome {
+            Outcome::Win(Color::White) => ("1-0", "white wins"),
+            Outcome::Win(Color::Black) => ("0-1", "black wins"),
+            Outcome::Draw => ("1/2-1/2", "drawn"),
+        })
+    } else if board.is_stalemate() {
+        Some(("1/2-1/2", "stalemate"))
+    } else if board.halfmove_clock() >= 100 {
+        Some(("1/2-1/2", "fifty moves without a capture or pawn move"))
+    } else {
+        None
+    }
+}
+
+/// coordinate notation if it reads as that, otherwise algebraic
+fn parse_move(board: &Board, text: &str) -> Result<Move, FailReason> {
+    Move::from_coordinate_notation(text, board.turn()).or_else(|_| parse_san(board, text))
+}
+
+/// today's date as PGN writes it, e.g. 2020.06.30
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() / 86_400) as i64;
+    // days since 1970 to a date in the proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// writes the game to the path, or if something is already there to the first of name-2.pgn,
+/// name-3.pgn and so on that is free, returning where it went
+fn save(path: &str, pgn: &str) -> io::Result<String> {
+    let original = Path::new(path);
+    let stem = original.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let extension = original.extension().map_or_else(String::new, |extension| format!(".{}", extension.to_string_lossy()));
+    for n in 1.. {
+        let candidate = if n == 1 { original.to_path_buf() } else { original.with_file_name(format!("{}-{}{}", stem, n, extension)) };
+        match OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(mut file) => {
+                file.write_all(pgn.as_bytes())?;
+                return Ok(candidate.display().to_string());
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    unreachable!("there is always another name to try")
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(reason) => {
+            eprintln!("{}\n{}", reason, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let start = match &options.fen {
+        Some(fen) => Board::from_fen(fen),
+        None => Board::from_fen(options.variant.starting_fen()),
+    };
+    let mut start = match start {
+        Ok(board) => board,
+        Err(reason) => {
+            eprintln!("cannot set up the board: {:?}", reason);
+            std::process::exit(2);
+        }
+    };
+    // a fen can say the variant itself, with pockets or counted checks
+    if options.fen.is_none() || options.variant != Variant::Standard {
+        start.set_variant(options.variant);
+    }
+
+    let mut board = start.clone();
+    let mut moves: Vec<Move> = Vec::new();
+    let mut style = TextStyle {
+        unicode: options.unicode,
+        orientation: if options.engine == Some(Color::White) { Color::Black } else { Color::White },
+        // no escape codes when piped into a file or another program
+        ansi: io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none() && env::var("TERM").is_ok_and(|term| term != "dumb"),
+        ..TextStyle::default()
+    };
+    let book = match options.book.as_ref().map(Book::open) {
+        Some(Ok(book)) => Some(book),
+        Some(Err(error)) => {
+            eprintln!("cannot read the book: {}", error);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let tablebase = match options.syzygy.as_ref().map(Tablebase::open) {
+        Some(Ok(tablebase)) => Some(Arc::new(tablebase)),
+        Some(Err(error)) => {
+            eprintln!("cannot read the tablebase: {}", error);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let table = Arc::new(TranspositionTable::new(DEFAULT_HASH_MB));
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    // the board is only drawn again once something about it changes
+    let mut redraw = true;
+    let result = loop {
+        if redraw {
+            style.last_move = board.last_move();
+            print!("\n{}", render(&board, &style));
+            redraw = false;
+        }
+        if let Some((result, reason)) = game_over(&board) {
+            println!("{}, {}", reason, result);
+            break result;
+        }
+
+        if options.engine == Some(board.turn()) {
+            let random = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos() as u64);
+            if let Some(m) = book.as_ref().and_then(|book| book.choose(&board, random)) {
+                println!("the engine plays {} from its book", to_san(&board, m));
+                board.make_move(m).expect("book moves are checked to be legal");
+                moves.push(m);
+                redraw = true;
+                continue;
+            }
+            if let Some((m, _)) = tablebase.as_ref().and_then(|tablebase| tablebase.best_move(&board)) {
+                println!("the engine plays {} from the tablebase", to_san(&board, m));
+                board.make_move(m).expect("tablebase moves are legal");
+                moves.push(m);
+                redraw = true;
+                continue;
+            }
+            let limits = SearchLimits { movetime: Some(options.movetime), tablebase: tablebase.clone(), ..SearchLimits::default() };
+            let stop = Arc::new(AtomicBool::new(false));
+            match search_parallel(&board, limits, 1, Arc::clone(&table), stop, |_| {}) {
+                Some(m) => {
+                    println!("the engine plays {}", to_san(&board, m));
+                    board.make_move(m).expect("the engine only plays legal moves");
+                    moves.push(m);
+                    redraw = true;
+                }
+                None => break "*",
+            }
+            continue;
+        }
+
+        print!("{} to move: ", match board.turn() {
+            Color::White => "white",
+            Color::Black => "black",
+        });
+        let _ = io::stdout().flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => {
+                println!();
+                break "*";
+            }
+        };
+        match line.trim() {
+            "" => {}
+            "help" | "?" => println!("{}", HELP),
+            "undo" => {
+                // against the engine, take back its reply too so it is our move again
+                let plies = if options.engine.is_some() && moves.len() >= 2 { 2 } else { 1 };
+                for _ in 0..plies.min(moves.len()) {
+                    board.undo_last_move();
+                    moves.pop();
+                }
+                redraw = true;
+            }
+            "flip" => {
+                style.orientation = style.orientation.opposite();
+                redraw = true;
+            }
+            "fen" => println!("{}", board.to_fen()),
+            "pgn" => print!("{}", write_pgn(&[], &start, &moves, "*")),
+            "resign" => {
+                break match board.turn() {
+                    Color::White => "0-1",
+                    Color::Black => "1-0",
+                };
+            }
+            "quit" | "exit" => break "*",
+            text => match parse_move(&board, text).and_then(|m| board.make_move(m).map(|_| m)) {
+                Ok(m) => {
+                    moves.push(m);
+                    redraw = true;
+                }
+                Err(reason) => println!("illegal move: {:?}", reason),
+            }
+        }
+    };
+
+    let name = |color: Color| String::from(if options.engine == Some(color) { "chess" } else { "Human" });
+    let mut tags = vec!(
+        ("Event", String::from("Casual game")),
+        ("Site", String::from("?")),
+        ("Date", today()),
+        ("Round", String::from("-")),
+        ("White", name(Color::White)),
+        ("Black", name(Color::Black)),
+        ("Result", String::from(result)),
+    );
+    if start.variant() != Variant::Standard {
+        tags.push(("Variant", String::from(start.variant().name())));
+    }
+    if let Some(opening) = Openings::built_in().classify_moves(&start, &moves) {
+        tags.push(("ECO", opening.eco.clone()));
+        tags.push(("Opening", opening.name.clone()));
+    }
+    match save(&options.pgn, &write_pgn(&tags, &start, &moves, result)) {
+        Ok(path) if path != options.pgn => println!("{} is already there, so saved the game to {}", options.pgn, path),
+        Ok(path) => println!("saved the game to {}", path),
+        Err(error) => eprintln!("could not save the game to {}: {}", options.pgn, error),
+    }
+}
@@ -1,49 +1,647 @@
 pub mod board {
     use crate::chess::{FailReason, Piece, Movable, Color, Pawn, King};
-    use crate::location::location::Location;
+    use crate::location::location::{Location, MAX_SIZE};
     use std::collections::LinkedList;
     use crate::chess_move::chess_move::Move;
+    use crate::variant::variant::{Variant, Outcome};
+    use crate::fairy::fairy::FairyPiece;
 
+    pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    const KNIGHT_STEPS: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+    const KING_STEPS: [(i32, i32); 10] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1), (2, 0), (-2, 0)];
+    const ORTHOGONAL: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    const DIAGONAL: [(i32, i32); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+    #[derive(Clone)]
     pub struct Board {
-        squares: [[Option<Piece>; 8]; 8],
+        /// indexed [x][y], only the first width files and height ranks are used
+        squares: [[Option<Piece>; MAX_SIZE as usize]; MAX_SIZE as usize],
+        width: i32,
+        height: i32,
         past_moves: LinkedList<(Move, Piece)>,
+        history: LinkedList<Irreversible>,
+        turn: Color,
+        /// the squares of the rooks that may castle, as far as anything before the position was set up is concerned
+        castling_rights: Vec<Location>,
+        en_passant: Option<Location>,
+        halfmove_clock: u32,
+        fullmove_number: u32,
+        /// castling is written as the king taking its own rook, as the king might not move at all
+        chess960: bool,
+        variant: Variant,
+        /// pieces in hand in crazyhouse, colored by who may drop them
+        pockets: Vec<Piece>,
+        /// the squares of pieces that were pawns before they promoted
+        promoted: Vec<Location>,
+        /// how many times white and black have given check, for three-check
+        checks_given: [u32; 2],
+    }
+
+    /// a piece and the square it stood on
+    type Placed = (Location, Piece);
+
+    /// what a move destroys that undo_last_move needs to put back, kept in step with past_moves
+    #[derive(Clone)]
+    struct Irreversible {
+        taken: Option<Placed>,
+        /// where the rook came from, if the move was castling
+        castled_with: Option<Location>,
+        halfmove_clock: u32,
+        promoted: Vec<Location>,
+        /// pieces blown up around a capture in atomic chess
+        exploded: Vec<Placed>,
+        gave_check: bool,
     }
 
+    /// which two of the five squares left after placing the bishops and queen get the knights
+    const CHESS960_KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+
+    impl Default for Board {
+        fn default() -> Self {
+            Board::new()
+        }
+    }
 
     impl Board {
         pub fn new() -> Self {
-            Board {
+            Board::with_dimensions(8, 8).expect("8x8 is within the sizes a board can be")
+        }
+
+        /// an empty board with the given number of files and ranks, up to 16 of each
+        pub fn with_dimensions(width: i32, height: i32) -> Result<Self, FailReason> {
+            if width < 1 || height < 1 || width > MAX_SIZE || height > MAX_SIZE {
+                return Err(FailReason::OutOfBounds(format!("a board can be from 1x1 up to {}x{}, not {}x{}", MAX_SIZE, MAX_SIZE, width, height)));
+            }
+            let (right, top) = (width - 1, height - 1);
+            Ok(Board {
                 squares: Default::default(),
+                width,
+                height,
                 past_moves: LinkedList::new(),
+                history: LinkedList::new(),
+                turn: Color::White,
+                castling_rights: vec!(Location::new(0, 0), Location::new(right, 0), Location::new(0, top), Location::new(right, top)),
+                en_passant: None,
+                halfmove_clock: 0,
+                fullmove_number: 1,
+                chess960: false,
+                variant: Variant::Standard,
+                pockets: Vec::new(),
+                promoted: Vec::new(),
+                checks_given: [0, 0],
+            })
+        }
+
+        pub fn starting_position() -> Self {
+            Board::from_fen(STARTING_FEN).expect("the starting position is a valid fen")
+        }
+
+        /// the chess960 starting position with the given number from 0 to 959, using the standard
+        /// numbering under which 518 is the normal starting position
+        pub fn chess960(index: u32) -> Result<Self, FailReason> {
+            if index > 959 {
+                return Err(FailReason::ParseError(format!("chess960 positions are numbered 0 to 959, not {}", index)));
+            }
+            let mut back_rank = [' '; 8];
+            let mut n = index as usize;
+            back_rank[2 * (n % 4) + 1] = 'b';
+            n /= 4;
+            back_rank[2 * (n % 4)] = 'b';
+            n /= 4;
+            let empty = |back_rank: &[char; 8]| -> Vec<usize> { (0..8).filter(|x| back_rank[*x] == ' ').collect() };
+            back_rank[empty(&back_rank)[n % 6]] = 'q';
+            n /= 6;
+            let (first, second) = CHESS960_KNIGHTS[n];
+            let squares = empty(&back_rank);
+            back_rank[squares[first]] = 'n';
+            back_rank[squares[second]] = 'n';
+            for (square, piece) in empty(&back_rank).into_iter().zip(['r', 'k', 'r'].iter()) {
+                back_rank[square] = *piece;
+            }
+
+            let black: String = back_rank.iter().collect();
+            let fen = format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", black, black.to_uppercase());
+            let mut board = Board::from_fen(&fen)?;
+            board.chess960 = true;
+            Ok(board)
+        }
+
+        /// the number of files
+        pub fn width(&self) -> i32 {
+            self.width
+        }
+
+        /// the number of ranks
+        pub fn height(&self) -> i32 {
+            self.height
+        }
+
+        /// whether the square is on this board
+        pub fn contains(&self, square: &Location) -> bool {
+            square.is_within(self.width, self.height)
+        }
+
+        pub fn is_chess960(&self) -> bool {
+            self.chess960
+        }
+
+        /// switches between the two ways of writing castling, the king moving two squares or the
+        /// king taking its own rook as chess960 needs
+        pub fn set_chess960(&mut self, chess960: bool) {
+            self.chess960 = chess960;
+        }
+
+        pub fn variant(&self) -> Variant {
+            self.variant
+        }
+
+        pub fn set_variant(&mut self, variant: Variant) {
+            self.variant = variant;
+        }
+
+        /// how many checks the given color has given, which only matters in three-check
+        pub fn checks_given(&self, color: Color) -> u32 {
+            self.checks_given[color as usize]
+        }
+
+        /// the pieces the given color has in hand to drop, in crazyhouse
+        pub fn pocket(&self, color: Color) -> Vec<Piece> {
+            self.pockets.iter().filter(|piece| *piece.color() == color).cloned().collect()
+        }
+
+        pub fn from_fen(fen: &str) -> Result<Self, FailReason> {
+            let fields: Vec<&str> = fen.split_whitespace().collect();
+            if fields.len() < 4 {
+                return Err(FailReason::ParseError(String::from("a fen needs at least the placement, side to move, castling and en passant fields")));
+            }
+
+            // crazyhouse fens list the pieces in hand after the placement, e.g. RNBQKBNR[Qp]
+            let (placement, pocket) = match fields[0].find('[') {
+                Some(at) if fields[0].ends_with(']') => (&fields[0][..at], Some(&fields[0][at + 1..fields[0].len() - 1])),
+                Some(_) => return Err(FailReason::ParseError(String::from("the pieces in hand need to be closed with a ]"))),
+                None => (fields[0], None),
+            };
+            // the board is as wide as the top rank and has as many ranks as there are, so that
+            // bigger boards such as Capablanca's 10x8 are read the same way
+            let ranks: Vec<&str> = placement.split('/').collect();
+            let width = Board::squares_in_rank(ranks[0])?.0.len() as i32;
+            let mut board = Board::with_dimensions(width, ranks.len() as i32)
+                .map_err(|_| FailReason::ParseError(format!("a fen has to describe a board from 1x1 up to {}x{}, this one is {}x{}", MAX_SIZE, MAX_SIZE, width, ranks.len())))?;
+            for (i, rank) in ranks.iter().enumerate() {
+                let y = board.height - 1 - i as i32;
+                let (squares, promoted) = Board::squares_in_rank(rank)?;
+                if squares.len() as i32 != board.width {
+                    return Err(FailReason::ParseError(format!("rank {} of the fen does not have {} squares", y + 1, board.width)));
+                }
+                for (x, piece) in squares.into_iter().enumerate() {
+                    board.squares[x][y as usize] = piece;
+                }
+                board.promoted.extend(promoted.into_iter().map(|x| Location::new(x, y)));
+            }
+
+            if let Some(pocket) = pocket {
+                board.variant = Variant::Crazyhouse;
+                for c in pocket.chars() {
+                    match Piece::from_char(c) {
+                        Some(Piece::King(_)) | None => return Err(FailReason::ParseError(format!("'{}' cannot be in a pocket", c))),
+                        Some(piece) => board.pockets.push(piece),
+                    }
+                }
+            }
+
+            board.turn = match fields[1] {
+                "w" => Color::White,
+                "b" => Color::Black,
+                other => return Err(FailReason::ParseError(format!("\"{}\" is not a side to move", other)))
+            };
+
+            board.castling_rights = Vec::new();
+            if fields[2] != "-" {
+                for c in fields[2].chars() {
+                    let rook_location = board.castling_right_from_char(c)?;
+                    board.castling_rights.push(rook_location);
+                }
+            }
+            let standard_rook_files = board.castling_rights.iter().all(|rook| rook.x == 0 || rook.x == board.width - 1);
+            let standard_king_files = board.castling_rights.iter()
+                .all(|rook| board.pieces().any(|(location, piece)| location == Location::new(board.width / 2, rook.y) && piece == Piece::King(if rook.y == 0 { Color::White } else { Color::Black })));
+            if !standard_rook_files || !standard_king_files {
+                board.chess960 = true;
+            }
+
+            board.en_passant = match fields[3] {
+                "-" => None,
+                square => match Location::from_algebraic(square)? {
+                    target if board.contains(&target) => Some(target),
+                    _ => return Err(FailReason::ParseError(format!("the en passant square {} is not on the board", square))),
+                }
+            };
+
+            // three-check fens have the checks each side has left before the counters, as in 3+3,
+            // or the checks each side has given after them, as in +0+0
+            let mut counters: Vec<&str> = fields[4..].to_vec();
+            if let Some(at) = counters.iter().position(|field| field.contains('+')) {
+                let checks = counters.remove(at);
+                board.variant = Variant::ThreeCheck;
+                board.checks_given = Board::parse_checks(checks)?;
+            }
+            board.halfmove_clock = Board::parse_counter(counters.first(), 0)?;
+            board.fullmove_number = Board::parse_counter(counters.get(1), 1)?;
+            Ok(board)
+        }
+
+        /// what is on each square of a rank of a fen, where empty squares can be counted with more
+        /// than one digit, along with the files marked ~ as promoted
+        fn squares_in_rank(rank: &str) -> Result<(Vec<Option<Piece>>, Vec<i32>), FailReason> {
+            let mut squares = Vec::new();
+            let mut promoted = Vec::new();
+            let mut chars = rank.chars().peekable();
+            while let Some(c) = chars.next() {
+                if let Some(digit) = c.to_digit(10) {
+                    let mut empty = digit;
+                    while let Some(more) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        empty = empty * 10 + more;
+                        chars.next();
+                    }
+                    if empty > MAX_SIZE as u32 {
+                        return Err(FailReason::ParseError(format!("{} is more empty squares than fit in a rank", empty)));
+                    }
+                    squares.extend((0..empty).map(|_| None));
+                } else if c == '~' && !squares.is_empty() {
+                    promoted.push(squares.len() as i32 - 1);
+                } else {
+                    let piece = Piece::from_char(c).ok_or_else(|| FailReason::ParseError(format!("'{}' is not a piece", c)))?;
+                    squares.push(Some(piece));
+                }
             }
+            Ok((squares, promoted))
         }
 
-        pub fn place(&mut self, piece: Piece, location: Location) -> Result<(), ()> {
-            if self.squares[location.x as usize][location.y as usize].is_some() {
-                Err(())
+        fn parse_checks(field: &str) -> Result<[u32; 2], FailReason> {
+            let invalid = || FailReason::ParseError(format!("\"{}\" is not a count of checks", field));
+            let (given, counts) = match field.strip_prefix('+') {
+                Some(given) => (true, given),
+                None => (false, field),
+            };
+            let counts: Vec<u32> = counts.split('+').map(|count| count.parse().map_err(|_| invalid())).collect::<Result<_, _>>()?;
+            match counts[..] {
+                [white, black] if given => Ok([white, black]),
+                [white, black] if white <= 3 && black <= 3 => Ok([3 - white, 3 - black]),
+                _ => Err(invalid())
+            }
+        }
+
+        /// K and Q are the outermost rook on that side of the king, as in X-FEN. A file letter, as in
+        /// Shredder-FEN, picks out the rook on that file and means the game is chess960.
+        fn castling_right_from_char(&mut self, c: char) -> Result<Location, FailReason> {
+            let (color, y) = if c.is_ascii_uppercase() { (Color::White, 0) } else { (Color::Black, self.height - 1) };
+            match c.to_ascii_lowercase() {
+                side @ 'k' | side @ 'q' => {
+                    let files: Vec<i32> = match self.find_king(color).filter(|king| king.y == y) {
+                        Some(king) if side == 'k' => (king.x + 1..self.width).rev().collect(),
+                        Some(king) => (0..king.x).collect(),
+                        None => Vec::new(),
+                    };
+                    let outermost = files.into_iter()
+                        .map(|x| Location::new(x, y))
+                        .find(|square| self.get_piece_from(square) == Some(Piece::Rook(color)));
+                    Ok(outermost.unwrap_or_else(|| Location::new(if side == 'k' { self.width - 1 } else { 0 }, y)))
+                }
+                file @ 'a'..='p' if (file as i32 - 'a' as i32) < self.width => {
+                    self.chess960 = true;
+                    Ok(Location::new(file as i32 - 'a' as i32, y))
+                }
+                _ => Err(FailReason::ParseError(format!("'{}' is not a castling right", c)))
+            }
+        }
+
+        /// the letter to_fen writes for castling with this rook
+        fn castling_right_to_char(&self, rook_location: Location) -> char {
+            let color = if rook_location.y == 0 { Color::White } else { Color::Black };
+            let king = self.find_king(color).expect("there is a king if we can still castle");
+            let further_out: Vec<i32> = if rook_location.x > king.x { (rook_location.x + 1..self.width).collect() } else { (0..rook_location.x).collect() };
+            let outermost = !further_out.into_iter().any(|x| self.get_piece_from(&Location::new(x, rook_location.y)) == Some(Piece::Rook(color)));
+            let c = match (outermost || !self.chess960, rook_location.x > king.x) {
+                (true, true) => 'k',
+                (true, false) => 'q',
+                (false, _) => (b'a' + rook_location.x as u8) as char,
+            };
+            match color {
+                Color::White => c.to_ascii_uppercase(),
+                Color::Black => c,
+            }
+        }
+
+        fn parse_counter(field: Option<&&str>, default: u32) -> Result<u32, FailReason> {
+            match field {
+                None => Ok(default),
+                Some(s) => s.parse().map_err(|_| FailReason::ParseError(format!("\"{}\" is not a move counter", s)))
+            }
+        }
+
+        pub fn to_fen(&self) -> String {
+            let mut fen = String::new();
+            for y in (0..self.height).rev() {
+                let mut empty = 0;
+                for x in 0..self.width {
+                    match self.get_piece_from(&Location::new(x, y)) {
+                        None => empty += 1,
+                        Some(piece) => {
+                            if empty > 0 {
+                                fen.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            fen.push(piece.to_char());
+                            if self.variant == Variant::Crazyhouse && self.is_promoted(Location::new(x, y)) {
+                                fen.push('~');
+                            }
+                        }
+                    }
+                }
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                }
+                if y > 0 {
+                    fen.push('/');
+                }
+            }
+
+            if self.variant == Variant::Crazyhouse {
+                let mut pockets = self.pockets.clone();
+                pockets.sort_by_key(|piece| (*piece.color() as usize, "QRBNP".find(piece.to_char().to_ascii_uppercase())));
+                fen.push('[');
+                fen.extend(pockets.iter().map(|piece| piece.to_char()));
+                fen.push(']');
+            }
+
+            fen.push_str(match self.turn {
+                Color::White => " w ",
+                Color::Black => " b ",
+            });
+
+            let castling: String = self.castling_rights().into_iter().map(|rook| self.castling_right_to_char(rook)).collect();
+            fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+            match self.en_passant_target() {
+                None => fen.push_str(" -"),
+                Some(target) => fen.push_str(&format!(" {}", target)),
+            }
+            if self.variant == Variant::ThreeCheck {
+                let left = |color: Color| 3u32.saturating_sub(self.checks_given(color));
+                fen.push_str(&format!(" {}+{}", left(Color::White), left(Color::Black)));
+            }
+            fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+            fen
+        }
+
+        /// the position in 12 bytes and half a byte a piece, 28 for the 32 pieces chess starts
+        /// with but more for horde or a board a designer filled up. First a bitboard of the
+        /// occupied squares with a1 as the lowest bit, little endian, then a nibble for each piece
+        /// on them in the same order, low nibble first, then the halfmove clock and the number of
+        /// plies since the start of the game as two bytes each, the top bit of the plies saying
+        /// whether it is chess960. Pieces are 0 to 5 for a white pawn,
+        /// knight, bishop, rook, queen and king and 6 to 11 for black's. A pawn that can be taken
+        /// en passant is 12 and a rook that can still castle is 13 for white and 14 for black.
+        ///
+        /// Only 8x8 boards without pockets, checks to count or fairy pieces fit, and the variant is
+        /// not written so has to be set again on the decoded board.
+        pub fn to_compact(&self) -> Result<Vec<u8>, FailReason> {
+            if self.width != 8 || self.height != 8 {
+                return Err(FailReason::OutOfBounds(String::from("only 8x8 boards have a compact form")));
+            }
+            if self.variant == Variant::Crazyhouse || self.variant == Variant::ThreeCheck {
+                return Err(FailReason::ImpossibleMove(format!("a {} position has more to it than the compact form holds", self.variant.name())));
+            }
+            let en_passant_pawn = self.en_passant_target().map(|target| Location::new(target.x, target.y + if self.turn == Color::White { -1 } else { 1 }));
+            let mut occupied = 0u64;
+            let mut nibbles = Vec::new();
+            for y in 0..8 {
+                for x in 0..8 {
+                    let square = Location::new(x, y);
+                    let piece = match self.get_piece_from(&square) {
+                        Some(piece) => piece,
+                        None => continue,
+                    };
+                    let nibble = match piece {
+                        Piece::Pawn(_) if Some(square) == en_passant_pawn => 12,
+                        Piece::Rook(Color::White) if self.can_still_castle_with(square) => 13,
+                        Piece::Rook(Color::Black) if self.can_still_castle_with(square) => 14,
+                        Piece::Fairy(..) => return Err(FailReason::ImpossibleMove(String::from("fairy pieces have no compact form"))),
+                        _ => {
+                            let kind = "pnbrqk".find(piece.to_char().to_ascii_lowercase()).expect("every other piece is one of these") as u8;
+                            kind + if *piece.color() == Color::White { 0 } else { 6 }
+                        }
+                    };
+                    occupied |= 1 << (x + 8 * y);
+                    nibbles.push(nibble);
+                }
+            }
+            let mut bytes = occupied.to_le_bytes().to_vec();
+            bytes.extend(nibbles.chunks(2).map(|pair| pair[0] | pair.get(1).map_or(0, |high| high << 4)));
+            let plies = (self.fullmove_number.max(1) - 1) * 2 + if self.turn == Color::White { 0 } else { 1 };
+            let halfmove_clock = self.halfmove_clock.min(u16::MAX as u32) as u16;
+            let plies = plies.min(0x7fff) as u16 | if self.chess960 { 0x8000 } else { 0 };
+            bytes.extend_from_slice(&halfmove_clock.to_le_bytes());
+            bytes.extend_from_slice(&plies.to_le_bytes());
+            Ok(bytes)
+        }
+
+        /// reads a position written by to_compact
+        pub fn from_compact(bytes: &[u8]) -> Result<Self, FailReason> {
+            let invalid = |why: &str| FailReason::ParseError(format!("not a compact position, {}", why));
+            if bytes.len() < 12 {
+                return Err(invalid("it is too short"));
+            }
+            let mut occupancy = [0; 8];
+            occupancy.copy_from_slice(&bytes[..8]);
+            let occupied = u64::from_le_bytes(occupancy);
+            let count = occupied.count_ones() as usize;
+            if bytes.len() != 8 + count.div_ceil(2) + 4 {
+                return Err(invalid("the length does not match the number of pieces"));
+            }
+            let counters = &bytes[bytes.len() - 4..];
+            let plies = u16::from_le_bytes([counters[2], counters[3]]);
+
+            let mut board = Board::new();
+            board.castling_rights = Vec::new();
+            board.halfmove_clock = u16::from_le_bytes([counters[0], counters[1]]) as u32;
+            board.chess960 = plies & 0x8000 != 0;
+            let plies = (plies & 0x7fff) as u32;
+            board.fullmove_number = plies / 2 + 1;
+            board.turn = if plies.is_multiple_of(2) { Color::White } else { Color::Black };
+            let squares = (0..64).filter(|index| occupied & 1 << index != 0).map(|index| Location::new(index % 8, index / 8));
+            for (i, square) in squares.enumerate() {
+                let nibble = bytes[8 + i / 2] >> (4 * (i % 2)) & 15;
+                let piece = match nibble {
+                    12 => {
+                        // the pawn belongs to whoever just moved, the square behind it can be taken on
+                        let forward = if board.turn == Color::White { 1 } else { -1 };
+                        board.en_passant = Some(Location::new(square.x, square.y + forward));
+                        Piece::Pawn(board.turn.opposite())
+                    }
+                    13 | 14 => {
+                        board.castling_rights.push(square);
+                        Piece::Rook(if nibble == 13 { Color::White } else { Color::Black })
+                    }
+                    15 => return Err(invalid("15 is not a piece")),
+                    _ => {
+                        let color = if nibble < 6 { Color::White } else { Color::Black };
+                        let letter = "pnbrqk".chars().nth(nibble as usize % 6).expect("nibbles under 12 are a piece");
+                        Piece::from_char(letter).expect("pnbrqk are all pieces").with_color(color)
+                    }
+                };
+                board.squares[square.x as usize][square.y as usize] = Some(piece);
+            }
+            Ok(board)
+        }
+
+        pub fn place(&mut self, piece: Piece, location: Location) -> Result<(), FailReason> {
+            if !self.contains(&location) {
+                Err(FailReason::OutOfBounds(format!("the board is {} files by {} ranks", self.width, self.height)))
+            } else if self.squares[location.x as usize][location.y as usize].is_some() {
+                Err(FailReason::Blocked(String::from("there is already a piece there")))
             } else {
                 self.squares[location.x as usize][location.y as usize] = Some(piece);
                 Ok(())
             }
         }
 
+        pub fn turn(&self) -> Color {
+            self.turn
+        }
+
+        pub fn halfmove_clock(&self) -> u32 {
+            self.halfmove_clock
+        }
+
+        pub fn fullmove_number(&self) -> u32 {
+            self.fullmove_number
+        }
+
+        /// a hash of everything that decides which moves can be played from here
+        pub fn zobrist_key(&self) -> u64 {
+            let mut key = self.pieces().fold(0, |key, (location, piece)| {
+                key ^ Board::zobrist(10_000 + Board::piece_index(&piece) * 256 + Board::square_index(location))
+            });
+            if self.turn == Color::Black {
+                key ^= Board::zobrist(768);
+            }
+            for rook in &self.castling_rights {
+                if self.can_still_castle_with(*rook) {
+                    key ^= Board::zobrist(1100 + rook.x as u64 + if rook.y == 0 { 0 } else { 16 });
+                }
+            }
+            if let Some(target) = self.en_passant_target() {
+                key ^= Board::zobrist(784 + target.x as u64);
+            }
+            let mut in_hand = [0; 64];
+            for piece in &self.pockets {
+                in_hand[Board::piece_index(piece) as usize] += 1;
+            }
+            for (index, count) in in_hand.iter().enumerate().filter(|(_, count)| **count > 0) {
+                key ^= Board::zobrist(5000 + index as u64 * 32 + count);
+            }
+            key ^= Board::zobrist(1300 + self.checks_given[0].min(3) as u64) ^ Board::zobrist(1304 + self.checks_given[1].min(3) as u64);
+            self.promoted.iter().fold(key, |key, square| key ^ Board::zobrist(2000 + Board::square_index(*square)))
+        }
+
+        /// a number for each square of the biggest board there can be
+        fn square_index(location: Location) -> u64 {
+            (location.x + MAX_SIZE * location.y) as u64
+        }
+
+        /// splitmix64, a fixed pseudo random number for each thing that goes into a zobrist key
+        fn zobrist(index: u64) -> u64 {
+            let mut z = index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+
+        fn piece_index(piece: &Piece) -> u64 {
+            let kind = match piece {
+                Piece::Pawn(_) => 0,
+                Piece::Knight(_) => 1,
+                Piece::Bishop(_) => 2,
+                Piece::Rook(_) => 3,
+                Piece::Queen(_) => 4,
+                Piece::King(_) => 5,
+                // after the twelve standard pieces, two for each letter
+                Piece::Fairy(fairy, _) => {
+                    let letter = (fairy.letter.to_ascii_lowercase() as u64).saturating_sub('a' as u64).min(25);
+                    return 12 + letter * 2 + *piece.color() as u64;
+                }
+            };
+            match piece.color() {
+                Color::White => kind,
+                Color::Black => kind + 6,
+            }
+        }
+
+        pub fn last_move(&self) -> Option<Move> {
+            self.past_moves.front().map(|(m, _)| *m)
+        }
+
         pub fn make_move(&mut self, m: Move) -> Result<(), FailReason> {
+            if let Some(color) = [Color::White, Color::Black].iter().find(|color| self.needs_king(**color) && self.find_king(**color).is_none()) {
+                return Err(FailReason::NoPieceHere(format!("{:?} has no king, which {} needs", color, self.variant.name())));
+            }
             self.is_valid_move(m)?;
-            let moving_peice = self.get_piece_from(&m.from).expect("should be a piece here after is_valid_move call");
-            self.past_moves.push_front((m, moving_peice));
-            let color = self.get_piece_from(&m.from).unwrap().color().clone();
-            self.do_move(m);
-            if self.is_in_check(&color) {
+            if self.variant == Variant::Antichess && !self.is_capture(&m) && self.has_capture() {
+                return Err(FailReason::ImpossibleMove(String::from("taking is compulsory in antichess, and there is something to take")));
+            }
+            let moving_peice = self.moving_piece(&m).expect("should be a piece here after is_valid_move call");
+            let color = *moving_peice.color();
+            self.play(m);
+            if self.is_exposed(color) {
                 self.undo_last_move();
                 return Err(FailReason::Checked(String::from("your in check mate")));
             }
             Ok(())
         }
 
+        /// carries out a move that is already known to be legal and records it so it can be undone
+        pub(crate) fn play(&mut self, m: Move) {
+            let piece = self.moving_piece(&m).expect("play is only called with legal moves");
+            let halfmove_clock = self.halfmove_clock;
+            let castled_with = self.castling_rook(&m);
+            let promoted = self.promoted.clone();
+            let (taken, exploded) = self.do_move(m);
+            let mover = *piece.color();
+            let gave_check = self.variant == Variant::ThreeCheck && self.is_in_check(&mover.opposite());
+            if gave_check {
+                self.checks_given[mover as usize] += 1;
+            }
+            self.past_moves.push_front((m, piece));
+            self.history.push_front(Irreversible { taken, castled_with, halfmove_clock, promoted, exploded, gave_check });
+
+            self.halfmove_clock = match (piece, taken) {
+                (Piece::Pawn(_), _) | (_, Some(_)) => 0,
+                _ => halfmove_clock + 1,
+            };
+            if *piece.color() == Color::Black {
+                self.fullmove_number += 1;
+            }
+            self.turn = piece.color().opposite();
+        }
+
         fn is_valid_move(&self, m: Move) -> Result<(), FailReason> {
-            Board::do_bounds_check(m)?;
-            let piece = self.get_piece_from(&m.from)?;
+            self.do_bounds_check(m)?;
+            if let Some(dropped) = m.dropped {
+                return self.is_valid_drop(m, dropped);
+            }
+            if m.from == m.to {
+                return Err(FailReason::ImpossibleMove(String::from("a piece has to move somewhere")));
+            }
+            let piece = self.get_piece_from(&m.from)
+                .ok_or_else(|| FailReason::NoPieceHere(String::from("there is no piece to move there")))?;
             self.do_piece_specific_checks(&m, piece)?;
+            if self.castling_rook(&m).is_some() {
+                // is_valid_king_move has already looked at every square the king and rook cross
+                return Ok(());
+            }
             let blocked = piece.squares_moved_over(m)?.iter().any(|square| { self.is_blocked(m, piece, square) });
 
 
@@ -54,24 +652,85 @@ pub mod board {
             }
         }
 
+        fn is_valid_drop(&self, m: Move, dropped: Piece) -> Result<(), FailReason> {
+            if self.variant != Variant::Crazyhouse {
+                return Err(FailReason::ImpossibleMove(String::from("pieces can only be dropped in crazyhouse")));
+            }
+            if m.from != m.to || m.promoted.is_some() {
+                return Err(FailReason::ImpossibleMove(String::from("a drop only says which piece goes on which square")));
+            }
+            if !self.pockets.contains(&dropped) {
+                return Err(FailReason::NoPieceHere(String::from("there is no such piece in the pocket to drop")));
+            }
+            if self.get_piece_from(&m.to).is_some() {
+                return Err(FailReason::Blocked(String::from("pieces can only be dropped on empty squares")));
+            }
+            match dropped {
+                Piece::Pawn(_) if self.is_promotion(m) => Err(FailReason::ImpossibleMove(String::from("pawns cannot be dropped on the first or last rank"))),
+                _ => Ok(())
+            }
+        }
+
+        /// the fairy module only knows the shape of a move, whether it is blocked or has something
+        /// to hop over is down to what is on the board
+        fn is_valid_fairy_move(&self, m: &Move, fairy: &FairyPiece) -> Result<(), FailReason> {
+            let taking = self.get_piece_from(&m.to).is_some();
+            let reachable = fairy.reach(m.from, self.width, self.height, |square| self.get_piece_from(&square).is_some())
+                .iter()
+                .any(|(square, moves, captures)| *square == m.to && if taking { *captures } else { *moves });
+            if reachable {
+                Ok(())
+            } else {
+                Err(FailReason::ImpossibleMove(format!("a {} cannot get there from here", fairy.name)))
+            }
+        }
+
+        /// the piece that ends up moving, which for a drop is the one taken from the pocket
+        fn moving_piece(&self, m: &Move) -> Option<Piece> {
+            m.dropped.or_else(|| self.get_piece_from(&m.from))
+        }
+
+        fn is_promoted(&self, location: Location) -> bool {
+            self.promoted.contains(&location)
+        }
+
         fn do_piece_specific_checks(&self, m: &Move, piece: Piece) -> Result<(), FailReason> {
             match piece {
-                Piece::Pawn(c) => self.is_valid_pawn_move(&m, &c),
-                Piece::King(_) => self.is_valid_king_move(&m),
+                Piece::King(_) if self.variant == Variant::Atomic && self.get_piece_from(&m.to).is_some() && self.castling_rook(m).is_none() => {
+                    Err(FailReason::ImpossibleMove(String::from("a king cannot take in atomic chess, it would blow itself up")))
+                }
+                Piece::Pawn(c) => self.is_valid_pawn_move(m, &c),
+                _ if m.promoted.is_some() => Err(FailReason::ImpossibleMove(String::from("only pawns can be promoted"))),
+                Piece::King(c) => self.is_valid_king_move(m, &c),
+                Piece::Fairy(fairy, _) => self.is_valid_fairy_move(m, fairy),
                 _ => Ok(())
             }
         }
 
-        fn is_promotion(m: Move) -> bool {
-            m.to.y == 0 || m.to.y == 7
+        /// whether the move goes to the first or last rank, where pawns promote
+        fn is_promotion(&self, m: Move) -> bool {
+            m.to.y == 0 || m.to.y == self.height - 1
         }
 
         fn is_valid_pawn_move(&self, m: &Move, c: &Color) -> Result<(), FailReason> {
-            if Board::is_promotion(*m) && m.promoted.is_none() {
+            if self.is_promotion(*m) && m.promoted.is_none() {
                 return Err(FailReason::NeedPromotion(String::from("the pawn moved to the last row, but we dont know what you want to promote it to")));
-            } else if Pawn::is_attacking_validly(*m, &c) {
+            }
+            match m.promoted {
+                Some(_) if !self.is_promotion(*m) => return Err(FailReason::ImpossibleMove(String::from("a pawn can only promote on the last row"))),
+                Some(Piece::King(_)) if self.variant == Variant::Antichess => {}
+                Some(Piece::Pawn(_)) | Some(Piece::King(_)) => return Err(FailReason::ImpossibleMove(String::from("a pawn cannot promote to a pawn or a king"))),
+                Some(promoted) if promoted.color() != c => return Err(FailReason::ImpossibleMove(String::from("a pawn cannot promote to a piece of the other color"))),
+                _ => {}
+            }
+
+            if (m.to - m.from).as_abs_tup() == (0, 2) && !Pawn::is_in_original_position(m.from, c, self.variant, self.height) {
+                return Err(FailReason::ImpossibleMove(String::from("invalid move for a pawn, cannot move two forward if already moved")));
+            }
+
+            if Pawn::is_attacking_validly(*m, c) {
                 let target = match self.get_piece_from(&m.to) {
-                    None => return self.check_en_passant(&m),
+                    None => return self.check_en_passant(m),
                     Some(target) => target
                 };
                 match self.is_opposite_color(target, &m.from) {
@@ -80,35 +739,49 @@ pub mod board {
                     Err(err) => Err(err),
                     _ => unreachable!("covered both true and false cases for Ok and all Err")
                 }
+            } else if self.get_piece_from(&m.to).is_some() {
+                Err(FailReason::Blocked(String::from("a pawn can only take diagonally")))
             } else {
                 Ok(())
             }
         }
 
-        fn check_en_passant(&self, m: &&Move) -> Result<(), FailReason> {
-            match self.past_moves.back() {
-                None => { Err(FailReason::ImpossibleMove(String::from("can only en passant a pawn that just moved"))) }
-                Some((last_move, _)) => {
-                    if (last_move.to - last_move.from).as_abs_tup() == (0, 2) && self.is_opposite_color(self.get_piece_from(&last_move.to)?, &m.from)? {
-                        match self.get_piece_from(&last_move.to) {
-                            None => { panic!("there should be a piece here") }
-                            Some(p) => {
-                                match p {
-                                    Piece::Pawn(_) => { Ok(()) }
-                                    _ => Err(FailReason::ImpossibleMove(String::from("piece we're trying to en passant is not a pawn")))
-                                }
-                            }
-                        }
-                    } else {
-                        Err(FailReason::ImpossibleMove(String::from("last move was not a opposite colored pawn moving two spaces forward")))
+        /// the squares of the rooks that can still castle, white's first and each side's from the h file
+        pub fn castling_rights(&self) -> Vec<Location> {
+            let mut rooks: Vec<Location> = self.castling_rights.iter().cloned().filter(|rook| self.can_still_castle_with(*rook)).collect();
+            rooks.sort_by_key(|rook| (rook.y, -rook.x));
+            rooks.dedup();
+            rooks
+        }
+
+        /// the square a pawn can move to in order to take en passant, if there is one. As in FEN
+        /// it is there after every double step, whether or not a pawn is next to it.
+        pub fn en_passant_target(&self) -> Option<Location> {
+            match self.past_moves.front() {
+                None => self.en_passant,
+                Some((last_move, Piece::Pawn(_))) if (last_move.to - last_move.from).as_abs_tup() == (0, 2) => {
+                    Some(Location::new(last_move.from.x, (last_move.from.y + last_move.to.y) / 2))
+                }
+                Some(_) => None,
+            }
+        }
+
+        fn check_en_passant(&self, m: &Move) -> Result<(), FailReason> {
+            match self.en_passant_target() {
+                Some(target) if target == m.to => {
+                    let taken = self.get_piece_from(&Pawn::get_en_passant_target(m));
+                    match (taken, self.get_piece_from(&m.from)) {
+                        (Some(Piece::Pawn(taken_color)), Some(attacker)) if taken_color != *attacker.color() => Ok(()),
+                        _ => Err(FailReason::ImpossibleMove(String::from("piece we're trying to en passant is not an opposite colored pawn")))
                     }
                 }
+                _ => Err(FailReason::ImpossibleMove(String::from("can only en passant a pawn that just moved two spaces forward, and only by moving behind it")))
             }
         }
 
-        fn do_bounds_check(m: Move) -> Result<(), FailReason> {
-            if !m.from.is_in_bounds() || !m.to.is_in_bounds() {
-                return Err(FailReason::OutOfBounds(String::from("invalid move, ranges for location are 0-7 inclusive")));
+        fn do_bounds_check(&self, m: Move) -> Result<(), FailReason> {
+            if !self.contains(&m.from) || !self.contains(&m.to) {
+                Err(FailReason::OutOfBounds(format!("invalid move, the board is {} files by {} ranks", self.width, self.height)))
             } else {
                 Ok(())
             }
@@ -149,7 +822,6 @@ pub mod board {
 
         fn is_opposite_color(&self, piece: Piece, taken_square: &Location) -> Result<bool, FailReason> {
             if let Some(other) = self.get_piece_from(taken_square) {
-                println!("{:?}", other);
                 Ok(other.color().ne(piece.color()))
             } else {
                 Err(FailReason::NoPieceHere(String::from("no piece where we are trying to attack")))
@@ -164,19 +836,47 @@ pub mod board {
             self.squares[square.x as usize][square.y as usize]
         }
 
-        fn do_move(&mut self, m: Move) {
+        /// every occupied square on the board along with the piece on it
+        pub fn pieces(&self) -> impl Iterator<Item=(Location, Piece)> + '_ {
+            let height = self.height;
+            (0..self.width).flat_map(move |x| (0..height).map(move |y| Location::new(x, y)))
+                .filter_map(move |location| self.get_piece_from(&location).map(|piece| (location, piece)))
+        }
+
+        /// returns the piece taken by the move, if any, and anything blown up along with it in atomic chess
+        fn do_move(&mut self, m: Move) -> (Option<Placed>, Vec<Placed>) {
             // this should always be done after an isvalid call, this function trusts the move is valid and executes the move no matter how dumb is it
-            if King::is_castling(&m) {
-                self.do_move(King::get_rooks_move_for_castle(&m).expect("as we're in do_move, I can huck anything"))
-            } else if self.check_en_passant(&&m).is_ok() {
-                let target = Pawn::get_en_passant_target(&m);
-                self.squares[target.x as usize][target.y as usize] = None;
+            if let Some(dropped) = m.dropped {
+                if let Some(at) = self.pockets.iter().position(|piece| *piece == dropped) {
+                    self.pockets.remove(at);
+                }
+                self.squares[m.to.x as usize][m.to.y as usize] = Some(dropped);
+                return (None, Vec::new());
+            }
+            let piece = self.get_piece_from(&m.from).expect("this should really be a valid move");
+            if let Some(rook_location) = self.castling_rook(&m) {
+                let rook = self.get_piece_from(&rook_location).expect("as we're in do_move, I can huck anything");
+                let rooks_move = King::get_rooks_move_for_castle(m.from, rook_location, self.width);
+                let kings_destination = King::get_kings_destination_for_castle(m.from, rook_location, self.width);
+                self.squares[m.from.x as usize][m.from.y as usize] = None;
+                self.squares[rook_location.x as usize][rook_location.y as usize] = None;
+                self.squares[kings_destination.x as usize][kings_destination.y as usize] = Some(piece);
+                self.squares[rooks_move.to.x as usize][rooks_move.to.y as usize] = Some(rook);
+                return (None, Vec::new());
+            }
+            let mut taken = self.get_piece_from(&m.to).map(|taken| (m.to, taken));
+            match piece {
+                Piece::Pawn(_) if m.from.x != m.to.x && taken.is_none() => {
+                    let target = Pawn::get_en_passant_target(&m);
+                    taken = self.get_piece_from(&target).map(|taken| (target, taken));
+                    self.squares[target.x as usize][target.y as usize] = None;
+                }
+                _ => {}
             }
 
 
             let Move { from, to, promoted: promotion, .. } = m;
             {
-                let piece = self.get_piece_from(&from).expect("this should really be a valid move");
                 if let Some(promotion) = promotion {
                     self.squares[to.x as usize][to.y as usize] = Some(promotion);
                 } else {
@@ -184,51 +884,518 @@ pub mod board {
                 }
             }
             self.squares[from.x as usize][from.y as usize] = None;
+
+            let moved_promoted = self.is_promoted(from) || promotion.is_some();
+            if let Some((location, taken_piece)) = taken {
+                if self.variant == Variant::Crazyhouse {
+                    // a promoted piece goes back to being a pawn when it is taken
+                    let in_hand = if self.is_promoted(location) { Piece::Pawn(*piece.color()) } else { taken_piece.with_color(*piece.color()) };
+                    self.pockets.push(in_hand);
+                }
+                self.promoted.retain(|square| *square != location);
+            }
+            self.promoted.retain(|square| *square != from && *square != to);
+            if moved_promoted {
+                self.promoted.push(to);
+            }
+            let exploded = match taken {
+                Some(_) if self.variant == Variant::Atomic => self.explode(to),
+                _ => Vec::new(),
+            };
+            (taken, exploded)
         }
 
-        fn is_valid_king_move(&self, m: &Move) -> Result<(), FailReason> {
-            if King::is_castling(m) {
-                if self.past_moves.iter().any(|(past_move, _)| { past_move.to == m.from || past_move.from == m.from }) {
-                    return Err(FailReason::ImpossibleMove(String::from("cannot castle, king has already moved")));
-                } else {
-                    let rook_location = King::get_rooks_location_for_castle(m).expect("already checked king was castling");
-                    if self.past_moves.iter().any(|(past_move, _)| { past_move.from == rook_location || past_move.to == rook_location }) {
-                        return Err(FailReason::ImpossibleMove(String::from("cannot castle, rook has already moved")));
+        /// a capture in atomic chess blows up the capturing piece along with every piece but a pawn
+        /// next to where it landed. The capturing piece is left to undo_last_move to put back.
+        fn explode(&mut self, center: Location) -> Vec<Placed> {
+            self.squares[center.x as usize][center.y as usize] = None;
+            self.promoted.retain(|square| *square != center);
+            let mut exploded = Vec::new();
+            for (x, y) in KING_STEPS.iter().take(8) {
+                let square = center + Location::new(*x, *y);
+                if !self.contains(&square) {
+                    continue;
+                }
+                match self.get_piece_from(&square) {
+                    Some(Piece::Pawn(_)) | None => {}
+                    Some(piece) => {
+                        exploded.push((square, piece));
+                        self.squares[square.x as usize][square.y as usize] = None;
+                        self.promoted.retain(|promoted| *promoted != square);
                     }
                 }
             }
+            exploded
+        }
+
+        fn back_rank(&self, color: &Color) -> i32 {
+            match color {
+                Color::White => 0,
+                Color::Black => self.height - 1,
+            }
+        }
+
+        /// the square of the rook the king is castling with, if the move is castling. In chess the
+        /// king moves from the e file to the c or g file, or on a board of another width from the
+        /// middle file to the third file from either side. In chess960 it takes its own rook.
+        pub fn castling_rook(&self, m: &Move) -> Option<Location> {
+            if self.variant == Variant::Antichess {
+                return None;
+            }
+            let color = match self.get_piece_from(&m.from) {
+                Some(Piece::King(color)) if m.from.y == self.back_rank(&color) && m.to.y == m.from.y => color,
+                _ => return None
+            };
+            if self.chess960 {
+                match self.get_piece_from(&m.to) {
+                    Some(Piece::Rook(rook_color)) if rook_color == color => Some(m.to),
+                    _ => None
+                }
+            } else if m.from.x == self.width / 2 && (m.to.x - m.from.x).abs() >= 2 && (m.to.x == 2 || m.to.x == self.width - 2) {
+                Some(Location::new(if m.to.x > m.from.x { self.width - 1 } else { 0 }, m.from.y))
+            } else {
+                None
+            }
+        }
+
+        fn is_valid_king_move(&self, m: &Move, c: &Color) -> Result<(), FailReason> {
+            let rook_location = match self.castling_rook(m) {
+                Some(rook_location) => rook_location,
+                None if (m.to - m.from).as_abs_tup() == (2, 0) => {
+                    return Err(FailReason::ImpossibleMove(String::from("a king can only move two squares when castling")));
+                }
+                None => return Ok(())
+            };
+            if self.past_moves.iter().any(|(past_move, piece)| { *piece == Piece::King(*c) || past_move.to == m.from || past_move.from == m.from }) {
+                return Err(FailReason::ImpossibleMove(String::from("cannot castle, king has already moved")));
+            }
+            if self.past_moves.iter().any(|(past_move, _)| { past_move.from == rook_location || past_move.to == rook_location }) {
+                return Err(FailReason::ImpossibleMove(String::from("cannot castle, rook has already moved")));
+            }
+            if !self.castling_rights.contains(&rook_location) {
+                return Err(FailReason::ImpossibleMove(String::from("cannot castle, the right to castle on this side was lost before the game was set up")));
+            }
+            match self.get_piece_from(&rook_location) {
+                Some(Piece::Rook(rook_color)) if rook_color == *c => {}
+                _ => return Err(FailReason::ImpossibleMove(String::from("cannot castle, there is no rook to castle with")))
+            }
+
+            let kings_destination = King::get_kings_destination_for_castle(m.from, rook_location, self.width);
+            let rooks_destination = King::get_rooks_move_for_castle(m.from, rook_location, self.width).to;
+            let files = [m.from.x, rook_location.x, kings_destination.x, rooks_destination.x];
+            let (leftmost, rightmost) = (*files.iter().min().unwrap(), *files.iter().max().unwrap());
+            let in_the_way = (leftmost..=rightmost)
+                .map(|x| Location::new(x, m.from.y))
+                .any(|square| square != m.from && square != rook_location && self.get_piece_from(&square).is_some());
+            if in_the_way {
+                return Err(FailReason::Blocked(String::from("cannot castle, there are pieces between the king and the rook or where they are going")));
+            }
+
+            let kings_path = Location::new(m.from.x.min(kings_destination.x), m.from.y).locations_between(Location::new(m.from.x.max(kings_destination.x), m.from.y))?;
+            if kings_path.iter().any(|square| self.is_attacked(*square, c.opposite())) {
+                return Err(FailReason::Checked(String::from("cannot castle out of, through or into check")));
+            }
             Ok(())
         }
 
+        /// whether the rook on this square could castle now or later, ignoring anything in the way
+        fn can_still_castle_with(&self, rook_location: Location) -> bool {
+            let color = match self.get_piece_from(&rook_location) {
+                Some(Piece::Rook(color)) if self.back_rank(&color) == rook_location.y => color,
+                _ => return false
+            };
+            let king_location = match self.find_king(color) {
+                Some(king) if king.y == rook_location.y && (self.chess960 || king.x == self.width / 2) => king,
+                _ => return false
+            };
+            self.castling_rights.contains(&rook_location)
+                && !self.past_moves.iter().any(|(past_move, piece)| {
+                *piece == Piece::King(color) || [king_location, rook_location].iter().any(|square| past_move.from == *square || past_move.to == *square)
+            })
+        }
+
         pub fn undo_last_move(&mut self) {
-            let (last_move, _) = self.past_moves.pop_front().expect("if we're undoing moves, there should have been one prior");
-            self.do_move(Move::new(last_move.to, last_move.from));
-            if let Some((_, taken_piece)) = self.past_moves.iter().find(|(m, _)| { m.to == last_move.to }) {
-                self.place(*taken_piece, last_move.to).expect("just moved a piece out of this position");
+            let (last_move, piece) = self.past_moves.pop_front().expect("if we're undoing moves, there should have been one prior");
+            let Irreversible { taken, castled_with, halfmove_clock, promoted, exploded, gave_check } = self.history.pop_front().expect("history is kept in step with past_moves");
+            if gave_check {
+                self.checks_given[*piece.color() as usize] -= 1;
+            }
+            if let Some(dropped) = last_move.dropped {
+                self.squares[last_move.to.x as usize][last_move.to.y as usize] = None;
+                self.pockets.push(dropped);
+            } else if let Some(rook_location) = castled_with {
+                let rooks_move = King::get_rooks_move_for_castle(last_move.from, rook_location, self.width);
+                let kings_destination = King::get_kings_destination_for_castle(last_move.from, rook_location, self.width);
+                let rook = self.get_piece_from(&rooks_move.to).expect("the rook should still be where it castled to");
+                self.squares[rooks_move.to.x as usize][rooks_move.to.y as usize] = None;
+                self.squares[kings_destination.x as usize][kings_destination.y as usize] = None;
+                self.place(rook, rook_location).expect("the rook came from here");
+            } else {
+                self.squares[last_move.to.x as usize][last_move.to.y as usize] = None;
+            }
+            if !last_move.is_drop() {
+                self.place(piece, last_move.from).expect("the piece that moved has just been lifted");
+            }
+
+            if let Some((location, taken_piece)) = taken {
+                self.place(taken_piece, location).expect("just moved a piece out of this position");
+                if self.variant == Variant::Crazyhouse {
+                    let was_promoted = promoted.contains(&location);
+                    let in_hand = if was_promoted { Piece::Pawn(*piece.color()) } else { taken_piece.with_color(*piece.color()) };
+                    let at = self.pockets.iter().rposition(|pocketed| *pocketed == in_hand).expect("the taken piece went into the pocket");
+                    self.pockets.remove(at);
+                }
+            }
+            for (location, exploded_piece) in exploded {
+                self.place(exploded_piece, location).expect("the explosion emptied this square");
+            }
+            self.promoted = promoted;
+
+            self.halfmove_clock = halfmove_clock;
+            self.turn = *piece.color();
+            if self.turn == Color::Black {
+                self.fullmove_number -= 1;
             }
         }
 
-        fn is_in_check(&self, c: &Color) -> bool {
-            if let Some(king_pos) = self.find_king(*c) {
-                for x in 0..8 {
-                    for y in 0..8 {
-                        if self.get_piece_from(&Location::new(x, y)).unwrap_or(Piece::Rook(*c)).color() != c && self.is_valid_move(Move::new(Location::new(x, y), king_pos)).is_ok()
-                        {
-                            return true;
-                        } else {
-                            continue;
-                        };
-                    };
-                };
+        pub fn is_in_check(&self, c: &Color) -> bool {
+            if self.variant == Variant::Antichess {
+                // the king is just another piece to be given away
+                return false;
+            }
+            match self.find_king(*c) {
+                // taking a king next to its own would blow up both
+                Some(_) if self.variant == Variant::Atomic && self.kings_touch() => false,
+                Some(king_pos) => self.is_attacked(king_pos, c.opposite()),
+                None if !self.needs_king(*c) => false,
+                None => panic!("{:?} has no king, which {} needs", c, self.variant.name()),
+            }
+        }
+
+        /// whether the color has to have a king on the board, which is every side in every variant
+        /// but the horde, antichess where the king is just another piece, and atomic chess where
+        /// the game ends once one goes up
+        fn needs_king(&self, color: Color) -> bool {
+            match self.variant {
+                Variant::Horde => color == Color::Black,
+                Variant::Antichess | Variant::Atomic => false,
+                _ => true,
+            }
+        }
+
+        fn kings_touch(&self) -> bool {
+            match (self.find_king(Color::White), self.find_king(Color::Black)) {
+                (Some(white), Some(black)) => {
+                    let (x, y) = (white - black).as_abs_tup();
+                    x <= 1 && y <= 1
+                }
+                _ => false
+            }
+        }
+
+        /// whether the side that just moved has left itself open, which makes the move illegal. In
+        /// atomic chess that is losing the king, unless the other king went up with it first.
+        fn is_exposed(&self, mover: Color) -> bool {
+            match self.variant {
+                Variant::Atomic => match (self.find_king(mover), self.find_king(mover.opposite())) {
+                    (None, _) => true,
+                    (Some(_), None) => false,
+                    (Some(_), Some(_)) => self.is_in_check(&mover),
+                },
+                Variant::RacingKings => self.is_in_check(&mover) || self.is_in_check(&mover.opposite()),
+                Variant::Antichess => false,
+                _ => self.is_in_check(&mover)
+            }
+        }
+
+        /// whether the game has ended by a rule of the variant rather than by checkmate or stalemate
+        pub fn variant_outcome(&self) -> Option<Outcome> {
+            let (middle_x, middle_y) = (self.width / 2, self.height / 2);
+            let king_on = |color: Color, squares: &[(i32, i32)]| {
+                self.find_king(color).is_some_and(|king| squares.contains(&(king.x, king.y)))
+            };
+            match self.variant {
+                Variant::Atomic => match (self.find_king(Color::White), self.find_king(Color::Black)) {
+                    (None, _) => Some(Outcome::Win(Color::Black)),
+                    (_, None) => Some(Outcome::Win(Color::White)),
+                    _ => None,
+                },
+                Variant::KingOfTheHill => [Color::White, Color::Black].iter()
+                    .find(|color| king_on(**color, &[(middle_x - 1, middle_y - 1), (middle_x, middle_y - 1), (middle_x - 1, middle_y), (middle_x, middle_y)]))
+                    .map(|color| Outcome::Win(*color)),
+                Variant::ThreeCheck => [Color::White, Color::Black].iter()
+                    .find(|color| self.checks_given(**color) >= 3)
+                    .map(|color| Outcome::Win(*color)),
+                Variant::RacingKings => {
+                    let finished = |color: Color| self.find_king(color).is_some_and(|king| king.y == self.height - 1);
+                    match (finished(Color::White), finished(Color::Black)) {
+                        (true, true) => Some(Outcome::Draw),
+                        (false, true) => Some(Outcome::Win(Color::Black)),
+                        // black moves second, so gets one more move to draw by getting there too
+                        (true, false) if self.turn == Color::Black && self.black_king_can_finish() => None,
+                        (true, false) => Some(Outcome::Win(Color::White)),
+                        (false, false) => None,
+                    }
+                }
+                Variant::Antichess => {
+                    // giving away everything, or being left with no moves, wins
+                    let stuck = self.pieces()
+                        .filter(|(_, piece)| *piece.color() == self.turn)
+                        .all(|(from, _)| self.moves_from(from).is_empty());
+                    if stuck { Some(Outcome::Win(self.turn)) } else { None }
+                }
+                Variant::Horde => {
+                    if self.pieces().any(|(_, piece)| *piece.color() == Color::White) { None } else { Some(Outcome::Win(Color::Black)) }
+                }
+                Variant::Standard | Variant::Crazyhouse => None,
+            }
+        }
+
+        fn black_king_can_finish(&self) -> bool {
+            self.find_king(Color::Black)
+                .is_some_and(|king| self.legal_moves_from(king).iter().any(|m| m.to.y == self.height - 1))
+        }
+
+        /// whether any piece of the given color could take on the target square
+        pub fn is_attacked(&self, target: Location, by: Color) -> bool {
+            self.pieces().any(|(from, piece)| *piece.color() == by && from != target && self.attacks(piece, from, target))
+        }
+
+        fn attacks(&self, piece: Piece, from: Location, target: Location) -> bool {
+            let m = Move::new(from, target);
+            match piece {
+                Piece::Pawn(c) => Pawn::is_attacking_validly(m, &c),
+                Piece::King(_) if self.variant == Variant::Atomic => false,
+                Piece::King(_) => {
+                    let (x, y) = (target - from).as_abs_tup();
+                    x <= 1 && y <= 1
+                }
+                Piece::Fairy(fairy, _) => fairy.reach(from, self.width, self.height, |square| square != target && self.get_piece_from(&square).is_some())
+                    .iter()
+                    .any(|(square, _, captures)| *square == target && *captures),
+                _ => match piece.squares_moved_over(m) {
+                    Ok(squares) => squares.iter().all(|square| {
+                        !Board::square_is_in_middle_of_path(*square, m) || self.get_piece_from(square).is_none()
+                    }),
+                    Err(_) => false
+                }
+            }
+        }
+
+        pub fn is_checkmate(&self) -> bool {
+            self.is_in_check(&self.turn) && self.legal_moves().is_empty()
+        }
+
+        pub fn is_stalemate(&self) -> bool {
+            !self.is_in_check(&self.turn) && self.variant_outcome().is_none() && self.legal_moves().is_empty()
+        }
+
+        /// whether the color could never checkmate, however badly the other side played. Only
+        /// standard chess is judged, in the variants anything might still happen.
+        pub fn has_insufficient_material(&self, color: Color) -> bool {
+            if self.variant != Variant::Standard {
                 return false;
+            }
+            // helpers are pieces of theirs that could hem their own king in for a mate
+            let (mut ours, mut knights, mut bishop_colours, mut helpers) = (0, 0, Vec::new(), false);
+            for (square, piece) in self.pieces() {
+                let mine = *piece.color() == color;
+                match piece {
+                    Piece::King(_) => continue,
+                    Piece::Queen(_) if !mine => {}
+                    Piece::Knight(_) if mine => knights += 1,
+                    Piece::Bishop(_) => bishop_colours.push((square.x + square.y) % 2),
+                    Piece::Knight(_) | Piece::Pawn(_) | Piece::Rook(_) if !mine => helpers = true,
+                    _ => return false,
+                }
+                if mine {
+                    ours += 1;
+                }
+            }
+            let bishops_alike = bishop_colours.windows(2).all(|pair| pair[0] == pair[1]);
+            if knights > 0 {
+                ours == 1 && !helpers && bishop_colours.is_empty()
             } else {
-                false // for testing
+                !helpers && bishops_alike
+            }
+        }
+
+        /// whether the move takes a piece, including taking en passant
+        pub fn is_capture(&self, m: &Move) -> bool {
+            match self.get_piece_from(&m.from) {
+                _ if self.get_piece_from(&m.to).is_some() => true,
+                Some(Piece::Pawn(_)) => m.from.x != m.to.x,
+                _ => false
             }
         }
 
+        /// every legal move for the side to move
+        pub fn legal_moves(&self) -> Vec<Move> {
+            if self.variant_outcome().is_some() {
+                return Vec::new();
+            }
+            let mut moves: Vec<Move> = self.pieces()
+                .filter(|(_, piece)| *piece.color() == self.turn)
+                .flat_map(|(from, _)| self.moves_from(from))
+                .collect();
+            moves.extend(self.legal_drops());
+            if self.variant == Variant::Antichess && moves.iter().any(|m| self.is_capture(m)) {
+                moves.retain(|m| self.is_capture(m));
+            }
+            moves
+        }
+
+        /// whether the side to move can take anything, which in antichess it then has to
+        fn has_capture(&self) -> bool {
+            self.pieces()
+                .filter(|(_, piece)| *piece.color() == self.turn)
+                .any(|(from, _)| self.moves_from(from).iter().any(|m| self.is_capture(m)))
+        }
+
+        /// every legal drop for the side to move, in crazyhouse
+        pub fn legal_drops(&self) -> Vec<Move> {
+            let mut kinds: Vec<Piece> = Vec::new();
+            for piece in self.pocket(self.turn) {
+                if !kinds.contains(&piece) {
+                    kinds.push(piece);
+                }
+            }
+            let empty: Vec<Location> = (0..self.width).flat_map(|x| (0..self.height).map(move |y| Location::new(x, y)))
+                .filter(|square| self.get_piece_from(square).is_none())
+                .collect();
+            kinds.iter()
+                .flat_map(|piece| empty.iter().map(move |square| Move::new_drop(*piece, *square)))
+                .filter(|m| self.is_valid_move(*m).is_ok() && !self.leaves_king_in_check(*m))
+                .collect()
+        }
+
+        /// every legal move for the piece on the given square, regardless of whose turn it is
+        pub fn legal_moves_from(&self, from: Location) -> Vec<Move> {
+            let mut moves = self.moves_from(from);
+            let to_move = self.get_piece_from(&from).is_some_and(|piece| *piece.color() == self.turn);
+            if self.variant == Variant::Antichess && to_move && self.has_capture() {
+                moves.retain(|m| self.is_capture(m));
+            }
+            moves
+        }
+
+        /// legal moves for the piece on the square leaving out the variant rules that depend on
+        /// the rest of the position, like having to take in antichess
+        fn moves_from(&self, from: Location) -> Vec<Move> {
+            let piece = match self.get_piece_from(&from) {
+                Some(piece) => piece,
+                None => return Vec::new()
+            };
+            self.candidate_destinations(from, piece).into_iter()
+                .flat_map(|to| self.with_promotions(piece, from, to))
+                .filter(|m| self.is_valid_move(*m).is_ok() && !self.leaves_king_in_check(*m))
+                .collect()
+        }
+
+        /// squares a piece could plausibly reach, to be checked with is_valid_move
+        fn candidate_destinations(&self, from: Location, piece: Piece) -> Vec<Location> {
+            let leaps = |steps: &[(i32, i32)]| -> Vec<Location> {
+                steps.iter().map(|(x, y)| from + Location::new(*x, *y)).filter(|to| self.contains(to)).collect()
+            };
+            match piece {
+                Piece::Knight(_) => leaps(&KNIGHT_STEPS),
+                Piece::King(c) => {
+                    let mut squares = leaps(&KING_STEPS);
+                    if self.chess960 {
+                        let rooks = self.castling_rights.iter().filter(|rook| rook.y == self.back_rank(&c));
+                        squares.extend(rooks.filter(|rook| !squares.contains(rook)).cloned().collect::<Vec<_>>());
+                    } else if from == Location::new(self.width / 2, self.back_rank(&c)) {
+                        // on boards wider than 8 the king moves further than two squares to castle
+                        for to in leaps(&[(2 - from.x, 0), (self.width - 2 - from.x, 0)]) {
+                            if !squares.contains(&to) {
+                                squares.push(to);
+                            }
+                        }
+                    }
+                    squares
+                }
+                Piece::Pawn(c) => {
+                    let forward = match c {
+                        Color::White => 1,
+                        Color::Black => -1,
+                    };
+                    leaps(&[(0, forward), (0, 2 * forward), (1, forward), (-1, forward)])
+                }
+                Piece::Rook(_) => self.slides(from, &ORTHOGONAL),
+                Piece::Bishop(_) => self.slides(from, &DIAGONAL),
+                Piece::Queen(_) => {
+                    let mut squares = self.slides(from, &ORTHOGONAL);
+                    squares.extend(self.slides(from, &DIAGONAL));
+                    squares
+                }
+                Piece::Fairy(fairy, _) => {
+                    let mut squares = Vec::new();
+                    for (square, _, _) in fairy.reach(from, self.width, self.height, |square| self.get_piece_from(&square).is_some()) {
+                        if !squares.contains(&square) {
+                            squares.push(square);
+                        }
+                    }
+                    squares
+                }
+            }
+        }
+
+        fn slides(&self, from: Location, directions: &[(i32, i32)]) -> Vec<Location> {
+            let mut squares = Vec::new();
+            for (x, y) in directions {
+                let mut to = from + Location::new(*x, *y);
+                while self.contains(&to) {
+                    squares.push(to);
+                    if self.get_piece_from(&to).is_some() {
+                        break;
+                    }
+                    to = to + Location::new(*x, *y);
+                }
+            }
+            squares
+        }
+
+        fn with_promotions(&self, piece: Piece, from: Location, to: Location) -> Vec<Move> {
+            match piece {
+                Piece::Pawn(c) if self.is_promotion(Move::new(from, to)) => {
+                    let mut promotions = vec!(Piece::Queen(c), Piece::Rook(c), Piece::Bishop(c), Piece::Knight(c));
+                    if self.variant == Variant::Antichess {
+                        promotions.push(Piece::King(c));
+                    }
+                    promotions.into_iter()
+                        .map(|promoted| Move::new_with_piece(from, to, promoted))
+                        .collect()
+                }
+                _ => vec!(Move::new(from, to))
+            }
+        }
+
+        fn leaves_king_in_check(&self, m: Move) -> bool {
+            let color = *self.moving_piece(&m).expect("checked by is_valid_move").color();
+            let mut after = Board {
+                squares: self.squares,
+                width: self.width,
+                height: self.height,
+                past_moves: LinkedList::new(),
+                history: LinkedList::new(),
+                turn: self.turn,
+                castling_rights: Vec::new(),
+                en_passant: None,
+                halfmove_clock: 0,
+                fullmove_number: 1,
+                chess960: self.chess960,
+                variant: self.variant,
+                pockets: self.pockets.clone(),
+                promoted: self.promoted.clone(),
+                checks_given: self.checks_given,
+            };
+            after.do_move(m);
+            after.is_exposed(color)
+        }
+
         fn find_king(&self, color: Color) -> Option<Location> {
-            for x in 0..8 {
-                for y in 0..8 {
+            for x in 0..self.width {
+                for y in 0..self.height {
                     if let Some(Piece::King(king_color)) = self.get_piece_from(&Location::new(x, y)) {
                         if color == king_color {
                             return Some(Location { x, y });
@@ -243,4 +1410,4 @@ pub mod board {
             None
         }
     }
-}
\ No newline at end of file
+}
@@ -1,12 +1,16 @@
-pub(crate) mod chess_move {
+pub mod chess_move {
     use crate::location::location::Location;
-    use crate::chess::Piece;
+    use crate::chess::{Piece, Color, FailReason};
+    use std::fmt::{Display, Formatter};
 
-    #[derive(Copy, Clone, Debug)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Move {
         pub from: Location,
         pub to: Location,
         pub promoted: Option<Piece>,
+        /// the piece put on the board from a pocket in crazyhouse, from and to are then both the square dropped on
+        pub dropped: Option<Piece>,
     }
 
 
@@ -16,6 +20,7 @@ pub(crate) mod chess_move {
                 from,
                 to,
                 promoted: None,
+                dropped: None,
             }
         }
 
@@ -24,6 +29,7 @@ pub(crate) mod chess_move {
                 from,
                 to,
                 promoted: piece,
+                dropped: None,
             }
         }
 
@@ -32,7 +38,134 @@ pub(crate) mod chess_move {
                 from,
                 to,
                 promoted: Some(piece),
+                dropped: None,
             }
         }
+
+        pub fn new_drop(piece: Piece, to: Location) -> Self {
+            Move {
+                from: to,
+                to,
+                promoted: None,
+                dropped: Some(piece),
+            }
+        }
+
+        pub fn is_drop(&self) -> bool {
+            self.dropped.is_some()
+        }
+
+        /// parses a move in the coordinate notation used by UCI, e.g. "e2e4", "e7e8q" or the drop
+        /// "N@f3". The piece letter is given the color passed in as it is not part of the notation.
+        pub fn from_coordinate_notation(s: &str, color: Color) -> Result<Self, FailReason> {
+            let invalid = || FailReason::ParseError(format!("\"{}\" is not a move, expected something like e2e4", s));
+            if !s.is_ascii() {
+                return Err(invalid());
+            }
+            if s.len() > 2 && &s[1..2] == "@" {
+                let to = Location::from_algebraic(&s[2..])?;
+                return match s.chars().next().and_then(Piece::from_char) {
+                    Some(Piece::King(_)) | None => Err(FailReason::ParseError(format!("\"{}\" is not something that can be dropped", &s[0..1]))),
+                    Some(piece) => Ok(Move::new_drop(piece.with_color(color), to)),
+                };
+            }
+            // squares can have two digit ranks on bigger boards, as in a10a9
+            let (from, rest) = Location::split_algebraic(s).ok_or_else(invalid)?;
+            let (to, rest) = Location::split_algebraic(rest).ok_or_else(invalid)?;
+            let mut rest = rest.chars();
+            let promoted = match (rest.next(), rest.next()) {
+                (None, _) => None,
+                // kings are allowed through here for antichess, the board decides whether they're legal
+                (Some(c), None) => match Piece::from_char(c.to_ascii_lowercase()) {
+                    Some(Piece::Pawn(_)) | None => return Err(FailReason::ParseError(format!("\"{}\" is not something a pawn can promote to", c))),
+                    Some(piece) => Some(piece.with_color(color)),
+                },
+                _ => return Err(invalid()),
+            };
+            Ok(Move::new_with_opt_piece(from, to, promoted))
+        }
+
+        /// the move in 16 bits for storing games: from and to as 6 bit square numbers on an 8x8
+        /// board, then 4 bits for the piece promoted to or dropped, 1 to 6 for a knight, bishop,
+        /// rook, queen, pawn and king. A drop has from and to the same, which no other move does.
+        pub fn to_u16(&self) -> Result<u16, FailReason> {
+            let square = |location: Location| -> Result<u16, FailReason> {
+                if location.is_in_bounds() {
+                    Ok((location.x + 8 * location.y) as u16)
+                } else {
+                    Err(FailReason::OutOfBounds(format!("only moves on an 8x8 board fit in 16 bits, not one to or from {:?}", location)))
+                }
+            };
+            let piece = match self.promoted.or(self.dropped) {
+                None => 0,
+                Some(Piece::Knight(_)) => 1,
+                Some(Piece::Bishop(_)) => 2,
+                Some(Piece::Rook(_)) => 3,
+                Some(Piece::Queen(_)) => 4,
+                Some(Piece::Pawn(_)) => 5,
+                Some(Piece::King(_)) => 6,
+                Some(Piece::Fairy(..)) => return Err(FailReason::ImpossibleMove(String::from("fairy pieces do not fit in 16 bits"))),
+            };
+            Ok(square(self.from)? | square(self.to)? << 6 | piece << 12)
+        }
+
+        /// reads a move written by to_u16, giving any piece in it the color passed in
+        pub fn from_u16(bits: u16, color: Color) -> Result<Move, FailReason> {
+            let square = |bits: u16| Location::new((bits & 7) as i32, ((bits >> 3) & 7) as i32);
+            let (from, to) = (square(bits), square(bits >> 6));
+            let piece = match bits >> 12 {
+                0 => None,
+                1 => Some(Piece::Knight(color)),
+                2 => Some(Piece::Bishop(color)),
+                3 => Some(Piece::Rook(color)),
+                4 => Some(Piece::Queen(color)),
+                5 => Some(Piece::Pawn(color)),
+                6 => Some(Piece::King(color)),
+                _ => None,
+            };
+            match (piece, from == to) {
+                (Some(Piece::King(_)), true) | (None, true) => Err(FailReason::ParseError(format!("{:#06x} is not a move", bits))),
+                (Some(Piece::Pawn(_)), false) => Err(FailReason::ParseError(format!("{:#06x} promotes to a pawn", bits))),
+                (None, false) if bits >> 12 != 0 => Err(FailReason::ParseError(format!("{:#06x} is not a move", bits))),
+                (Some(piece), true) => Ok(Move::new_drop(piece, to)),
+                (piece, false) => Ok(Move::new_with_opt_piece(from, to, piece)),
+            }
+        }
+    }
+
+    /// the moves at two bytes each, so a game of 80 plies takes 160
+    pub fn encode_moves(moves: &[Move]) -> Result<Vec<u8>, FailReason> {
+        let mut bytes = Vec::with_capacity(moves.len() * 2);
+        for m in moves {
+            bytes.extend_from_slice(&m.to_u16()?.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// reads moves written by encode_moves, the first being played by the given color and the
+    /// colors taking turns from there
+    pub fn decode_moves(bytes: &[u8], first: Color) -> Result<Vec<Move>, FailReason> {
+        if !bytes.len().is_multiple_of(2) {
+            return Err(FailReason::ParseError(String::from("moves take two bytes each, there is one left over")));
+        }
+        let mut color = first;
+        bytes.chunks(2).map(|pair| {
+            let m = Move::from_u16(u16::from_le_bytes([pair[0], pair[1]]), color);
+            color = color.opposite();
+            m
+        }).collect()
+    }
+
+    impl Display for Move {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            if let Some(dropped) = self.dropped {
+                return write!(f, "{}@{}", dropped.to_char().to_ascii_uppercase(), self.to);
+            }
+            write!(f, "{}{}", self.from, self.to)?;
+            if let Some(promoted) = self.promoted {
+                write!(f, "{}", promoted.to_char().to_ascii_lowercase())?;
+            }
+            Ok(())
+        }
     }
 }
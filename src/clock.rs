@@ -0,0 +1,310 @@
+pub mod clock {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use crate::board::board::Board;
+    use crate::chess::{Color, FailReason};
+    use crate::chess_move::chess_move::Move;
+    use crate::syzygy::syzygy::Tablebase;
+    use crate::time_manager::time_manager::Clock;
+    use crate::variant::variant::Outcome;
+
+    /// how a clock holds back before counting down each move
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum Delay {
+        None,
+        /// the clock only starts counting down once this much of the move has gone by
+        Simple(Duration),
+        /// the clock counts down from the start, and up to this much of what was used is given
+        /// back once the move is made
+        Bronstein(Duration),
+    }
+
+    /// one period of a time control, so many moves in so much time
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Stage {
+        /// the moves to be made in this stage, None for the rest of the game
+        pub moves: Option<u32>,
+        /// added to the clock as the stage begins
+        pub time: Duration,
+        /// added to the clock after every move made in this stage
+        pub increment: Duration,
+        pub delay: Delay,
+    }
+
+    /// the stages a game is played under. A last stage with a number of moves is played over and
+    /// over, as in 40 moves in 2 hours for the whole game.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Control {
+        stages: Vec<Stage>,
+    }
+
+    impl Control {
+        pub fn new(first: Stage) -> Self {
+            Control { stages: vec!(first) }
+        }
+
+        /// adds a stage to be played once those before it are done, e.g. 40/90+30 is 90 minutes
+        /// for 40 moves then 30 for the rest, which is `Control::new(forty).then(rest)`
+        pub fn then(mut self, stage: Stage) -> Self {
+            self.stages.push(stage);
+            self
+        }
+
+        pub fn sudden_death(time: Duration) -> Self {
+            Control::new(Stage { moves: None, time, increment: Duration::from_secs(0), delay: Delay::None })
+        }
+
+        pub fn fischer(time: Duration, increment: Duration) -> Self {
+            Control::new(Stage { moves: None, time, increment, delay: Delay::None })
+        }
+
+        pub fn simple_delay(time: Duration, delay: Duration) -> Self {
+            Control::new(Stage { moves: None, time, increment: Duration::from_secs(0), delay: Delay::Simple(delay) })
+        }
+
+        pub fn bronstein(time: Duration, delay: Duration) -> Self {
+            Control::new(Stage { moves: None, time, increment: Duration::from_secs(0), delay: Delay::Bronstein(delay) })
+        }
+
+        pub fn stages(&self) -> &[Stage] {
+            &self.stages
+        }
+
+        /// the stage the move after the given number of moves is made in, and how many moves
+        /// including that one are left before the next stage
+        fn stage_after(&self, moves: u32) -> (&Stage, Option<u32>) {
+            let mut moves = moves;
+            let last = self.stages.len() - 1;
+            for (i, stage) in self.stages.iter().enumerate() {
+                match stage.moves {
+                    Some(n) if i < last && moves >= n => moves -= n,
+                    Some(n) => return (stage, Some(n.max(1) - moves % n.max(1))),
+                    None => return (stage, None),
+                }
+            }
+            (&self.stages[last], None)
+        }
+    }
+
+    /// a two sided chess clock, reading the time from a Clock so tests can move it along by hand
+    pub struct ChessClock {
+        control: Control,
+        source: Arc<dyn Clock>,
+        remaining: [Duration; 2],
+        moves: [u32; 2],
+        /// whose time is going down and when their move started
+        running: Option<(Color, Duration)>,
+    }
+
+    impl ChessClock {
+        /// both sides get the time of the first stage, and neither clock is running
+        pub fn new(control: Control, source: Arc<dyn Clock>) -> Self {
+            let time = control.stages[0].time;
+            ChessClock { control, source, remaining: [time, time], moves: [0, 0], running: None }
+        }
+
+        pub fn control(&self) -> &Control {
+            &self.control
+        }
+
+        /// sets the color's time going, stopping the other's without counting it as a move
+        pub fn start(&mut self, color: Color) {
+            self.stop();
+            self.running = Some((color, self.source.now()));
+        }
+
+        /// stops whichever clock is running, charging it for the time used
+        pub fn stop(&mut self) {
+            if let Some((color, _)) = self.running {
+                self.remaining[color as usize] = self.remaining(color);
+                self.running = None;
+            }
+        }
+
+        pub fn running(&self) -> Option<Color> {
+            self.running.map(|(color, _)| color)
+        }
+
+        /// the time the color has left right now, counting the move in progress
+        pub fn remaining(&self, color: Color) -> Duration {
+            let left = self.remaining[color as usize];
+            match self.running {
+                Some((running, started)) if running == color => {
+                    let used = self.source.now() - started;
+                    let used = match self.control.stage_after(self.moves[color as usize]).0.delay {
+                        Delay::Simple(delay) => used.checked_sub(delay).unwrap_or_default(),
+                        _ => used,
+                    };
+                    left.checked_sub(used).unwrap_or_default()
+                }
+                _ => left,
+            }
+        }
+
+        /// whether the color's time has run out
+        pub fn is_flagged(&self, color: Color) -> bool {
+            self.remaining(color) == Duration::from_secs(0)
+        }
+
+        /// how many moves the color has made on the clock
+        pub fn moves_made(&self, color: Color) -> u32 {
+            self.moves[color as usize]
+        }
+
+        /// how many moves the color has to make before the next stage's time is added, None in
+        /// the last stage of the game
+        pub fn moves_to_go(&self, color: Color) -> Option<u32> {
+            self.control.stage_after(self.moves[color as usize]).1
+        }
+
+        /// the increment the color gets for its next move
+        pub fn increment(&self, color: Color) -> Duration {
+            self.control.stage_after(self.moves[color as usize]).0.increment
+        }
+
+        /// ends the move of the side whose clock is running and starts the other side's, as
+        /// pressing the button on a real clock. Returns false, leaving the clocks stopped, if the
+        /// time had already run out.
+        pub fn press(&mut self) -> bool {
+            let (color, started) = match self.running {
+                Some(running) => running,
+                None => return false,
+            };
+            let used = self.source.now() - started;
+            let index = color as usize;
+            self.stop();
+            if self.remaining[index] == Duration::from_secs(0) {
+                return false;
+            }
+            let (&stage, to_go) = self.control.stage_after(self.moves[index]);
+            if let Delay::Bronstein(delay) = stage.delay {
+                self.remaining[index] += used.min(delay);
+            }
+            self.remaining[index] += stage.increment;
+            self.moves[index] += 1;
+            if to_go == Some(1) {
+                self.remaining[index] += self.control.stage_after(self.moves[index]).0.time;
+            }
+            self.running = Some((color.opposite(), self.source.now()));
+            true
+        }
+    }
+
+    /// a game on a clock. Moves are only taken while the side to move has time left, and the
+    /// game ends when someone flags, is mated or cannot move.
+    pub struct TimedGame {
+        board: Board,
+        clock: ChessClock,
+        outcome: Option<Outcome>,
+        flagged: Option<Color>,
+    }
+
+    impl TimedGame {
+        /// starts the clock of the side to move straight away
+        pub fn new(board: Board, control: Control, source: Arc<dyn Clock>) -> Self {
+            let mut clock = ChessClock::new(control, source);
+            clock.start(board.turn());
+            let mut game = TimedGame { board, clock, outcome: None, flagged: None };
+            game.check_board();
+            game
+        }
+
+        pub fn board(&self) -> &Board {
+            &self.board
+        }
+
+        pub fn clock(&self) -> &ChessClock {
+            &self.clock
+        }
+
+        /// the side that ran out of time, if that is how the game ended
+        pub fn flagged(&self) -> Option<Color> {
+            self.flagged
+        }
+
+        /// how the game ended, if it has, looking at the clock first
+        pub fn outcome(&mut self) -> Option<Outcome> {
+            self.check_time();
+            self.outcome
+        }
+
+        /// plays the move for the side to move and hands the clock over, unless they are out of time
+        pub fn make_move(&mut self, m: Move) -> Result<(), FailReason> {
+            if self.outcome().is_some() {
+                return Err(FailReason::ImpossibleMove(String::from(match self.flagged {
+                    Some(Color::White) => "white has run out of time",
+                    Some(Color::Black) => "black has run out of time",
+                    None => "the game is over",
+                })));
+            }
+            self.board.make_move(m)?;
+            if !self.clock.press() {
+                // the time ran out between looking and pressing, which still counts
+                self.board.undo_last_move();
+                self.check_time();
+                return Err(FailReason::ImpossibleMove(String::from("out of time")));
+            }
+            self.check_board();
+            Ok(())
+        }
+
+        /// takes the last move back and hands the clock back to the side that made it, without
+        /// giving back the time they used. A game that ended on the board goes on again, one lost
+        /// on time stays lost.
+        pub fn take_back(&mut self) -> bool {
+            self.check_time();
+            if self.flagged.is_some() || self.board.last_move().is_none() {
+                return false;
+            }
+            self.board.undo_last_move();
+            self.outcome = None;
+            self.clock.start(self.board.turn());
+            true
+        }
+
+        /// ends the game with the result the tablebase has for the position, if it has one,
+        /// stopping the clock
+        pub fn adjudicate(&mut self, tablebase: &Tablebase) -> Option<Outcome> {
+            if let Some(outcome) = self.outcome() {
+                return Some(outcome);
+            }
+            let outcome = tablebase.outcome(&self.board)?;
+            self.clock.stop();
+            self.outcome = Some(outcome);
+            Some(outcome)
+        }
+
+        /// a flag is a loss, unless the other side could never have mated in which case it is a draw
+        fn check_time(&mut self) {
+            if self.outcome.is_some() {
+                return;
+            }
+            let turn = self.board.turn();
+            if self.clock.is_flagged(turn) {
+                self.clock.stop();
+                self.flagged = Some(turn);
+                self.outcome = Some(if self.board.has_insufficient_material(turn.opposite()) {
+                    Outcome::Draw
+                } else {
+                    Outcome::Win(turn.opposite())
+                });
+            }
+        }
+
+        fn check_board(&mut self) {
+            let outcome = if self.board.is_checkmate() {
+                Some(Outcome::Win(self.board.turn().opposite()))
+            } else if let Some(outcome) = self.board.variant_outcome() {
+                Some(outcome)
+            } else if self.board.is_stalemate() {
+                Some(Outcome::Draw)
+            } else {
+                None
+            };
+            if outcome.is_some() {
+                self.clock.stop();
+                self.outcome = outcome;
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+pub mod eco {
+    use std::collections::HashMap;
+    use std::fmt::{Display, Formatter};
+    use std::sync::OnceLock;
+    use crate::board::board::Board;
+    use crate::chess::FailReason;
+    use crate::chess_move::chess_move::Move;
+    use crate::game::game::Game;
+    use crate::pgn::pgn::parse_san;
+    use crate::polyglot::polyglot::polyglot_key;
+    use crate::variant::variant::Variant;
+
+    /// the openings we know, one a line as the ECO code, the name and the moves in algebraic
+    /// notation, all separated by tabs. Every code from A00 to E99 has at least one line, the
+    /// main line the ECO gives for it, and the most played codes have a few named lines more.
+    const TABLE: &str = include_str!("eco.tsv");
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Opening {
+        /// the Encyclopaedia of Chess Openings code, as in C65
+        pub eco: String,
+        pub name: String,
+        /// the moves of the main line that leads to it, in algebraic notation
+        pub moves: String,
+    }
+
+    impl Display for Opening {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} {}", self.eco, self.name)
+        }
+    }
+
+    /// names the opening of a game by the position it reaches rather than the order the moves
+    /// came in, so a transposition into a known line is still found
+    pub struct Openings {
+        openings: Vec<Opening>,
+        by_position: HashMap<u64, usize>,
+    }
+
+    impl Openings {
+        /// the table built into the crate, with all 500 ECO codes. It is read the first time it
+        /// is asked for. A line that does not read is left out rather than stopping the program;
+        /// the tests check there are none.
+        pub fn built_in() -> &'static Openings {
+            static BUILT_IN: OnceLock<Openings> = OnceLock::new();
+            BUILT_IN.get_or_init(|| Openings::from_lines(lines(TABLE).filter_map(|line| read_line(line).ok())))
+        }
+
+        /// a table in the same form as the built in one, failing on the first line that does not read
+        pub fn parse(table: &str) -> Result<Openings, FailReason> {
+            let lines: Vec<(Opening, u64)> = lines(table).map(read_line).collect::<Result<_, _>>()?;
+            Ok(Openings::from_lines(lines))
+        }
+
+        fn from_lines<I: IntoIterator<Item = (Opening, u64)>>(lines: I) -> Openings {
+            let mut openings = Vec::new();
+            let mut by_position = HashMap::new();
+            for (opening, key) in lines {
+                // where two lines reach the same position the first one is kept
+                by_position.entry(key).or_insert(openings.len());
+                openings.push(opening);
+            }
+            Openings { openings, by_position }
+        }
+
+        pub fn all(&self) -> &[Opening] {
+            &self.openings
+        }
+
+        /// the opening of exactly this position, ignoring how it came about
+        pub fn at(&self, board: &Board) -> Option<&Opening> {
+            if board.variant() != Variant::Standard || board.width() != 8 || board.height() != 8 {
+                return None;
+            }
+            self.by_position.get(&polyglot_key(board)).map(|i| &self.openings[*i])
+        }
+
+        /// the opening of the game the board is part of, the last position in its history that
+        /// is a known opening so it is still named once the game has left the table
+        pub fn classify(&self, board: &Board) -> Option<&Opening> {
+            let mut board = board.clone();
+            loop {
+                if let Some(opening) = self.at(&board) {
+                    return Some(opening);
+                }
+                board.last_move()?;
+                board.undo_last_move();
+            }
+        }
+
+        /// the opening of the moves played from the start, the last known one they pass through
+        pub fn classify_moves(&self, start: &Board, moves: &[Move]) -> Option<&Opening> {
+            let mut board = start.clone();
+            let mut found = self.at(&board);
+            for m in moves {
+                if board.make_move(*m).is_err() {
+                    break;
+                }
+                found = self.at(&board).or(found);
+            }
+            found
+        }
+
+        /// sets the ECO and Opening headers from the game's main line, returning false and
+        /// leaving them alone if it is not a known opening
+        pub fn tag_game(&self, game: &mut Game) -> bool {
+            let (eco, name) = match self.classify_moves(game.start(), &game.mainline()) {
+                Some(opening) => (opening.eco.clone(), opening.name.clone()),
+                None => return false,
+            };
+            game.set_header("ECO", &eco);
+            game.set_header("Opening", &name);
+            true
+        }
+    }
+
+    /// the lines of a table that are not blank or comments
+    fn lines(table: &str) -> impl Iterator<Item = &str> {
+        table.lines().filter(|line| !line.is_empty() && !line.starts_with('#'))
+    }
+
+    /// an opening and the key of the position its moves reach
+    fn read_line(line: &str) -> Result<(Opening, u64), FailReason> {
+        let mut fields = line.split('\t');
+        let (eco, name, moves) = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(eco), Some(name), Some(moves), None) => (eco, name, moves),
+            _ => return Err(FailReason::ParseError(format!("\"{}\" is not an ECO code, name and moves", line))),
+        };
+        let code = eco.as_bytes();
+        if code.len() != 3 || !(b'A'..=b'E').contains(&code[0]) || !code[1..].iter().all(u8::is_ascii_digit) {
+            return Err(FailReason::ParseError(format!("\"{}\" is not an ECO code", eco)));
+        }
+        let mut board = Board::starting_position();
+        for san in moves.split_whitespace() {
+            let m = parse_san(&board, san).map_err(|reason| FailReason::ParseError(format!("{} in the {}: {:?}", san, name, reason)))?;
+            board.make_move(m)?;
+        }
+        Ok((Opening { eco: eco.to_string(), name: name.to_string(), moves: moves.to_string() }, polyglot_key(&board)))
+    }
+}
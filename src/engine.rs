@@ -0,0 +1,486 @@
+pub mod engine {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+    use crate::board::board::Board;
+    use crate::chess::{Piece, Color, Pawn};
+    use crate::chess_move::chess_move::Move;
+    use crate::location::location::Location;
+    use crate::time_manager::time_manager::{Clock, SystemClock, TimeControl, TimeManager};
+    use crate::syzygy::syzygy::{Tablebase, Wdl};
+    use crate::transposition::transposition::{TranspositionTable, TableEntry, Bound, DEFAULT_HASH_MB};
+    use crate::variant::variant::{Variant, Outcome};
+    use std::thread;
+
+    pub const MATE: i32 = 100_000;
+    const INFINITY: i32 = 1_000_000;
+    const MAX_DEPTH: u32 = 64;
+    /// a tablebase win, less than any mate the search can find so one is still preferred
+    const TABLEBASE_WIN: i32 = MATE - 2 * MAX_DEPTH as i32;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum Score {
+        Centipawns(i32),
+        /// mate in this many moves, negative when the side to move is getting mated
+        Mate(i32),
+    }
+
+    impl Score {
+        pub fn from_value(value: i32) -> Score {
+            if value.abs() >= MATE - MAX_DEPTH as i32 {
+                let plies = MATE - value.abs();
+                Score::Mate(value.signum() * (plies + 1) / 2)
+            } else {
+                Score::Centipawns(value)
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct SearchLimits {
+        pub depth: Option<u32>,
+        pub movetime: Option<Duration>,
+        pub nodes: Option<u64>,
+        pub infinite: bool,
+        /// play under a clock, the time manager works out how long to spend
+        pub time_control: Option<TimeControl>,
+        /// how many of the best moves to find lines for, 0 is the same as 1
+        pub multi_pv: usize,
+        /// endgame tables to look positions up in once a capture or pawn move brings them in range
+        pub tablebase: Option<Arc<Tablebase>>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SearchInfo {
+        pub depth: u32,
+        /// which of the best lines this is, starting at 1
+        pub multipv: usize,
+        pub score: Score,
+        pub nodes: u64,
+        pub time: Duration,
+        pub pv: Vec<Move>,
+    }
+
+    /// one of the candidate moves found by a search, best first
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct AnalysisLine {
+        pub m: Move,
+        pub score: Score,
+        pub pv: Vec<Move>,
+    }
+
+    pub struct Searcher {
+        stop: Arc<AtomicBool>,
+        clock: Arc<dyn Clock>,
+        table: Arc<TranspositionTable>,
+        /// helper threads start deeper than the main thread so they aren't all doing the same work
+        first_depth: u32,
+        limits: SearchLimits,
+        time_manager: Option<TimeManager>,
+        started: Duration,
+        nodes: u64,
+        aborted: bool,
+    }
+
+    impl Searcher {
+        /// the search gives up as soon as it can once stop is set
+        pub fn new(stop: Arc<AtomicBool>) -> Self {
+            Searcher::with_clock(stop, Arc::new(SystemClock::new()))
+        }
+
+        pub fn with_clock(stop: Arc<AtomicBool>, clock: Arc<dyn Clock>) -> Self {
+            Searcher::with_table(stop, clock, Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)))
+        }
+
+        /// a searcher sharing its transposition table, which is how threads searching the same
+        /// position help each other out
+        pub fn with_table(stop: Arc<AtomicBool>, clock: Arc<dyn Clock>, table: Arc<TranspositionTable>) -> Self {
+            Searcher {
+                stop,
+                started: clock.now(),
+                clock,
+                table,
+                first_depth: 1,
+                limits: SearchLimits::default(),
+                time_manager: None,
+                nodes: 0,
+                aborted: false,
+            }
+        }
+
+        fn elapsed(&self) -> Duration {
+            self.clock.now() - self.started
+        }
+
+        /// iterative deepening search, reporting each finished depth to on_info. Returns None
+        /// only when there are no legal moves.
+        pub fn search<F: FnMut(&SearchInfo)>(&mut self, board: &Board, limits: SearchLimits, on_info: F) -> Option<Move> {
+            self.analyse(board, limits, on_info).first().map(|line| line.m)
+        }
+
+        /// like search, but finds a line for each of the limits.multi_pv best moves, reporting every
+        /// line of each finished depth to on_info and returning them best first. Lines from an
+        /// unfinished depth are thrown away.
+        pub fn analyse<F: FnMut(&SearchInfo)>(&mut self, board: &Board, limits: SearchLimits, mut on_info: F) -> Vec<AnalysisLine> {
+            let mut board = board.clone();
+            let first = match board.legal_moves().first() {
+                Some(m) => *m,
+                None => return Vec::new()
+            };
+            self.time_manager = limits.time_control.as_ref().map(|control| TimeManager::new(control, Arc::clone(&self.clock)));
+            self.limits = limits;
+            self.started = self.clock.now();
+            self.nodes = 0;
+            self.aborted = false;
+
+            let mut lines = vec!(AnalysisLine { m: first, score: Score::Centipawns(0), pv: vec!(first) });
+            for depth in self.first_depth..=self.limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH) {
+                let mut found: Vec<(i32, Vec<Move>)> = Vec::new();
+                while found.len() < self.limits.multi_pv.max(1) {
+                    let excluded: Vec<Move> = found.iter().map(|(_, pv)| pv[0]).collect();
+                    let previous = lines.get(found.len()).map_or(&[][..], |line| &line.pv[..]);
+                    match self.search_root(&mut board, depth, &excluded, previous) {
+                        Some(line) => found.push(line),
+                        None => break,
+                    }
+                }
+                if self.aborted {
+                    break;
+                }
+                // a later line can come out better than an earlier one when the earlier search was cut short
+                found.sort_by_key(|(value, _)| -value);
+                for (i, (_, pv)) in found.iter().enumerate() {
+                    on_info(&SearchInfo {
+                        depth,
+                        multipv: i + 1,
+                        score: Score::from_value(found[i].0),
+                        nodes: self.nodes,
+                        time: self.elapsed(),
+                        pv: pv.clone(),
+                    });
+                }
+                let (best_value, best_pv) = &found[0];
+                if let Some(time_manager) = &mut self.time_manager {
+                    time_manager.on_iteration(best_pv[0], *best_value);
+                }
+                lines = found.into_iter()
+                    .map(|(value, pv)| AnalysisLine { m: pv[0], score: Score::from_value(value), pv })
+                    .collect();
+                if self.time_manager.as_ref().is_some_and(|time_manager| !time_manager.should_continue()) {
+                    break;
+                }
+            }
+            lines
+        }
+
+        /// searches every root move not in excluded, returning the best one's score and line
+        fn search_root(&mut self, board: &mut Board, depth: u32, excluded: &[Move], previous_pv: &[Move]) -> Option<(i32, Vec<Move>)> {
+            let mut moves: Vec<Move> = board.legal_moves().into_iter().filter(|m| !excluded.contains(m)).collect();
+            if moves.is_empty() {
+                return None;
+            }
+            let table_move = self.table.probe(board.zobrist_key(), board.turn()).and_then(|entry| entry.best);
+            order_moves(board, &mut moves, previous_pv.first().or(table_move.as_ref()));
+
+            let mut alpha = -INFINITY;
+            let mut pv = Vec::new();
+            for m in moves {
+                let follow = if previous_pv.first() == Some(&m) { &previous_pv[1..] } else { &[] };
+                board.play(m);
+                let (value, line) = self.negamax(board, depth - 1, 1, -INFINITY, -alpha, follow);
+                let value = -value;
+                board.undo_last_move();
+                if self.aborted {
+                    return None;
+                }
+                if value > alpha {
+                    alpha = value;
+                    pv.clear();
+                    pv.push(m);
+                    pv.extend(line);
+                }
+            }
+            Some((alpha, pv))
+        }
+
+        fn should_stop(&mut self) -> bool {
+            if self.nodes.is_multiple_of(64) {
+                let out_of_time = self.limits.movetime.is_some_and(|movetime| self.elapsed() >= movetime)
+                    || self.time_manager.as_ref().is_some_and(|time_manager| time_manager.should_stop());
+                let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+                if out_of_time || out_of_nodes || self.stop.load(Ordering::Relaxed) {
+                    self.aborted = true;
+                }
+            }
+            self.aborted
+        }
+
+        /// the value of a position the tablebase has, looked up only right after a capture or
+        /// pawn move as that is the only time the position can have come into its range
+        fn probe_tablebase(&self, board: &Board, ply: u32) -> Option<i32> {
+            let tablebase = self.limits.tablebase.as_ref()?;
+            if board.halfmove_clock() != 0 || !tablebase.covers(board) {
+                return None;
+            }
+            Some(match tablebase.probe_wdl(board)? {
+                Wdl::Win => TABLEBASE_WIN - ply as i32,
+                Wdl::CursedWin => 1,
+                Wdl::Draw => 0,
+                Wdl::BlessedLoss => -1,
+                Wdl::Loss => -TABLEBASE_WIN + ply as i32,
+            })
+        }
+
+        /// the value of the position and the line that leads to it
+        fn negamax(&mut self, board: &mut Board, depth: u32, ply: u32, mut alpha: i32, beta: i32, previous_pv: &[Move]) -> (i32, Vec<Move>) {
+            if let Some(outcome) = board.variant_outcome() {
+                return (outcome_value(outcome, board.turn(), ply), Vec::new());
+            }
+            if depth == 0 {
+                return (self.quiesce(board, ply, alpha, beta), Vec::new());
+            }
+            self.nodes += 1;
+            if self.should_stop() {
+                return (0, Vec::new());
+            }
+
+            let key = board.zobrist_key();
+            let entry = self.table.probe(key, board.turn());
+            if let Some(entry) = entry {
+                let score = value_from_table(entry.score, ply);
+                let usable = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+                if entry.depth >= depth && usable {
+                    return (score, Vec::new());
+                }
+            }
+
+            if let Some(value) = self.probe_tablebase(board, ply) {
+                return (value, Vec::new());
+            }
+
+            let mut moves = board.legal_moves();
+            if moves.is_empty() {
+                return (if board.is_in_check(&board.turn()) { -MATE + ply as i32 } else { 0 }, Vec::new());
+            }
+            if board.halfmove_clock() >= 100 {
+                return (0, Vec::new());
+            }
+            let table_move = entry.and_then(|entry| entry.best);
+            order_moves(board, &mut moves, previous_pv.first().or(table_move.as_ref()));
+
+            let original_alpha = alpha;
+            let mut best = None;
+            let mut pv = Vec::new();
+            for m in moves {
+                let follow = if previous_pv.first() == Some(&m) { &previous_pv[1..] } else { &[] };
+                board.play(m);
+                let (value, line) = self.negamax(board, depth - 1, ply + 1, -beta, -alpha, follow);
+                let value = -value;
+                board.undo_last_move();
+                if self.aborted {
+                    return (0, Vec::new());
+                }
+                if value > alpha {
+                    alpha = value;
+                    best = Some(m);
+                    pv.clear();
+                    pv.push(m);
+                    pv.extend(line);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if alpha > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.table.store(key, TableEntry { depth, score: value_to_table(alpha, ply), bound, best: best.or(table_move) });
+            (alpha, pv)
+        }
+
+        /// only looks at captures so the search doesn't stop in the middle of an exchange
+        fn quiesce(&mut self, board: &mut Board, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+            self.nodes += 1;
+            if self.should_stop() {
+                return 0;
+            }
+            if let Some(outcome) = board.variant_outcome() {
+                return outcome_value(outcome, board.turn(), ply);
+            }
+            let stand_pat = evaluate(board);
+            if stand_pat >= beta {
+                return beta;
+            }
+            alpha = alpha.max(stand_pat);
+
+            let mut captures: Vec<Move> = board.legal_moves().into_iter().filter(|m| board.is_capture(m)).collect();
+            order_moves(board, &mut captures, None);
+            for m in captures {
+                board.play(m);
+                let value = -self.quiesce(board, ply + 1, -beta, -alpha);
+                board.undo_last_move();
+                if self.aborted {
+                    return 0;
+                }
+                if value >= beta {
+                    return beta;
+                }
+                alpha = alpha.max(value);
+            }
+            alpha
+        }
+    }
+
+    /// the score of a game the variant's rules have already ended, from the side to move's point of view
+    fn outcome_value(outcome: Outcome, turn: Color, ply: u32) -> i32 {
+        match outcome {
+            Outcome::Draw => 0,
+            Outcome::Win(winner) if winner == turn => MATE - ply as i32,
+            Outcome::Win(_) => -MATE + ply as i32,
+        }
+    }
+
+    /// mate scores are stored as distance from the position rather than from the root, so they
+    /// still mean the same thing when the position turns up at a different ply
+    fn value_to_table(value: i32, ply: u32) -> i32 {
+        if value >= MATE - MAX_DEPTH as i32 {
+            value + ply as i32
+        } else if value <= -MATE + MAX_DEPTH as i32 {
+            value - ply as i32
+        } else {
+            value
+        }
+    }
+
+    fn value_from_table(value: i32, ply: u32) -> i32 {
+        if value >= MATE - MAX_DEPTH as i32 {
+            value - ply as i32
+        } else if value <= -MATE + MAX_DEPTH as i32 {
+            value + ply as i32
+        } else {
+            value
+        }
+    }
+
+    /// Lazy SMP: the calling thread searches as normal and reports through on_info while the
+    /// other threads search the same position, sharing what they find through the table. With
+    /// one thread this is the plain, deterministic single threaded search.
+    pub fn search_parallel<F: FnMut(&SearchInfo)>(board: &Board, limits: SearchLimits, threads: usize, table: Arc<TranspositionTable>, stop: Arc<AtomicBool>, on_info: F) -> Option<Move> {
+        analyse_parallel(board, limits, threads, table, stop, on_info).first().map(|line| line.m)
+    }
+
+    /// search_parallel returning every line asked for with limits.multi_pv
+    pub fn analyse_parallel<F: FnMut(&SearchInfo)>(board: &Board, limits: SearchLimits, threads: usize, table: Arc<TranspositionTable>, stop: Arc<AtomicBool>, on_info: F) -> Vec<AnalysisLine> {
+        let helpers_stop = Arc::new(AtomicBool::new(false));
+        let helpers: Vec<_> = (1..threads.max(1)).map(|id| {
+            let board = board.clone();
+            let mut helper = Searcher::with_table(Arc::clone(&helpers_stop), Arc::new(SystemClock::new()), Arc::clone(&table));
+            helper.first_depth = 1 + (id as u32 % 2);
+            thread::spawn(move || {
+                helper.search(&board, SearchLimits { infinite: true, ..SearchLimits::default() }, |_| {});
+            })
+        }).collect();
+
+        let lines = Searcher::with_table(stop, Arc::new(SystemClock::new()), table).analyse(board, limits, on_info);
+        helpers_stop.store(true, Ordering::Relaxed);
+        for helper in helpers {
+            helper.join().expect("a helper search thread panicked");
+        }
+        lines
+    }
+
+    /// puts the previous best move first, then captures of the most valuable pieces by the least valuable
+    pub fn order_moves(board: &Board, moves: &mut [Move], best: Option<&Move>) {
+        moves.sort_by_key(|m| {
+            if Some(m) == best {
+                return i32::MIN;
+            }
+            let attacker = board.get_piece_from(&m.from).map_or(0, |piece| piece_value(&piece));
+            let victim = match board.get_piece_from(&m.to) {
+                Some(piece) => piece_value(&piece),
+                None if board.is_capture(m) => piece_value(&Piece::Pawn(Color::White)),
+                None => 0,
+            };
+            let promotion = m.promoted.map_or(0, |piece| piece_value(&piece));
+            -(victim * 10 + promotion * 10 - if victim > 0 { attacker } else { 0 })
+        });
+    }
+
+    pub fn piece_value(piece: &Piece) -> i32 {
+        match piece {
+            Piece::Pawn(_) => 100,
+            Piece::Knight(_) => 320,
+            Piece::Bishop(_) => 330,
+            Piece::Rook(_) => 500,
+            Piece::Queen(_) => 900,
+            Piece::King(_) => 0,
+            Piece::Fairy(fairy, _) => fairy.value,
+        }
+    }
+
+    /// a small bonus for where a piece stands, pawns want to advance, minor pieces want the centre
+    /// and the king wants to stay out of it
+    fn positional_value(board: &Board, piece: &Piece, location: Location) -> i32 {
+        let (right, top) = (board.width() - 1, board.height() - 1);
+        let centrality = right + top - (2 * location.x - right).abs() - (2 * location.y - top).abs();
+        match piece {
+            Piece::Pawn(c @ Color::White) => (location.y - Pawn::starting_rank(c, board.height())) * 8 + centrality,
+            Piece::Pawn(c @ Color::Black) => (Pawn::starting_rank(c, board.height()) - location.y) * 8 + centrality,
+            Piece::Knight(_) | Piece::Bishop(_) => centrality * 3,
+            Piece::Queen(_) => centrality,
+            Piece::Rook(_) => 0,
+            Piece::King(_) => -centrality * 2,
+            Piece::Fairy(..) => centrality * 2,
+        }
+    }
+
+    /// what the variant's own way of winning is worth to one side: a king near the hill, checks
+    /// given towards three, or a king further up the board in the race
+    fn variant_value(board: &Board, color: Color) -> i32 {
+        let king = board.pieces().find(|(_, piece)| *piece == Piece::King(color)).map(|(location, _)| location);
+        match (board.variant(), king) {
+            (Variant::KingOfTheHill, Some(king)) => {
+                let (right, top) = (board.width() - 1, board.height() - 1);
+                let distance = (2 * king.x - right).abs().max((2 * king.y - top).abs());
+                (right.max(top) - distance) * 20
+            }
+            (Variant::ThreeCheck, _) => board.checks_given(color) as i32 * 200,
+            (Variant::RacingKings, Some(king)) => king.y * 60,
+            _ => 0,
+        }
+    }
+
+    /// static evaluation in centipawns from the point of view of the side to move
+    pub fn evaluate(board: &Board) -> i32 {
+        let on_board: i32 = board.pieces()
+            .map(|(location, piece)| {
+                let value = piece_value(&piece) + positional_value(board, &piece, location);
+                match piece.color() {
+                    Color::White => value,
+                    Color::Black => -value,
+                }
+            })
+            .sum();
+        // pieces in hand in crazyhouse are worth at least as much as on the board
+        let in_hand: i32 = board.pocket(Color::White).iter().map(piece_value).sum::<i32>()
+            - board.pocket(Color::Black).iter().map(piece_value).sum::<i32>();
+        let mut white = on_board + in_hand + variant_value(board, Color::White) - variant_value(board, Color::Black);
+        if board.variant() == Variant::Antichess {
+            // the fewer pieces the better, whatever they are
+            white = board.pieces().map(|(_, piece)| if *piece.color() == Color::White { -100 } else { 100 }).sum();
+        }
+        match board.turn() {
+            Color::White => white,
+            Color::Black => -white,
+        }
+    }
+}
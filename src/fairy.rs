@@ -0,0 +1,254 @@
+pub mod fairy {
+    use std::sync::{OnceLock, RwLock};
+    use crate::chess::FailReason;
+    use crate::chess_move::chess_move::Move;
+    use crate::location::location::Location;
+
+    /// a piece defined by how it moves rather than by its own code. New pieces are made with new,
+    /// registered so FEN knows their letter, and go on the board as Piece::Fairy.
+    #[derive(Debug, PartialEq)]
+    pub struct FairyPiece {
+        pub name: String,
+        /// the letter used in FEN, lowercase
+        pub letter: char,
+        /// how the piece moves, in Betza's funny notation, e.g. "BN" for a bishop and knight in one
+        pub betza: String,
+        /// roughly what the piece is worth in centipawns, for the engine
+        pub value: i32,
+        atoms: Vec<Atom>,
+    }
+
+    /// the pieces registered before anyone asks: name, letter, Betza notation and value. The
+    /// grasshopper moves along queen lines by hopping over the first piece in the way, landing
+    /// just beyond it.
+    const BUILT_IN: [(&str, char, &str, i32); 5] = [
+        ("archbishop", 'a', "BN", 850),
+        ("chancellor", 'c', "RN", 900),
+        ("amazon", 'm', "QN", 1200),
+        ("camel", 'l', "C", 250),
+        ("grasshopper", 'g', "gQ", 250),
+    ];
+
+    /// the letters the ordinary pieces already have
+    const TAKEN: [char; 6] = ['k', 'q', 'r', 'b', 'n', 'p'];
+
+    /// every piece FEN knows the letter of. Pieces are leaked into it, as the board holds them
+    /// by 'static reference, so they live as long as the program.
+    fn registry() -> &'static RwLock<Vec<&'static FairyPiece>> {
+        static REGISTRY: OnceLock<RwLock<Vec<&'static FairyPiece>>> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let pieces = BUILT_IN.iter()
+                .map(|(name, letter, betza, value)| FairyPiece::new(name, *letter, betza, *value).expect("the built in pieces are valid"))
+                .map(|piece| &*Box::leak(Box::new(piece)))
+                .collect();
+            RwLock::new(pieces)
+        })
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum Range {
+        /// one jump of the step, over anything in the way
+        Leap,
+        /// the step repeated until something is in the way
+        Ride,
+        /// along the line of the step to the first piece, then one step beyond it
+        Hop,
+    }
+
+    /// one part of a piece's move, a step taken in all eight directions it can point
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct Atom {
+        pub step: (i32, i32),
+        pub range: Range,
+        /// whether the piece can go to an empty square this way
+        pub moves: bool,
+        /// whether the piece can take this way
+        pub captures: bool,
+    }
+
+    impl Atom {
+        /// the step turned and mirrored every way, without repeats
+        pub fn directions(&self) -> Vec<(i32, i32)> {
+            let (a, b) = self.step;
+            let mut directions = Vec::new();
+            for (x, y) in &[(a, b), (b, a)] {
+                for (sx, sy) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    let direction = (x * sx, y * sy);
+                    if !directions.contains(&direction) {
+                        directions.push(direction);
+                    }
+                }
+            }
+            directions
+        }
+    }
+
+    impl FairyPiece {
+        /// a piece moving as the Betza notation says, which is checked here once and for all
+        pub fn new(name: &str, letter: char, betza: &str, value: i32) -> Result<FairyPiece, FailReason> {
+            if !letter.is_ascii_lowercase() {
+                return Err(FailReason::ParseError(format!("the {} needs a lowercase letter, not '{}'", name, letter)));
+            }
+            let atoms = FairyPiece::parse_betza(name, betza)?;
+            Ok(FairyPiece { name: String::from(name), letter, betza: String::from(betza), value, atoms })
+        }
+
+        /// makes the piece's letter known to FEN from now on. The letter must not be one of the
+        /// ordinary pieces' or one already registered, unless it is this very piece again.
+        pub fn register(piece: FairyPiece) -> Result<&'static FairyPiece, FailReason> {
+            if TAKEN.contains(&piece.letter) {
+                return Err(FailReason::ParseError(format!("'{}' is already the letter of an ordinary piece", piece.letter)));
+            }
+            let mut registry = registry().write().expect("no one panics while registering");
+            match registry.iter().find(|other| other.letter == piece.letter) {
+                Some(other) if **other == piece => Ok(*other),
+                Some(other) => Err(FailReason::ParseError(format!("'{}' is already the letter of the {}", piece.letter, other.name))),
+                None => {
+                    let piece: &'static FairyPiece = Box::leak(Box::new(piece));
+                    registry.push(piece);
+                    Ok(piece)
+                }
+            }
+        }
+
+        /// the registered piece with this letter, in either case
+        pub fn from_letter(letter: char) -> Option<&'static FairyPiece> {
+            let registry = registry().read().expect("no one panics while registering");
+            registry.iter().cloned().find(|piece| piece.letter == letter.to_ascii_lowercase())
+        }
+
+        /// the registered piece with this name, such as "archbishop"
+        pub fn from_name(name: &str) -> Option<&'static FairyPiece> {
+            let registry = registry().read().expect("no one panics while registering");
+            registry.iter().cloned().find(|piece| piece.name == name)
+        }
+
+        /// the parts of the piece's move
+        pub fn atoms(&self) -> &[Atom] {
+            &self.atoms
+        }
+
+        /// parses the Betza description. Understood are the leapers W F D N A H C Z G, the
+        /// compounds K R B Q, a doubled leaper for a rider as in NN, and before an atom m for
+        /// moving only, c for taking only and g for hopping like a grasshopper.
+        fn parse_betza(name: &str, betza: &str) -> Result<Vec<Atom>, FailReason> {
+            let invalid = |reason: &str| FailReason::ParseError(format!("\"{}\" for the {}: {}", betza, name, reason));
+            let chars: Vec<char> = betza.chars().collect();
+            let mut atoms = Vec::new();
+            let (mut moves_only, mut captures_only, mut hops) = (false, false, false);
+            let mut i = 0;
+            while i < chars.len() {
+                let c = chars[i];
+                i += 1;
+                let steps: Vec<((i32, i32), Range)> = match c {
+                    'm' => { moves_only = true; continue; }
+                    'c' => { captures_only = true; continue; }
+                    'g' => { hops = true; continue; }
+                    'K' => vec!(((1, 0), Range::Leap), ((1, 1), Range::Leap)),
+                    'R' => vec!(((1, 0), Range::Ride)),
+                    'B' => vec!(((1, 1), Range::Ride)),
+                    'Q' => vec!(((1, 0), Range::Ride), ((1, 1), Range::Ride)),
+                    leaper => {
+                        let step = FairyPiece::leaper_step(leaper).ok_or_else(|| invalid(&format!("'{}' is not an atom we know", leaper)))?;
+                        let range = if chars.get(i) == Some(&leaper) {
+                            i += 1;
+                            Range::Ride
+                        } else {
+                            Range::Leap
+                        };
+                        vec!((step, range))
+                    }
+                };
+                for (step, range) in steps {
+                    let range = match (hops, range) {
+                        (true, Range::Ride) => Range::Hop,
+                        (true, _) => return Err(invalid("only riders can hop")),
+                        (false, range) => range,
+                    };
+                    atoms.push(Atom { step, range, moves: !captures_only, captures: !moves_only });
+                }
+                moves_only = false;
+                captures_only = false;
+                hops = false;
+            }
+            if atoms.is_empty() {
+                return Err(invalid("a piece has to move somehow"));
+            }
+            Ok(atoms)
+        }
+
+        fn leaper_step(atom: char) -> Option<(i32, i32)> {
+            match atom {
+                'W' => Some((1, 0)),
+                'F' => Some((1, 1)),
+                'D' => Some((2, 0)),
+                'N' => Some((2, 1)),
+                'A' => Some((2, 2)),
+                'H' => Some((3, 0)),
+                'C' => Some((3, 1)),
+                'Z' => Some((3, 2)),
+                'G' => Some((3, 3)),
+                _ => None
+            }
+        }
+
+        /// every square the piece gets to from where it stands on a board of the given size, given
+        /// which squares hold a piece, along with whether it could move there and whether it could
+        /// take there. Whose pieces are where is left to the board.
+        pub fn reach<F: Fn(Location) -> bool>(&self, from: Location, width: i32, height: i32, occupied: F) -> Vec<(Location, bool, bool)> {
+            let on_board = |square: Location| square.is_within(width, height);
+            let mut reached = Vec::new();
+            for atom in &self.atoms {
+                for (x, y) in atom.directions() {
+                    let step = Location::new(x, y);
+                    let mut to = from + step;
+                    match atom.range {
+                        Range::Leap => {
+                            if on_board(to) {
+                                reached.push((to, atom.moves, atom.captures));
+                            }
+                        }
+                        Range::Ride => {
+                            while on_board(to) {
+                                reached.push((to, atom.moves, atom.captures));
+                                if occupied(to) {
+                                    break;
+                                }
+                                to = to + step;
+                            }
+                        }
+                        Range::Hop => {
+                            while on_board(to) && !occupied(to) {
+                                to = to + step;
+                            }
+                            let beyond = to + step;
+                            if on_board(to) && on_board(beyond) {
+                                reached.push((beyond, atom.moves, atom.captures));
+                            }
+                        }
+                    }
+                }
+            }
+            reached
+        }
+
+        /// only whether the move has the right shape, what is in the way is for the board to check.
+        /// That leaves nothing in between to report, just where the piece starts and ends.
+        pub fn squares_moved(&self, m: Move) -> Result<Vec<Location>, FailReason> {
+            let (dx, dy) = ((m.to - m.from).x, (m.to - m.from).y);
+            let fits = self.atoms.iter().any(|atom| atom.directions().iter().any(|(x, y)| {
+                let times = if *x != 0 { dx / x } else { dy / y };
+                times >= 1 && dx == x * times && dy == y * times && match atom.range {
+                    Range::Leap => times == 1,
+                    Range::Ride => true,
+                    Range::Hop => times >= 2,
+                }
+            }));
+            if fits {
+                Ok(vec!(m.from, m.to))
+            } else {
+                Err(FailReason::ImpossibleMove(format!("invalid move for a {}", self.name)))
+            }
+        }
+    }
+}
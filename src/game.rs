@@ -0,0 +1,276 @@
+pub mod game {
+    use std::time::Duration;
+    use crate::board::board::Board;
+    use crate::chess::FailReason;
+    use crate::chess_move::chess_move::Move;
+    use crate::engine::engine::Score;
+
+    /// the tags every PGN game has, in the order they are written
+    pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+    /// a move in the game tree along with whatever was said about it. The first of the children
+    /// is how the line carries on, any others are variations on it. The move and the children
+    /// can only be changed through the game, so everything in the tree was legal when played.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Node {
+        m: Move,
+        /// written after the move, empty for none
+        pub comment: String,
+        /// numeric annotation glyphs, 1 for !, 2 for ?, 3 for !! and so on
+        pub nags: Vec<u8>,
+        /// the time left on the mover's clock after the move
+        pub clock: Option<Duration>,
+        /// what an engine made of the position after the move, from white's side
+        pub eval: Option<Score>,
+        children: Vec<Node>,
+    }
+
+    impl Node {
+        fn new(m: Move) -> Self {
+            Node { m, comment: String::new(), nags: Vec::new(), clock: None, eval: None, children: Vec::new() }
+        }
+
+        pub fn m(&self) -> Move {
+            self.m
+        }
+
+        /// the moves that can follow this one, the main one first
+        pub fn children(&self) -> &[Node] {
+            &self.children
+        }
+
+        /// the moves from here to the end of the line, this one first
+        pub fn mainline(&self) -> Vec<Move> {
+            let mut moves = vec!(self.m);
+            let mut node = self;
+            while let Some(next) = node.children.first() {
+                moves.push(next.m);
+                node = next;
+            }
+            moves
+        }
+    }
+
+    /// a game record: its headers, the position it started from and every line played or
+    /// looked at from there, with a cursor somewhere in the tree and the board as it is there
+    #[derive(Clone)]
+    pub struct Game {
+        headers: Vec<(String, String)>,
+        start: Board,
+        /// written before the first move, empty for none
+        comment: String,
+        moves: Vec<Node>,
+        /// which child to take at each step from the start down to the current move
+        cursor: Vec<usize>,
+        board: Board,
+    }
+
+    impl Default for Game {
+        fn default() -> Self {
+            Game::new(Board::starting_position())
+        }
+    }
+
+    impl Game {
+        /// a game from the given position with the seven tag roster filled in as unknown
+        pub fn new(start: Board) -> Self {
+            let headers = SEVEN_TAG_ROSTER.iter()
+                .map(|name| (name.to_string(), String::from(if *name == "Result" { "*" } else { "?" })))
+                .collect();
+            Game { headers, board: start.clone(), start, comment: String::new(), moves: Vec::new(), cursor: Vec::new() }
+        }
+
+        pub fn headers(&self) -> &[(String, String)] {
+            &self.headers
+        }
+
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+        }
+
+        /// changes the header if there is one, adding it at the end if not
+        pub fn set_header(&mut self, name: &str, value: &str) {
+            match self.headers.iter_mut().find(|(tag, _)| tag == name) {
+                Some((_, old)) => *old = value.to_string(),
+                None => self.headers.push((name.to_string(), value.to_string())),
+            }
+        }
+
+        pub fn remove_header(&mut self, name: &str) {
+            self.headers.retain(|(tag, _)| tag != name);
+        }
+
+        pub fn start(&self) -> &Board {
+            &self.start
+        }
+
+        /// the comment before the first move
+        pub fn comment(&self) -> &str {
+            &self.comment
+        }
+
+        pub fn set_comment(&mut self, comment: &str) {
+            self.comment = comment.to_string();
+        }
+
+        /// the moves from the start, the first being the one actually played
+        pub fn moves(&self) -> &[Node] {
+            &self.moves
+        }
+
+        /// the moves of the main line, from the start to the end
+        pub fn mainline(&self) -> Vec<Move> {
+            self.moves.first().map_or_else(Vec::new, |first| first.mainline())
+        }
+
+        /// the position at the cursor
+        pub fn board(&self) -> &Board {
+            &self.board
+        }
+
+        /// the moves from the start to the cursor
+        pub fn path(&self) -> Vec<Move> {
+            let mut moves = Vec::new();
+            let mut children = &self.moves;
+            for i in &self.cursor {
+                moves.push(children[*i].m);
+                children = &children[*i].children;
+            }
+            moves
+        }
+
+        /// the move the cursor is on, None at the start
+        pub fn current(&self) -> Option<&Node> {
+            let mut node = None;
+            let mut children = &self.moves;
+            for i in &self.cursor {
+                node = Some(&children[*i]);
+                children = &children[*i].children;
+            }
+            node
+        }
+
+        pub fn current_mut(&mut self) -> Option<&mut Node> {
+            let (last, rest) = self.cursor.split_last()?;
+            let mut children = &mut self.moves;
+            for i in rest {
+                children = &mut children[*i].children;
+            }
+            Some(&mut children[*last])
+        }
+
+        /// the moves that can follow the cursor, the main one first
+        pub fn continuations(&self) -> &[Node] {
+            match self.current() {
+                Some(node) => &node.children,
+                None => &self.moves,
+            }
+        }
+
+        fn continuations_mut(&mut self) -> &mut Vec<Node> {
+            let mut children = &mut self.moves;
+            for i in &self.cursor {
+                children = &mut children[*i].children;
+            }
+            children
+        }
+
+        /// plays the move from the cursor and moves onto it. A move already in the tree is
+        /// followed, a new one becomes the main line if there was nothing after the cursor and
+        /// a variation otherwise.
+        pub fn play(&mut self, m: Move) -> Result<(), FailReason> {
+            self.board.make_move(m)?;
+            let children = self.continuations_mut();
+            let index = match children.iter().position(|node| node.m == m) {
+                Some(index) => index,
+                None => {
+                    children.push(Node::new(m));
+                    children.len() - 1
+                }
+            };
+            self.cursor.push(index);
+            Ok(())
+        }
+
+        /// follows the main continuation, false at the end of the line
+        pub fn forward(&mut self) -> bool {
+            self.enter_variation(0)
+        }
+
+        /// follows the continuation with the given index, 0 being the main one
+        pub fn enter_variation(&mut self, index: usize) -> bool {
+            match self.continuations().get(index).map(|node| node.m) {
+                Some(m) => {
+                    self.board.make_move(m).expect("moves in the tree were legal when they were added");
+                    self.cursor.push(index);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// goes back a move, false at the start
+        pub fn back(&mut self) -> bool {
+            if self.cursor.pop().is_some() {
+                self.board.undo_last_move();
+                true
+            } else {
+                false
+            }
+        }
+
+        pub fn go_to_start(&mut self) {
+            self.cursor.clear();
+            self.board = self.start.clone();
+        }
+
+        /// follows the main continuation from the cursor as far as it goes
+        pub fn go_to_end(&mut self) {
+            while self.forward() {}
+        }
+
+        /// whether the cursor is on the main line of the game
+        pub fn is_mainline(&self) -> bool {
+            self.cursor.iter().all(|i| *i == 0)
+        }
+
+        /// makes the line the cursor is in the main line of the game, pushing aside whatever
+        /// was played instead at each point it branched off
+        pub fn promote_to_mainline(&mut self) {
+            let cursor = std::mem::take(&mut self.cursor);
+            let mut children = &mut self.moves;
+            for i in cursor.iter() {
+                let node = children.remove(*i);
+                children.insert(0, node);
+                children = &mut children[0].children;
+            }
+            self.cursor = vec!(0; cursor.len());
+        }
+
+        /// moves the line the cursor is in up a place among the alternatives where it branched
+        /// off, false if it is already the main line
+        pub fn promote_variation(&mut self) -> bool {
+            let depth = match self.cursor.iter().rposition(|i| *i != 0) {
+                Some(depth) => depth,
+                None => return false,
+            };
+            let index = self.cursor[depth];
+            let mut children = &mut self.moves;
+            for i in &self.cursor[..depth] {
+                children = &mut children[*i].children;
+            }
+            children.swap(index, index - 1);
+            self.cursor[depth] = index - 1;
+            true
+        }
+
+        /// removes the move at the cursor and everything after it, leaving the cursor on the
+        /// move before. Nothing happens at the start.
+        pub fn delete_subtree(&mut self) {
+            if let Some(index) = self.cursor.last().cloned() {
+                self.back();
+                self.continuations_mut().remove(index);
+            }
+        }
+    }
+}
@@ -1,17 +1,36 @@
-#![feature(try_trait)]
-#![feature(option_unwrap_none)]
+// every module wraps its contents in a module of the same name, as in board::board::Board
+#![allow(clippy::module_inception)]
 
+#[cfg(test)]
 mod test;
-mod location;
-mod board;
-mod chess_move;
-
-mod chess {
-
-    use std::option::NoneError;
+pub mod location;
+pub mod board;
+pub mod chess_move;
+pub mod engine;
+pub mod time_manager;
+pub mod transposition;
+pub mod uci;
+pub mod xboard;
+pub mod variant;
+pub mod fairy;
+pub mod render;
+pub mod svg;
+pub mod pgn;
+pub mod tui;
+pub mod clock;
+pub mod game;
+pub mod polyglot;
+pub mod eco;
+pub mod syzygy;
+#[cfg(feature = "serde")]
+pub mod serialize;
+
+pub mod chess {
 
     use crate::location::location::Location;
     use crate::chess_move::chess_move::Move;
+    use crate::variant::variant::Variant;
+    use crate::fairy::fairy::FairyPiece;
 
     #[derive(Copy, Clone, Debug, PartialEq)]
     pub enum Piece {
@@ -21,14 +40,26 @@ mod chess {
         King(Color),
         Queen(Color),
         Bishop(Color),
+        /// any piece described in the fairy module, such as an archbishop or a grasshopper
+        Fairy(&'static FairyPiece, Color),
     }
 
     #[derive(Copy, Clone, PartialEq, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
     pub enum Color {
         White,
         Black,
     }
 
+    impl Color {
+        pub fn opposite(&self) -> Color {
+            match self {
+                Color::White => Color::Black,
+                Color::Black => Color::White,
+            }
+        }
+    }
+
     impl Piece {
         pub fn color(&self) -> &Color {
             match self {
@@ -38,6 +69,50 @@ mod chess {
                 Piece::King(c) => { c }
                 Piece::Queen(c) => { c }
                 Piece::Bishop(c) => { c }
+                Piece::Fairy(_, c) => { c }
+            }
+        }
+
+        /// the letter used for this piece in FEN, uppercase for white and lowercase for black
+        pub fn to_char(&self) -> char {
+            let c = match self {
+                Piece::Rook(_) => 'r',
+                Piece::Knight(_) => 'n',
+                Piece::Pawn(_) => 'p',
+                Piece::King(_) => 'k',
+                Piece::Queen(_) => 'q',
+                Piece::Bishop(_) => 'b',
+                Piece::Fairy(fairy, _) => fairy.letter,
+            };
+            match self.color() {
+                Color::White => c.to_ascii_uppercase(),
+                Color::Black => c,
+            }
+        }
+
+        /// the same kind of piece in the given color
+        pub fn with_color(&self, color: Color) -> Piece {
+            match self {
+                Piece::Rook(_) => Piece::Rook(color),
+                Piece::Knight(_) => Piece::Knight(color),
+                Piece::Pawn(_) => Piece::Pawn(color),
+                Piece::King(_) => Piece::King(color),
+                Piece::Queen(_) => Piece::Queen(color),
+                Piece::Bishop(_) => Piece::Bishop(color),
+                Piece::Fairy(fairy, _) => Piece::Fairy(fairy, color),
+            }
+        }
+
+        pub fn from_char(c: char) -> Option<Piece> {
+            let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+            match c.to_ascii_lowercase() {
+                'r' => Some(Piece::Rook(color)),
+                'n' => Some(Piece::Knight(color)),
+                'p' => Some(Piece::Pawn(color)),
+                'k' => Some(Piece::King(color)),
+                'q' => Some(Piece::Queen(color)),
+                'b' => Some(Piece::Bishop(color)),
+                other => FairyPiece::from_letter(other).map(|fairy| Piece::Fairy(fairy, color))
             }
         }
     }
@@ -51,6 +126,7 @@ mod chess {
                 Piece::King(c) => { King::squares_moved(m, c) }
                 Piece::Queen(c) => { Queen::squares_moved(m, c) }
                 Piece::Bishop(c) => { Bishop::squares_moved(m, c) }
+                Piece::Fairy(fairy, _) => { fairy.squares_moved(m) }
             }
         }
     }
@@ -63,13 +139,7 @@ mod chess {
         OutOfBounds(String),
         NeedPromotion(String),
         Checked(String),
-    }
-
-
-    impl From<NoneError> for FailReason {
-        fn from(_: NoneError) -> Self {
-            FailReason::NoPieceHere(String::from("no piece found when trying to unwrap a value from the board"))
-        }
+        ParseError(String),
     }
 
     #[derive(Copy, Clone)]
@@ -112,10 +182,12 @@ mod chess {
                     }
                 }
                 (0, 2) | (0, -2) => {
-                    if Pawn::is_in_original_position(from, c) {
+                    // whether the pawn is still on a square it may move two from depends on the
+                    // variant, so that is left to the board
+                    if Pawn::is_right_direction(Move::new(from, Location::new(to.x, (from.y + to.y) / 2)), c) {
                         Ok(to.locations_between(from).unwrap())
                     } else {
-                        Err(FailReason::ImpossibleMove(String::from("invalid move for a pawn, cannot move two forward if already moved")))
+                        Err(FailReason::ImpossibleMove(String::from("invalid move for a pawn, must move forward")))
                     }
                 }
                 (x, y) => {
@@ -150,10 +222,23 @@ mod chess {
             }
         }
 
-        fn is_in_original_position(from: Location, color: &Color) -> bool {
+        /// the rank the pawns start on, so that four empty ranks are left between the two sides.
+        /// That is the second rank on a board 8 high, and the third on one 10 high as in Grand Chess.
+        pub fn starting_rank(color: &Color, height: i32) -> i32 {
+            let rank = ((height - 6) / 2).max(1);
             match color {
-                Color::White => from.y == 1,
-                Color::Black => from.y == 6,
+                Color::White => rank,
+                Color::Black => height - 1 - rank,
+            }
+        }
+
+        /// whether a pawn on this square may move two forward. In horde that includes white's
+        /// pawns on the rank behind.
+        pub fn is_in_original_position(from: Location, color: &Color, variant: Variant, height: i32) -> bool {
+            let rank = Pawn::starting_rank(color, height);
+            match (color, variant) {
+                (Color::White, Variant::Horde) => from.y == rank || from.y == rank - 1,
+                _ => from.y == rank,
             }
         }
 
@@ -177,35 +262,18 @@ mod chess {
             }
         }
 
-        pub fn is_castling(m: &Move) -> bool {
-            match (m.to.as_tup(), m.from.as_tup()) {
-                ((2, _), (4, _)) => true,
-                ((6, _), (4, _)) => true,
-                _ => false
-            }
+        /// where the king ends up castling with the rook on rook_location on a board this wide. In
+        /// chess960 just as in chess, that is the g file when castling towards the h file and the c
+        /// file otherwise, and on wider boards the file next to the last rather than the g file.
+        pub fn get_kings_destination_for_castle(king_location: Location, rook_location: Location, width: i32) -> Location {
+            let x = if rook_location.x > king_location.x { width - 2 } else { 2 };
+            Location { x, y: king_location.y }
         }
 
-        pub fn get_rooks_move_for_castle(kings_move: &Move) -> Result<Move, FailReason> {
-            let from = King::get_rooks_location_for_castle(kings_move)?;
-
-            let to = match kings_move.to.as_tup() {
-                (2, y) => Location { x: 3, y },
-                (6, y) => Location { x: 5, y },
-                _ => unreachable!("as we're castling, it should be one of these")
-            };
-            Ok(Move::new(from, to))
-        }
-
-        pub fn get_rooks_location_for_castle(kings_move: &Move) -> Result<Location, FailReason> {
-            assert!(King::is_castling(kings_move), "called get_rooks_location_for_castle when not castling");
-            match kings_move.to.as_tup() {
-                (2, y) => Ok(Location { x: 0, y }),
-                (6, y) => Ok(Location { x: 7, y }),
-                _ => unreachable!(
-                    format!("as we're castling, it should be one of these, instead the \
-             kings destination is {:?}", kings_move.to)
-                )
-            }
+        /// the rook goes next to where the king ends up, on the f or the d file on an 8x8 board
+        pub fn get_rooks_move_for_castle(king_location: Location, rook_location: Location, width: i32) -> Move {
+            let x = if rook_location.x > king_location.x { width - 3 } else { 3 };
+            Move::new(rook_location, Location { x, y: rook_location.y })
         }
     }
 
@@ -218,11 +286,11 @@ mod chess {
     impl Bishop {
         pub(crate) fn squares_moved(m: Move, _c: &Color) -> Result<Vec<Location>, FailReason> {
             let Move { from, to, .. } = m;
-            return if Bishop::is_diagonal(m) {
+            if Bishop::is_diagonal(m) {
                 let moves: Vec<(i32, i32)> = match from - to {
                     loc if loc.x > 0 && loc.y > 0 => {
                         ((to.x..=from.x).rev())
-                            .zip((to.x..=from.x).rev())
+                            .zip((to.y..=from.y).rev())
                             .collect()
                     }
                     loc if loc.x > 0 && loc.y < 0 => {
@@ -245,7 +313,7 @@ mod chess {
                 Ok(moves.iter().map(|(x, y)| { Location { x: *x, y: *y } }).collect())
             } else {
                 Err(FailReason::ImpossibleMove(String::from("invalid move for a bishop")))
-            };
+            }
         }
 
         fn is_diagonal(m: Move) -> bool {
@@ -258,8 +326,8 @@ mod chess {
         pub(crate) fn squares_moved(m: Move, _c: &Color) -> Result<Vec<Location>, FailReason> {
             let Move { from, to, .. } = m;
             match to - from {
-                Location { x, y: 0 } => Ok((0..=x).into_iter().map(|x| { from + Location { x, y: 0 } }).collect()),
-                Location { x: 0, y } => Ok((0..=y).into_iter().map(|y| { from + Location { x: 0, y } }).collect()),
+                Location { x, y: 0 } => Ok((0..=x.abs()).map(|i| { from + Location { x: i * x.signum(), y: 0 } }).collect()),
+                Location { x: 0, y } => Ok((0..=y.abs()).map(|i| { from + Location { x: 0, y: i * y.signum() } }).collect()),
                 _ => Err(FailReason::ImpossibleMove(String::from("invalid move for a rook")))
             }
         }
@@ -1,8 +1,12 @@
-pub(crate) mod location {
+pub mod location {
     use crate::chess::{FailReason, Color, Rook, Bishop};
     use std::ops::{Sub, Add};
+    use std::fmt::{Display, Formatter};
     use crate::chess_move::chess_move::Move;
 
+    /// the most files or ranks a board can have
+    pub const MAX_SIZE: i32 = 16;
+
     #[derive(Copy, Clone, Debug, Ord, PartialOrd, PartialEq, Eq)]
     pub struct Location {
         pub x: i32,
@@ -25,13 +29,40 @@ pub(crate) mod location {
             let (x, y) = self.as_tup();
             (x.abs(), y.abs())
         }
+        /// whether the square is on an ordinary 8x8 board
         pub fn is_in_bounds(&self) -> bool {
+            self.is_within(8, 8)
+        }
+
+        pub fn is_within(&self, width: i32, height: i32) -> bool {
             let (x, y) = self.as_tup();
-            x >= 0 && x <= 7 && y >= 0 && y <= 7
+            x >= 0 && x < width && y >= 0 && y < height
+        }
+
+        /// parses a square in algebraic notation such as "e4", or "j10" on a bigger board. Whether
+        /// the square is on the board being played on is up to the board.
+        pub fn from_algebraic(s: &str) -> Result<Location, FailReason> {
+            match Location::split_algebraic(s) {
+                Some((location, "")) => Ok(location),
+                _ => Err(FailReason::ParseError(format!("\"{}\" is not a square, expected a file and a rank like e4", s)))
+            }
+        }
+
+        /// the square at the start of the string, and whatever follows it
+        pub fn split_algebraic(s: &str) -> Option<(Location, &str)> {
+            let file = s.chars().next().filter(|c| c.is_ascii_lowercase())?;
+            let digits = s[1..].find(|c: char| !c.is_ascii_digit()).map_or(s.len(), |at| at + 1);
+            let rank: i32 = s[1..digits].parse().ok()?;
+            let location = Location::new(file as i32 - 'a' as i32, rank - 1);
+            if location.is_within(MAX_SIZE, MAX_SIZE) {
+                Some((location, &s[digits..]))
+            } else {
+                None
+            }
         }
 
         pub fn locations_between(&self, dest: Location) -> Result<Vec<Location>, FailReason> {
-            let m = Move::new(self.clone(), dest);
+            let m = Move::new(*self, dest);
             if let Ok(rook_result) = Rook::squares_moved(m, &Color::White) {
                 Ok(rook_result)
             } else if let Ok(bishop_result) = Bishop::squares_moved(m, &Color::White) {
@@ -42,6 +73,12 @@ pub(crate) mod location {
         }
     }
 
+    impl Display for Location {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
+        }
+    }
+
     impl Sub for Location {
         type Output = Location;
 
@@ -0,0 +1,519 @@
+pub mod pgn {
+    use crate::board::board::{Board, STARTING_FEN};
+    use crate::chess::{Piece, Color, FailReason};
+    use crate::chess_move::chess_move::Move;
+    use crate::engine::engine::Score;
+    use crate::game::game::{Game, Node};
+    use crate::location::location::Location;
+    use crate::variant::variant::Variant;
+    use std::time::Duration;
+
+    /// the move in standard algebraic notation, e.g. "Nbd2", "exd6", "e8=Q+" or "O-O", as played
+    /// from the given position
+    pub fn to_san(board: &Board, m: Move) -> String {
+        let mut san = match (m.dropped, board.castling_rook(&m), board.get_piece_from(&m.from)) {
+            (Some(dropped), _, _) => format!("{}@{}", piece_letter(dropped), m.to),
+            (None, Some(rook), _) if rook.x > m.from.x => String::from("O-O"),
+            (None, Some(_), _) => String::from("O-O-O"),
+            (None, None, Some(Piece::Pawn(_))) => {
+                let mut san = if board.is_capture(&m) { format!("{}x{}", file_letter(m.from.x), m.to) } else { m.to.to_string() };
+                if let Some(promoted) = m.promoted {
+                    san.push_str(&format!("={}", piece_letter(promoted)));
+                }
+                san
+            }
+            (None, None, Some(piece)) => {
+                // other pieces of the same kind that could also go there
+                let rivals: Vec<Location> = board.legal_moves().into_iter()
+                    .filter(|other| other.to == m.to && other.from != m.from && board.get_piece_from(&other.from) == Some(piece))
+                    .map(|other| other.from)
+                    .collect();
+                let from = if rivals.is_empty() {
+                    String::new()
+                } else if rivals.iter().all(|rival| rival.x != m.from.x) {
+                    file_letter(m.from.x).to_string()
+                } else if rivals.iter().all(|rival| rival.y != m.from.y) {
+                    (m.from.y + 1).to_string()
+                } else {
+                    m.from.to_string()
+                };
+                let takes = if board.is_capture(&m) { "x" } else { "" };
+                format!("{}{}{}{}", piece_letter(piece), from, takes, m.to)
+            }
+            (None, None, None) => m.to_string(),
+        };
+        let mut after = board.clone();
+        if after.make_move(m).is_ok() {
+            if after.is_checkmate() {
+                san.push('#');
+            } else if after.is_in_check(&after.turn()) {
+                san.push('+');
+            }
+        }
+        san
+    }
+
+    /// reads a move in standard algebraic notation. Checks, annotations like "!?", a missing "="
+    /// before a promotion and more disambiguation than needed are all accepted.
+    pub fn parse_san(board: &Board, san: &str) -> Result<Move, FailReason> {
+        let invalid = || FailReason::ParseError(format!("\"{}\" is not a move in algebraic notation", san));
+        let trimmed = san.trim_end_matches(|c| "+#!?".contains(c));
+        let legal = board.legal_moves();
+        let castling = match trimmed {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castling {
+            return legal.into_iter()
+                .find(|m| board.castling_rook(m).is_some_and(|rook| (rook.x > m.from.x) == kingside))
+                .ok_or_else(|| FailReason::ImpossibleMove(format!("cannot castle {} here", if kingside { "kingside" } else { "queenside" })));
+        }
+
+        if let Some(at) = trimmed.find('@') {
+            let letter = trimmed[..at].chars().next().unwrap_or('P');
+            let to = Location::from_algebraic(&trimmed[at + 1..])?;
+            return legal.into_iter()
+                .find(|m| m.to == to && m.dropped.is_some_and(|dropped| piece_letter(dropped) == letter))
+                .ok_or_else(|| FailReason::ImpossibleMove(format!("cannot drop {} here", san)));
+        }
+
+        // the promotion, if any, comes last and is the only uppercase letter after the square
+        let (rest, promotion) = match trimmed.char_indices().last() {
+            Some((at, c)) if c.is_ascii_uppercase() && at > 1 => (trimmed[..at].trim_end_matches('='), Some(c)),
+            _ => (trimmed, None),
+        };
+        let square_at = rest.rfind(|c: char| c.is_ascii_lowercase()).ok_or_else(invalid)?;
+        let to = Location::from_algebraic(&rest[square_at..])?;
+        let mut prefix = rest[..square_at].trim_end_matches(['x', ':']).chars().peekable();
+        let letter = match prefix.peek() {
+            Some(c) if c.is_ascii_uppercase() => prefix.next().unwrap_or('P'),
+            _ => 'P',
+        };
+        let (mut file, mut rank_digits) = (None, String::new());
+        for c in prefix {
+            match c {
+                'a'..='p' => file = Some(c as i32 - 'a' as i32),
+                '0'..='9' => rank_digits.push(c),
+                _ => return Err(invalid()),
+            }
+        }
+        let rank = if rank_digits.is_empty() { None } else { Some(rank_digits.parse::<i32>().map_err(|_| invalid())? - 1) };
+
+        let candidates: Vec<Move> = legal.into_iter()
+            .filter(|m| m.to == to && !m.is_drop() && board.castling_rook(m).is_none())
+            .filter(|m| board.get_piece_from(&m.from).is_some_and(|piece| piece_letter(piece) == letter))
+            .filter(|m| file.is_none_or(|x| m.from.x == x) && rank.is_none_or(|y| m.from.y == y))
+            .filter(|m| m.promoted.map(piece_letter) == promotion)
+            .collect();
+        match candidates[..] {
+            [m] => Ok(m),
+            [] => Err(FailReason::ImpossibleMove(format!("{} is not a legal move here", san))),
+            _ => Err(FailReason::ParseError(format!("{} could be more than one move", san))),
+        }
+    }
+
+    /// the game as PGN, starting from the given position. Tags are written in the order given,
+    /// with the position added as SetUp and FEN tags if the game did not start from the usual one.
+    pub fn write_pgn(tags: &[(&str, String)], start: &Board, moves: &[Move], result: &str) -> String {
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            pgn.push_str(&tag_pair(name, value));
+        }
+        if !tags.iter().any(|(name, _)| *name == "Result") {
+            pgn.push_str(&format!("[Result \"{}\"]\n", result));
+        }
+        let fen = start.to_fen();
+        if fen != STARTING_FEN {
+            pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", fen));
+        }
+        pgn.push('\n');
+
+        let mut board = start.clone();
+        let mut tokens = Vec::new();
+        for (i, m) in moves.iter().enumerate() {
+            match board.turn() {
+                Color::White => tokens.push(format!("{}.", board.fullmove_number())),
+                Color::Black if i == 0 => tokens.push(format!("{}...", board.fullmove_number())),
+                Color::Black => {}
+            }
+            tokens.push(to_san(&board, *m));
+            if board.make_move(*m).is_err() {
+                break;
+            }
+        }
+        tokens.push(String::from(result));
+        pgn.push_str(&wrap(&tokens));
+        pgn
+    }
+
+    /// the game as PGN with its variations, comments, annotation glyphs and the clock times and
+    /// evaluations as [%clk] and [%eval] commands in the comments
+    pub fn write_game(game: &Game) -> String {
+        let mut pgn = String::new();
+        for (name, value) in game.headers() {
+            pgn.push_str(&tag_pair(name, value));
+        }
+        let fen = game.start().to_fen();
+        if fen != STARTING_FEN && game.header("FEN").is_none() {
+            pgn.push_str(&tag_pair("SetUp", "1"));
+            pgn.push_str(&tag_pair("FEN", &fen));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        if !game.comment().is_empty() {
+            tokens.push(format!("{{{}}}", game.comment()));
+        }
+        write_line(&mut game.start().clone(), game.moves(), &mut tokens, true);
+        tokens.push(game.header("Result").unwrap_or("*").to_string());
+        pgn.push_str(&wrap(&tokens));
+        pgn
+    }
+
+    /// writes the first of the nodes, the others as variations on it, then carries on down the
+    /// line. Black's moves are numbered at the start of a line and after anything interrupting it.
+    fn write_line(board: &mut Board, nodes: &[Node], tokens: &mut Vec<String>, numbered: bool) {
+        let main = match nodes.first() {
+            Some(main) => main,
+            None => return,
+        };
+        let mut numbered = write_node(board, main, tokens, numbered);
+        for variation in &nodes[1..] {
+            let opened = tokens.len();
+            let interrupted = write_node(board, variation, tokens, true);
+            tokens[opened].insert(0, '(');
+            if board.make_move(variation.m()).is_ok() {
+                write_line(board, variation.children(), tokens, interrupted);
+                board.undo_last_move();
+            }
+            if let Some(last) = tokens.last_mut() {
+                last.push(')');
+            }
+            numbered = true;
+        }
+        if board.make_move(main.m()).is_ok() {
+            write_line(board, main.children(), tokens, numbered);
+            board.undo_last_move();
+        }
+    }
+
+    /// the move with its number if it needs one, and what was said about it. Returns whether the
+    /// next move needs its number written as something came in between.
+    fn write_node(board: &Board, node: &Node, tokens: &mut Vec<String>, numbered: bool) -> bool {
+        match board.turn() {
+            Color::White => tokens.push(format!("{}.", board.fullmove_number())),
+            Color::Black if numbered => tokens.push(format!("{}...", board.fullmove_number())),
+            Color::Black => {}
+        }
+        tokens.push(to_san(board, node.m()));
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        let mut commands = Vec::new();
+        if let Some(clock) = node.clock {
+            let seconds = clock.as_secs();
+            commands.push(format!("[%clk {}:{:02}:{:02}]", seconds / 3600, seconds / 60 % 60, seconds % 60));
+        }
+        match node.eval {
+            Some(Score::Centipawns(cp)) => commands.push(format!("[%eval {:.2}]", cp as f64 / 100.0)),
+            Some(Score::Mate(moves)) => commands.push(format!("[%eval #{}]", moves)),
+            None => {}
+        }
+        if !node.comment.is_empty() {
+            commands.push(node.comment.clone());
+        }
+        if commands.is_empty() {
+            false
+        } else {
+            tokens.push(format!("{{{}}}", commands.join(" ")));
+            true
+        }
+    }
+
+    /// reads every game in a PGN file, with its variations, comments and annotation glyphs and
+    /// any [%clk] and [%eval] commands taken out of the comments. A game that cannot be read is
+    /// an error in its place so the ones around it are still there.
+    pub fn read_pgn(text: &str) -> Vec<Result<Game, FailReason>> {
+        let mut chunks = vec!(String::new());
+        let (mut in_movetext, mut in_comment) = (false, false);
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            if !in_comment && trimmed.starts_with('[') {
+                if in_movetext {
+                    chunks.push(String::new());
+                    in_movetext = false;
+                }
+            } else if !trimmed.is_empty() && !trimmed.starts_with('%') {
+                in_movetext = true;
+                // a brace comment can run over several lines and have lines starting with [ in it
+                for c in line.chars() {
+                    match c {
+                        '}' if in_comment => in_comment = false,
+                        '{' if !in_comment => in_comment = true,
+                        ';' if !in_comment => break,
+                        _ => {}
+                    }
+                }
+            }
+            let chunk = chunks.last_mut().expect("there is always a chunk");
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+        chunks.iter().filter(|chunk| !chunk.trim().is_empty()).map(|chunk| read_game(chunk)).collect()
+    }
+
+    fn read_game(text: &str) -> Result<Game, FailReason> {
+        let mut headers = Vec::new();
+        let mut movetext = String::new();
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if movetext.trim().is_empty() && trimmed.starts_with('[') {
+                headers.push(read_tag(trimmed)?);
+            } else {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
+        let header = |name: &str| headers.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str());
+        let (variant, chess960) = match header("Variant").map(|name| name.replace(' ', "").to_lowercase()) {
+            None => (Variant::Standard, false),
+            Some(name) if name == "chess960" || name == "fischerandom" || name == "fischerrandom" => (Variant::Standard, true),
+            Some(name) if name == "fromposition" => (Variant::Standard, false),
+            Some(name) => (Variant::from_name(&name)?, false),
+        };
+        let mut start = Board::from_fen(header("FEN").unwrap_or_else(|| variant.starting_fen()))?;
+        if variant != Variant::Standard {
+            start.set_variant(variant);
+        }
+        if chess960 {
+            start.set_chess960(true);
+        }
+
+        let mut game = Game::new(start);
+        for (name, value) in &headers {
+            game.set_header(name, value);
+        }
+        // for each variation open, the move it is an alternative to and how far into it we are
+        let mut variations: Vec<(Move, usize)> = Vec::new();
+        for token in movetext_tokens(&movetext) {
+            match token {
+                MoveText::Comment(comment) => {
+                    let (comment, clock, eval) = read_commands(&comment);
+                    match game.current_mut() {
+                        Some(node) => {
+                            node.clock = clock.or(node.clock);
+                            node.eval = eval.or(node.eval);
+                            if !comment.is_empty() {
+                                if !node.comment.is_empty() {
+                                    node.comment.push(' ');
+                                }
+                                node.comment.push_str(&comment);
+                            }
+                        }
+                        None if !comment.is_empty() => {
+                            let comment = if game.comment().is_empty() { comment } else { format!("{} {}", game.comment(), comment) };
+                            game.set_comment(&comment);
+                        }
+                        None => {}
+                    }
+                }
+                MoveText::Nag(nag) => match game.current_mut() {
+                    Some(node) => node.nags.push(nag),
+                    None => return Err(FailReason::ParseError(format!("${} comes before any move", nag))),
+                },
+                MoveText::Open => {
+                    let m = game.current().map(|node| node.m())
+                        .ok_or_else(|| FailReason::ParseError(String::from("a variation has to follow the move it is played instead of")))?;
+                    game.back();
+                    variations.push((m, 0));
+                }
+                MoveText::Close => {
+                    let (m, plies) = variations.pop().ok_or_else(|| FailReason::ParseError(String::from("a ) without a ( before it")))?;
+                    for _ in 0..plies {
+                        game.back();
+                    }
+                    let index = game.continuations().iter().position(|node| node.m() == m).expect("the move varied from is still there");
+                    game.enter_variation(index);
+                }
+                MoveText::Result(result) => {
+                    if variations.is_empty() {
+                        if game.header("Result").is_none_or(|old| old == "*") {
+                            game.set_header("Result", &result);
+                        }
+                        break;
+                    }
+                }
+                MoveText::Move(san, nag) => {
+                    let m = parse_san(game.board(), &san)?;
+                    game.play(m)?;
+                    if let Some((_, plies)) = variations.last_mut() {
+                        *plies += 1;
+                    }
+                    if let (Some(nag), Some(node)) = (nag, game.current_mut()) {
+                        node.nags.push(nag);
+                    }
+                }
+            }
+        }
+        game.go_to_start();
+        Ok(game)
+    }
+
+    /// a tag pair line as in [White "Kasparov, Garry"]
+    fn read_tag(line: &str) -> Result<(String, String), FailReason> {
+        let invalid = || FailReason::ParseError(format!("\"{}\" is not a tag pair", line));
+        let inner = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')).ok_or_else(invalid)?;
+        let quote = inner.find('"').ok_or_else(invalid)?;
+        let name = inner[..quote].trim();
+        let quoted = inner[quote + 1..].trim_end().strip_suffix('"').ok_or_else(invalid)?;
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        let mut value = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            value.push(if c == '\\' { chars.next().unwrap_or('\\') } else { c });
+        }
+        Ok((name.to_string(), value))
+    }
+
+    enum MoveText {
+        Move(String, Option<u8>),
+        Comment(String),
+        Nag(u8),
+        Open,
+        Close,
+        Result(String),
+    }
+
+    /// splits movetext into moves, comments and so on, dropping the move numbers
+    fn movetext_tokens(text: &str) -> Vec<MoveText> {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+        let mut line_start = true;
+        while let Some(c) = chars.next() {
+            let at_line_start = line_start;
+            line_start = c == '\n';
+            match c {
+                c if c.is_whitespace() => {}
+                '%' if at_line_start => {
+                    chars.by_ref().find(|c| *c == '\n');
+                    line_start = true;
+                }
+                '{' => tokens.push(MoveText::Comment(chars.by_ref().take_while(|c| *c != '}').collect::<String>().trim().to_string())),
+                ';' => {
+                    tokens.push(MoveText::Comment(chars.by_ref().take_while(|c| *c != '\n').collect::<String>().trim().to_string()));
+                    line_start = true;
+                }
+                '(' => tokens.push(MoveText::Open),
+                ')' => tokens.push(MoveText::Close),
+                '$' => {
+                    let mut digits = String::new();
+                    while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                        digits.push(*digit);
+                        chars.next();
+                    }
+                    if let Ok(nag) = digits.parse() {
+                        tokens.push(MoveText::Nag(nag));
+                    }
+                }
+                c => {
+                    let mut word = c.to_string();
+                    while let Some(next) = chars.peek().filter(|c| !c.is_whitespace() && !"{}();$".contains(**c)) {
+                        word.push(*next);
+                        chars.next();
+                    }
+                    match word.as_str() {
+                        "1-0" | "0-1" | "1/2-1/2" | "*" => tokens.push(MoveText::Result(word)),
+                        _ => {
+                            // move numbers, as in 12. or 12... or stuck on the front as in 12.e4
+                            let san = match word.find('.') {
+                                Some(dot) if word[..dot].chars().all(|c| c.is_ascii_digit()) => word[dot..].trim_start_matches('.'),
+                                _ => &word[..],
+                            };
+                            let move_end = san.trim_end_matches(['!', '?']).len();
+                            let nag = match &san[move_end..] {
+                                "!" => Some(1),
+                                "?" => Some(2),
+                                "!!" => Some(3),
+                                "??" => Some(4),
+                                "!?" => Some(5),
+                                "?!" => Some(6),
+                                _ => None,
+                            };
+                            if move_end > 0 {
+                                tokens.push(MoveText::Move(san[..move_end].to_string(), nag));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        tokens
+    }
+
+    /// takes the [%clk] and [%eval] commands out of a comment, leaving the rest of it
+    fn read_commands(comment: &str) -> (String, Option<Duration>, Option<Score>) {
+        let (mut rest, mut clock, mut eval) = (String::new(), None, None);
+        let mut text = comment;
+        while let Some(open) = text.find("[%") {
+            let close = match text[open..].find(']') {
+                Some(close) => open + close,
+                None => break,
+            };
+            rest.push_str(&text[..open]);
+            let mut words = text[open + 2..close].split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("clk"), Some(time)) => {
+                    let parts: Vec<f64> = time.split(':').filter_map(|part| part.parse().ok()).collect();
+                    if !parts.is_empty() {
+                        let seconds = parts.iter().fold(0.0, |total, part| total * 60.0 + part);
+                        clock = Some(Duration::from_millis((seconds.max(0.0) * 1000.0).round() as u64));
+                    }
+                }
+                (Some("eval"), Some(score)) => {
+                    eval = match score.strip_prefix('#') {
+                        Some(moves) => moves.parse().ok().map(Score::Mate),
+                        None => score.parse::<f64>().ok().map(|pawns| Score::Centipawns((pawns * 100.0).round() as i32)),
+                    };
+                }
+                // commands we don't know about are kept as they were
+                _ => rest.push_str(&text[open..=close]),
+            }
+            text = &text[close + 1..];
+        }
+        rest.push_str(text);
+        (rest.split_whitespace().collect::<Vec<_>>().join(" "), clock, eval)
+    }
+
+    fn tag_pair(name: &str, value: &str) -> String {
+        format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// movetext lines are kept under 80 characters
+    fn wrap(tokens: &[String]) -> String {
+        let mut text = String::new();
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 79 {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(token);
+        }
+        text.push_str(&line);
+        text.push('\n');
+        text
+    }
+
+    fn piece_letter(piece: Piece) -> char {
+        piece.to_char().to_ascii_uppercase()
+    }
+
+    fn file_letter(x: i32) -> char {
+        (b'a' + x as u8) as char
+    }
+}
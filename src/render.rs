@@ -0,0 +1,117 @@
+pub mod render {
+    use crate::board::board::Board;
+    use crate::chess::{Piece, Color};
+    use crate::chess_move::chess_move::Move;
+    use crate::location::location::Location;
+    use std::fmt::{Display, Formatter};
+
+    const RESET: &str = "\x1b[0m";
+    const LAST_MOVE: &str = "\x1b[30;43m";
+    const ATTACKED: &str = "\x1b[30;41m";
+
+    /// how to draw a board as text
+    #[derive(Clone, Debug)]
+    pub struct TextStyle {
+        /// chess glyphs such as ♘ instead of letters
+        pub unicode: bool,
+        /// rank numbers down the side and file letters along the bottom
+        pub coordinates: bool,
+        /// the side whose pieces are drawn at the bottom
+        pub orientation: Color,
+        /// the squares this move went from and to are highlighted
+        pub last_move: Option<Move>,
+        /// the squares this side attacks are highlighted
+        pub attacked_by: Option<Color>,
+        /// highlight with ANSI colours, for writing to a terminal. Without them nothing is highlighted.
+        pub ansi: bool,
+    }
+
+    impl Default for TextStyle {
+        fn default() -> Self {
+            TextStyle {
+                unicode: false,
+                coordinates: true,
+                orientation: Color::White,
+                last_move: None,
+                attacked_by: None,
+                ansi: false,
+            }
+        }
+    }
+
+    /// the board as text, a rank to a line with the far side at the top
+    pub fn render(board: &Board, style: &TextStyle) -> String {
+        let files: Vec<i32> = match style.orientation {
+            Color::White => (0..board.width()).collect(),
+            Color::Black => (0..board.width()).rev().collect(),
+        };
+        let ranks: Vec<i32> = match style.orientation {
+            Color::White => (0..board.height()).rev().collect(),
+            Color::Black => (0..board.height()).collect(),
+        };
+        let label_width = board.height().to_string().len();
+
+        let mut text = String::new();
+        for y in ranks {
+            if style.coordinates {
+                text.push_str(&format!("{:>width$} ", y + 1, width = label_width));
+            }
+            let squares: Vec<String> = files.iter()
+                .map(|x| render_square(board, style, Location::new(*x, y)))
+                .collect();
+            text.push_str(&squares.join(" "));
+            text.push('\n');
+        }
+        if style.coordinates {
+            let letters: Vec<String> = files.iter().map(|x| ((b'a' + *x as u8) as char).to_string()).collect();
+            text.push_str(&format!("{:width$} {}\n", "", letters.join(" "), width = label_width));
+        }
+        text
+    }
+
+    fn render_square(board: &Board, style: &TextStyle, square: Location) -> String {
+        let symbol = match board.get_piece_from(&square) {
+            Some(piece) if style.unicode => glyph(piece).to_string(),
+            Some(piece) => piece.to_char().to_string(),
+            None if style.unicode => String::from("·"),
+            None => String::from("."),
+        };
+        if !style.ansi {
+            return symbol;
+        }
+        let moved = style.last_move.is_some_and(|m| m.from == square || m.to == square);
+        let attacked = style.attacked_by.is_some_and(|color| board.is_attacked(square, color));
+        match (moved, attacked) {
+            (true, _) => format!("{}{}{}", LAST_MOVE, symbol, RESET),
+            (false, true) => format!("{}{}{}", ATTACKED, symbol, RESET),
+            (false, false) => symbol,
+        }
+    }
+
+    /// the unicode chess symbol for the piece, fairy pieces have none so keep their letter
+    pub fn glyph(piece: Piece) -> char {
+        match piece {
+            Piece::King(Color::White) => '♔',
+            Piece::Queen(Color::White) => '♕',
+            Piece::Rook(Color::White) => '♖',
+            Piece::Bishop(Color::White) => '♗',
+            Piece::Knight(Color::White) => '♘',
+            Piece::Pawn(Color::White) => '♙',
+            Piece::King(Color::Black) => '♚',
+            Piece::Queen(Color::Black) => '♛',
+            Piece::Rook(Color::Black) => '♜',
+            Piece::Bishop(Color::Black) => '♝',
+            Piece::Knight(Color::Black) => '♞',
+            Piece::Pawn(Color::Black) => '♟',
+            Piece::Fairy(..) => piece.to_char(),
+        }
+    }
+
+    /// letters and coordinates from white's side, {:#} for unicode glyphs
+    impl Display for Board {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let style = TextStyle { unicode: f.alternate(), ..TextStyle::default() };
+            write!(f, "{}", render(self, &style))
+        }
+    }
+}
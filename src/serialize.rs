@@ -0,0 +1,209 @@
+pub mod serialize {
+    // serde support, behind the serde feature. Squares are written the way they are read out,
+    // as in "e4", and pieces as their fen letter, so the JSON is as readable as the notation.
+    // A Board is written as its fen along with its variant and whether it is chess960. A field
+    // can ask for the fen alone with #[serde(with = "chess::serialize::serialize::fen")] or for
+    // the square map with #[serde(with = "chess::serialize::serialize::square_map")].
+    use std::fmt;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::de::{self, Visitor};
+    use crate::board::board::Board;
+    use crate::chess::Piece;
+    use crate::location::location::Location;
+    use crate::variant::variant::Variant;
+
+    impl Serialize for Location {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Location {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(ParsedStr("a square such as e4", |s| Location::from_algebraic(s).map_err(|reason| format!("{:?}", reason))))
+        }
+    }
+
+    impl Serialize for Piece {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.to_char().to_string())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Piece {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(ParsedStr("a piece letter such as N or p", |s| {
+                let mut chars = s.chars();
+                match (chars.next().and_then(Piece::from_char), chars.next()) {
+                    (Some(piece), None) => Ok(piece),
+                    _ => Err(format!("\"{}\" is not a piece", s)),
+                }
+            }))
+        }
+    }
+
+    /// a board as it is written by default. The variant and chess960 are only taken from the fen
+    /// when they are left out, which it cannot always show.
+    #[derive(Serialize, Deserialize)]
+    struct FenAndVariant {
+        fen: String,
+        #[serde(default)]
+        variant: Option<String>,
+        #[serde(default)]
+        chess960: Option<bool>,
+    }
+
+    impl Serialize for Board {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            FenAndVariant {
+                fen: self.to_fen(),
+                variant: Some(self.variant().name().to_string()),
+                chess960: Some(self.is_chess960()),
+            }.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Board {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let written = FenAndVariant::deserialize(deserializer)?;
+            let mut board = Board::from_fen(&written.fen).map_err(|reason| de::Error::custom(format!("{:?}", reason)))?;
+            if let Some(name) = written.variant {
+                board.set_variant(Variant::from_name(&name).map_err(|reason| de::Error::custom(format!("{:?}", reason)))?);
+            }
+            if let Some(chess960) = written.chess960 {
+                board.set_chess960(chess960);
+            }
+            Ok(board)
+        }
+    }
+
+    /// reads a string with the given parser, for types that are written as text
+    struct ParsedStr<T>(&'static str, fn(&str) -> Result<T, String>);
+
+    impl<'de, T> Visitor<'de> for ParsedStr<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.0)
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+            (self.1)(s).map_err(E::custom)
+        }
+    }
+
+    /// a board as its fen, which is all a standard game needs. The variant is only kept as far as
+    /// the fen shows it, by pockets for crazyhouse or the checks for three-check.
+    pub mod fen {
+        use serde::{Serializer, Deserializer};
+        use crate::board::board::Board;
+        use super::ParsedStr;
+
+        pub fn serialize<S: Serializer>(board: &Board, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&board.to_fen())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+            deserializer.deserialize_str(ParsedStr("a fen", |s| Board::from_fen(s).map_err(|reason| format!("{:?}", reason))))
+        }
+    }
+
+    /// a board as an object with a map from squares to pieces and each of the other things a fen
+    /// holds in a field of its own, along with the variant and whether it is chess960
+    pub mod square_map {
+        use std::collections::BTreeMap;
+        use serde::{Serialize, Serializer, Deserialize, Deserializer};
+        use serde::de::Error;
+        use crate::board::board::Board;
+        use crate::chess::{Piece, Color};
+        use crate::location::location::Location;
+        use crate::variant::variant::Variant;
+
+        #[derive(Serialize, Deserialize)]
+        struct SquareMap {
+            width: i32,
+            height: i32,
+            squares: BTreeMap<Location, Piece>,
+            turn: Color,
+            /// as written in a fen, e.g. KQkq
+            castling: String,
+            en_passant: Option<Location>,
+            halfmove_clock: u32,
+            fullmove_number: u32,
+            variant: String,
+            #[serde(default)]
+            chess960: bool,
+            /// pieces in hand, for crazyhouse
+            #[serde(default)]
+            pockets: Vec<Piece>,
+            /// checks given by white and black, for three-check
+            #[serde(default)]
+            checks_given: [u32; 2],
+        }
+
+        pub fn serialize<S: Serializer>(board: &Board, serializer: S) -> Result<S::Ok, S::Error> {
+            let fen = board.to_fen();
+            let fields: Vec<&str> = fen.split_whitespace().collect();
+            SquareMap {
+                width: board.width(),
+                height: board.height(),
+                squares: board.pieces().collect(),
+                turn: board.turn(),
+                castling: fields[2].to_string(),
+                en_passant: Location::from_algebraic(fields[3]).ok(),
+                halfmove_clock: board.halfmove_clock(),
+                fullmove_number: board.fullmove_number(),
+                variant: board.variant().name().to_string(),
+                chess960: board.is_chess960(),
+                pockets: [Color::White, Color::Black].iter().flat_map(|color| board.pocket(*color)).collect(),
+                checks_given: [board.checks_given(Color::White), board.checks_given(Color::Black)],
+            }.serialize(serializer)
+        }
+
+        /// puts the map back together as a fen, so it is checked the same way as one
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+            let map = SquareMap::deserialize(deserializer)?;
+            let variant = Variant::from_name(&map.variant).map_err(|reason| D::Error::custom(format!("{:?}", reason)))?;
+            if let Some(square) = map.squares.keys().find(|square| !square.is_within(map.width, map.height)) {
+                return Err(D::Error::custom(format!("{} is not on a {}x{} board", square, map.width, map.height)));
+            }
+            let ranks: Vec<String> = (0..map.height).rev().map(|y| {
+                let mut rank = String::new();
+                let mut empty = 0;
+                for x in 0..map.width {
+                    match map.squares.get(&Location::new(x, y)) {
+                        Some(piece) => {
+                            if empty > 0 {
+                                rank.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            rank.push(piece.to_char());
+                        }
+                        None => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                }
+                rank
+            }).collect();
+            let mut fen = ranks.join("/");
+            if variant == Variant::Crazyhouse {
+                fen.push('[');
+                fen.extend(map.pockets.iter().map(|piece| piece.to_char()));
+                fen.push(']');
+            }
+            fen.push_str(&format!(" {} {} {}", if map.turn == Color::White { "w" } else { "b" }, map.castling,
+                                  map.en_passant.map_or_else(|| String::from("-"), |square| square.to_string())));
+            if variant == Variant::ThreeCheck {
+                fen.push_str(&format!(" +{}+{}", map.checks_given[0], map.checks_given[1]));
+            }
+            fen.push_str(&format!(" {} {}", map.halfmove_clock, map.fullmove_number));
+
+            let mut board = Board::from_fen(&fen).map_err(|reason| D::Error::custom(format!("{:?}", reason)))?;
+            board.set_variant(variant);
+            board.set_chess960(map.chess960);
+            Ok(board)
+        }
+    }
+}
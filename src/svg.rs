@@ -0,0 +1,209 @@
+pub mod svg {
+    use crate::board::board::Board;
+    use crate::chess::{Piece, Color};
+    use crate::location::location::Location;
+
+    const LIGHT: &str = "#f0d9b5";
+    const DARK: &str = "#b58863";
+    const HIGHLIGHT: &str = "#9bc700";
+    const ARROW: &str = "#15781b";
+
+    /// how to draw a board as an SVG diagram
+    #[derive(Clone, Debug)]
+    pub struct SvgStyle {
+        /// the width and height of a square in pixels
+        pub square_size: u32,
+        /// rank numbers and file letters in a margin around the board
+        pub coordinates: bool,
+        /// the side whose pieces are drawn at the bottom
+        pub orientation: Color,
+        /// drawn from the centre of the first square to the centre of the second
+        pub arrows: Vec<(Location, Location)>,
+        /// squares shaded in
+        pub highlights: Vec<Location>,
+    }
+
+    impl Default for SvgStyle {
+        fn default() -> Self {
+            SvgStyle {
+                square_size: 45,
+                coordinates: true,
+                orientation: Color::White,
+                arrows: Vec::new(),
+                highlights: Vec::new(),
+            }
+        }
+    }
+
+    /// a self contained SVG document of the board. The pieces, coordinates and the letters on fairy
+    /// pieces are all drawn with paths rather than fonts or images, so it looks the same wherever
+    /// it ends up.
+    pub fn render_svg(board: &Board, style: &SvgStyle) -> String {
+        let size = style.square_size as f64;
+        let margin = if style.coordinates { size / 2.0 } else { 0.0 };
+        let (board_width, board_height) = (board.width() as f64 * size, board.height() as f64 * size);
+        // the top left corner of a square
+        let corner = |square: Location| -> (f64, f64) {
+            let (column, row) = match style.orientation {
+                Color::White => (square.x, board.height() - 1 - square.y),
+                Color::Black => (board.width() - 1 - square.x, square.y),
+            };
+            (margin + column as f64 * size, margin + row as f64 * size)
+        };
+        let centre = |square: Location| -> (f64, f64) {
+            let (x, y) = corner(square);
+            (x + size / 2.0, y + size / 2.0)
+        };
+
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+                              board_width + 2.0 * margin, board_height + 2.0 * margin);
+        svg.push_str(&format!("<defs><marker id=\"arrowhead\" viewBox=\"0 0 10 10\" refX=\"5\" refY=\"5\" markerWidth=\"3\" markerHeight=\"3\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\" fill=\"{}\"/></marker></defs>\n", ARROW));
+        if style.coordinates {
+            svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n", board_width + 2.0 * margin, board_height + 2.0 * margin));
+        }
+
+        for x in 0..board.width() {
+            for y in 0..board.height() {
+                let square = Location::new(x, y);
+                let (left, top) = corner(square);
+                let fill = if (x + y) % 2 == 0 { DARK } else { LIGHT };
+                svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", left, top, size, size, fill));
+                if style.highlights.contains(&square) {
+                    svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.5\"/>\n", left, top, size, size, HIGHLIGHT));
+                }
+            }
+        }
+
+        if style.coordinates {
+            let scale = size / 24.0;
+            for x in 0..board.width() {
+                let (left, _) = corner(Location::new(x, 0));
+                let file = ((b'a' + x as u8) as char).to_string();
+                svg.push_str(&lettering(&file, left + size / 2.0, margin + board_height + margin / 2.0 + 2.0 * scale, scale, "#000000"));
+            }
+            for y in 0..board.height() {
+                let (_, top) = corner(Location::new(0, y));
+                svg.push_str(&lettering(&(y + 1).to_string(), margin / 2.0, top + size / 2.0 + 3.0 * scale, scale, "#000000"));
+            }
+        }
+
+        for (square, piece) in board.pieces() {
+            let (left, top) = corner(square);
+            svg.push_str(&format!("<g transform=\"translate({},{}) scale({})\">{}</g>\n", left, top, size / 45.0, piece_shapes(piece)));
+        }
+
+        for (from, to) in &style.arrows {
+            let ((x1, y1), (x2, y2)) = (centre(*from), centre(*to));
+            // stop short so the head ends up on the square rather than past its middle
+            let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().max(1.0);
+            let shorten = (size * 0.3).min(length);
+            let (x2, y2) = (x2 - (x2 - x1) / length * shorten, y2 - (y2 - y1) / length * shorten);
+            svg.push_str(&format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-opacity=\"0.8\" stroke-linecap=\"round\" marker-end=\"url(#arrowhead)\"/>\n",
+                                  x1, y1, x2, y2, ARROW, size / 6.0));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// the piece drawn in a 45 by 45 box. The standard pieces are Colin M.L. Burnett's, as used on
+    /// Wikipedia and by python-chess, and are used under the BSD licence in LICENSE-pieces. See
+    /// https://commons.wikimedia.org/wiki/Category:SVG_chess_pieces.
+    fn piece_shapes(piece: Piece) -> String {
+        let white = *piece.color() == Color::White;
+        let shapes = match (piece, white) {
+            (Piece::Pawn(_), true) => WHITE_PAWN,
+            (Piece::Pawn(_), false) => BLACK_PAWN,
+            (Piece::Knight(_), true) => WHITE_KNIGHT,
+            (Piece::Knight(_), false) => BLACK_KNIGHT,
+            (Piece::Bishop(_), true) => WHITE_BISHOP,
+            (Piece::Bishop(_), false) => BLACK_BISHOP,
+            (Piece::Rook(_), true) => WHITE_ROOK,
+            (Piece::Rook(_), false) => BLACK_ROOK,
+            (Piece::Queen(_), true) => WHITE_QUEEN,
+            (Piece::Queen(_), false) => BLACK_QUEEN,
+            (Piece::King(_), true) => WHITE_KING,
+            (Piece::King(_), false) => BLACK_KING,
+            (Piece::Fairy(fairy, _), _) => {
+                // a disc with the piece's letter on it
+                let (fill, line) = if white { ("#ffffff", "#000000") } else { ("#000000", "#ffffff") };
+                return format!("<g fill=\"{}\" stroke=\"#000000\" stroke-width=\"1.5\"><circle cx=\"22.5\" cy=\"22.5\" r=\"15\"/>{}</g>",
+                               fill, lettering(&fairy.letter.to_string(), 22.5, 28.5, 2.5, line));
+            }
+        };
+        String::from(shapes)
+    }
+
+    const WHITE_PAWN: &str = r##"<path d="M22.5 9c-2.21 0-4 1.79-4 4 0 .89.29 1.71.78 2.38C17.33 16.5 16 18.59 16 21c0 2.03.94 3.84 2.41 5.03-3 1.06-7.41 5.55-7.41 13.47h23c0-7.92-4.41-12.41-7.41-13.47 1.47-1.19 2.41-3 2.41-5.03 0-2.41-1.33-4.5-3.28-5.62.49-.67.78-1.49.78-2.38 0-2.21-1.79-4-4-4z" fill="#ffffff" stroke="#000000" stroke-width="1.5" stroke-linecap="round"/>"##;
+    const BLACK_PAWN: &str = r##"<path d="M22.5 9c-2.21 0-4 1.79-4 4 0 .89.29 1.71.78 2.38C17.33 16.5 16 18.59 16 21c0 2.03.94 3.84 2.41 5.03-3 1.06-7.41 5.55-7.41 13.47h23c0-7.92-4.41-12.41-7.41-13.47 1.47-1.19 2.41-3 2.41-5.03 0-2.41-1.33-4.5-3.28-5.62.49-.67.78-1.49.78-2.38 0-2.21-1.79-4-4-4z" fill="#000000" stroke="#000000" stroke-width="1.5" stroke-linecap="round"/>"##;
+    const WHITE_KNIGHT: &str = r##"<g fill="none" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22 10c10.5 1 16.5 8 16 29H15c0-9 10-6.5 8-21" fill="#ffffff"/><path d="M24 18c.38 2.91-5.55 7.37-8 9-3 2-2.82 4.34-5 4-1.042-.94 1.41-3.04 0-3-1 0 .19 1.23-1 2-1 0-4.003 1-4-4 0-2 6-12 6-12s1.89-1.9 2-3.5c-.73-.994-.5-2-.5-3 1-1 3 2.5 3 2.5h2s.78-1.992 2.5-3c1 0 1 3 1 3" fill="#ffffff"/><path d="M9.5 25.5a.5.5 0 1 1-1 0 .5.5 0 1 1 1 0zm5.433-9.75a.5 1.5 30 1 1-.866-.5.5 1.5 30 1 1 .866.5z" fill="#000000"/></g>"##;
+    const BLACK_KNIGHT: &str = r##"<g fill="none" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22 10c10.5 1 16.5 8 16 29H15c0-9 10-6.5 8-21" fill="#000000"/><path d="M24 18c.38 2.91-5.55 7.37-8 9-3 2-2.82 4.34-5 4-1.042-.94 1.41-3.04 0-3-1 0 .19 1.23-1 2-1 0-4.003 1-4-4 0-2 6-12 6-12s1.89-1.9 2-3.5c-.73-.994-.5-2-.5-3 1-1 3 2.5 3 2.5h2s.78-1.992 2.5-3c1 0 1 3 1 3" fill="#000000"/><path d="M9.5 25.5a.5.5 0 1 1-1 0 .5.5 0 1 1 1 0zm5.433-9.75a.5 1.5 30 1 1-.866-.5.5 1.5 30 1 1 .866.5z" fill="#ececec" stroke="#ececec"/><path d="M24.55 10.4l-.45 1.45.5.15c3.15 1 5.65 2.49 7.9 6.75S35.75 29.06 35.25 39l-.05.5h2.25l.05-.5c.5-10.06-.88-16.85-3.25-21.34-2.37-4.49-5.79-6.64-9.19-7.16l-.51-.1z" fill="#ececec" stroke="none"/></g>"##;
+    const WHITE_BISHOP: &str = r##"<g fill="none" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><g fill="#ffffff" stroke-linecap="butt"><path d="M9 36c3.39-.97 10.11.43 13.5-2 3.39 2.43 10.11 1.03 13.5 2 0 0 1.65.54 3 2-.68.97-1.65.99-3 .5-3.39-.97-10.11.46-13.5-1-3.39 1.46-10.11.03-13.5 1-1.354.49-2.323.47-3-.5 1.354-1.94 3-2 3-2zM15 32c2.5 2.5 12.5 2.5 15 0 .5-1.5 0-2 0-2 0-2.5-2.5-4-2.5-4 5.5-1.5 6-11.5-5-15.5-11 4-10.5 14-5 15.5 0 0-2.5 1.5-2.5 4 0 0-.5.5 0 2zM25 8a2.5 2.5 0 1 1-5 0 2.5 2.5 0 1 1 5 0z"/></g><path d="M17.5 26h10M15 30h15m-7.5-14.5v5M20 18h5" stroke-linejoin="miter"/></g>"##;
+    const BLACK_BISHOP: &str = r##"<g fill="none" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M9 36c3.39-.97 10.11.43 13.5-2 3.39 2.43 10.11 1.03 13.5 2 0 0 1.65.54 3 2-.68.97-1.65.99-3 .5-3.39-.97-10.11.46-13.5-1-3.39 1.46-10.11.03-13.5 1-1.354.49-2.323.47-3-.5 1.354-1.94 3-2 3-2zm6-4c2.5 2.5 12.5 2.5 15 0 .5-1.5 0-2 0-2 0-2.5-2.5-4-2.5-4 5.5-1.5 6-11.5-5-15.5-11 4-10.5 14-5 15.5 0 0-2.5 1.5-2.5 4 0 0-.5.5 0 2zM25 8a2.5 2.5 0 1 1-5 0 2.5 2.5 0 1 1 5 0z" fill="#000000" stroke-linecap="butt"/><path d="M17.5 26h10M15 30h15m-7.5-14.5v5M20 18h5" stroke="#ffffff" stroke-linejoin="miter"/></g>"##;
+    const WHITE_ROOK: &str = r##"<g fill="#ffffff" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M9 39h27v-3H9v3zm3-3v-4h21v4H12zm-1-22V9h4v2h5V9h5v2h5V9h4v5" stroke-linecap="butt"/><path d="M34 14l-3 3H14l-3-3"/><path d="M31 17v12.5H14V17" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M31 29.5l1.5 2.5h-20l1.5-2.5"/><path d="M11 14h23" fill="none" stroke-linejoin="miter"/></g>"##;
+    const BLACK_ROOK: &str = r##"<g fill="#000000" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M9 39h27v-3H9v3zm3.5-7l1.5-2.5h17l1.5 2.5h-20zm-.5 4v-4h21v4H12z" stroke-linecap="butt"/><path d="M14 29.5v-13h17v13H14z" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M14 16.5L11 14h23l-3 2.5H14zM11 14V9h4v2h5V9h5v2h5V9h4v5H11z" stroke-linecap="butt"/><path d="M12 35.5h21m-20-4h19m-18-2h17m-17-13h17M11 14h23" fill="none" stroke="#ffffff" stroke-width="1" stroke-linejoin="miter"/></g>"##;
+    const WHITE_QUEEN: &str = r##"<g fill="#ffffff" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M8 12a2 2 0 1 1-4 0 2 2 0 1 1 4 0zm16.5-4.5a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM41 12a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM16 8.5a2 2 0 1 1-4 0 2 2 0 1 1 4 0zM33 9a2 2 0 1 1-4 0 2 2 0 1 1 4 0z"/><path d="M9 26c8.5-1.5 21-1.5 27 0l2-12-7 11V11l-5.5 13.5-3-15-3 15-5.5-14V25L7 14l2 12zm0 0c0 2 1.5 2 2.5 4 1 1.5 1 1 .5 3.5-1.5 1-1.5 2.5-1.5 2.5-1.5 1.5.5 2.5.5 2.5 6.5 1 16.5 1 23 0 0 0 1.5-1 0-2.5 0 0 .5-1.5-1-2.5-.5-2.5-.5-2 .5-3.5 1-2 2.5-2 2.5-4-8.5-1.5-18.5-1.5-27 0z" stroke-linecap="butt"/><path d="M11.5 30c3.5-1 18.5-1 22 0M12 33.5c6-1 15-1 21 0" fill="none"/></g>"##;
+    const BLACK_QUEEN: &str = r##"<g fill="#000000" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><g stroke="none"><circle cx="6" cy="12" r="2.75"/><circle cx="14" cy="9" r="2.75"/><circle cx="22.5" cy="8" r="2.75"/><circle cx="31" cy="9" r="2.75"/><circle cx="39" cy="12" r="2.75"/></g><path d="M9 26c8.5-1.5 21-1.5 27 0l2.5-12.5L31 25l-.3-14.1-5.2 13.6-3-14.5-3 14.5-5.2-13.6L14 25 6.5 13.5 9 26zm0 0c0 2 1.5 2 2.5 4 1 1.5 1 1 .5 3.5-1.5 1-1.5 2.5-1.5 2.5-1.5 1.5.5 2.5.5 2.5 6.5 1 16.5 1 23 0 0 0 1.5-1 0-2.5 0 0 .5-1.5-1-2.5-.5-2.5-.5-2 .5-3.5 1-2 2.5-2 2.5-4-8.5-1.5-18.5-1.5-27 0z" stroke-linecap="butt"/><path d="M11 38.5a35 35 1 0 0 23 0" fill="none" stroke-linecap="butt"/><path d="M11 29a35 35 1 0 1 23 0m-21.5 2.5h20m-21 3a35 35 1 0 0 22 0m-23 3a35 35 1 0 0 24 0" fill="none" stroke="#ffffff"/></g>"##;
+    const WHITE_KING: &str = r##"<g fill="none" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22.5 11.63V6M20 8h5" stroke-linejoin="miter"/><path d="M22.5 25s4.5-7.5 3-10.5c0 0-1-2.5-3-2.5s-3 2.5-3 2.5c-1.5 3 3 10.5 3 10.5" fill="#ffffff" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M11.5 37c5.5 3.5 15.5 3.5 21 0v-7s9-4.5 6-10.5c-4-6.5-13.5-3.5-16 4V27v-3.5c-3.5-7.5-13-10.5-16-4-3 6 5 10 5 10V37z" fill="#ffffff"/><path d="M11.5 30c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0"/></g>"##;
+    const BLACK_KING: &str = r##"<g fill="none" fill-rule="evenodd" stroke="#000000" stroke-width="1.5" stroke-linecap="round" stroke-linejoin="round"><path d="M22.5 11.63V6" stroke-linejoin="miter"/><path d="M22.5 25s4.5-7.5 3-10.5c0 0-1-2.5-3-2.5s-3 2.5-3 2.5c-1.5 3 3 10.5 3 10.5" fill="#000000" stroke-linecap="butt" stroke-linejoin="miter"/><path d="M11.5 37c5.5 3.5 15.5 3.5 21 0v-7s9-4.5 6-10.5c-4-6.5-13.5-3.5-16 4V27v-3.5c-3.5-7.5-13-10.5-16-4-3 6 5 10 5 10V37z" fill="#000000"/><path d="M20 8h5" stroke-linejoin="miter"/><path d="M32 29.5s8.5-4 6.03-9.65C34.15 14 25 18 22.5 24.5l.01 2.1-.01-2.1C20 18 9.906 14 6.997 19.85c-2.497 5.65 4.853 9 4.853 9M11.5 30c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0m-21 3.5c5.5-3 15.5-3 21 0" stroke="#ffffff"/></g>"##;
+
+    /// how far along each character of lettering starts, in glyph units
+    const ADVANCE: f64 = 5.0;
+
+    /// the strokes of a lowercase letter or a digit, 4 units wide with the baseline at 0. Digits
+    /// and tall letters reach up to -6, the rest to -4, and tails hang down to 2.
+    fn glyph(c: char) -> Option<&'static str> {
+        let strokes = match c {
+            'a' => "M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2 M4,-4 L4,0",
+            'b' => "M0,-6 L0,0 M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2",
+            'c' => "M3.4,-3.4 A2,2 0 1 0 3.4,-0.6",
+            'd' => "M4,-6 L4,0 M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2",
+            'e' => "M0,-2 L4,-2 A2,2 0 1 0 3.4,-0.6",
+            'f' => "M3.5,-6 A1.5,1.5 0 0 0 2,-4.5 L2,0 M0.5,-4 L3.5,-4",
+            'g' => "M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2 M4,-4 L4,0.5 A1.5,1.5 0 0 1 2.5,2 L0.5,2",
+            'h' => "M0,-6 L0,0 M0,-2 A2,2 0 0 1 4,-2 L4,0",
+            'i' => "M2,-4 L2,0 M2,-5.6 L2,-5.5",
+            'j' => "M2.5,-4 L2.5,0.5 A1.5,1.5 0 0 1 1,2 L0.5,2 M2.5,-5.6 L2.5,-5.5",
+            'k' => "M0,-6 L0,0 M3.5,-4 L0,-1.5 M1.2,-2.4 L4,0",
+            'l' => "M1.5,-6 L1.5,-1 A1,1 0 0 0 2.5,0 L3,0",
+            'm' => "M0,-4 L0,0 M0,-3 A1,1 0 0 1 2,-3 L2,0 M2,-3 A1,1 0 0 1 4,-3 L4,0",
+            'n' => "M0,-4 L0,0 M0,-2 A2,2 0 0 1 4,-2 L4,0",
+            'o' => "M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2",
+            'p' => "M0,-4 L0,2 M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2",
+            'q' => "M4,-4 L4,2 M0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2",
+            'r' => "M0,-4 L0,0 M0,-2 A2,2 0 0 1 3.4,-3.4",
+            's' => "M3.5,-3.5 C2.5,-4.5 0.5,-4.2 0.5,-3 C0.5,-1.8 3.5,-2.2 3.5,-1 C3.5,0.2 1.5,0.5 0.5,-0.5",
+            't' => "M2,-5.5 L2,-1 A1,1 0 0 0 3,0 L3.5,0 M0.5,-4 L3.5,-4",
+            'u' => "M0,-4 L0,-2 A2,2 0 0 0 4,-2 M4,-4 L4,0",
+            'v' => "M0,-4 L2,0 L4,-4",
+            'w' => "M0,-4 L1,0 L2,-3 L3,0 L4,-4",
+            'x' => "M0,-4 L4,0 M4,-4 L0,0",
+            'y' => "M0,-4 L2,0 M4,-4 L1,2",
+            'z' => "M0,-4 L4,-4 L0,0 L4,0",
+            '0' => "M2,-6 A2,3 0 1 0 2,0 A2,3 0 1 0 2,-6",
+            '1' => "M1,-5 L2.5,-6 L2.5,0 M1,0 L4,0",
+            '2' => "M0,-4.5 A2,1.5 0 0 1 4,-4.5 C4,-2.5 0,-2 0,0 L4,0",
+            '3' => "M0,-6 L4,-6 L1.5,-3.5 A2,2 0 1 1 0,-0.8",
+            '4' => "M3,0 L3,-6 L0,-1.5 L4,-1.5",
+            '5' => "M4,-6 L0.5,-6 L0.2,-3.3 A2,2 0 1 1 0,-0.8",
+            '6' => "M3.5,-6 C1.5,-5.5 0,-4 0,-2 A2,2 0 1 0 4,-2 A2,2 0 1 0 0,-2",
+            '7' => "M0,-6 L4,-6 L1.5,0",
+            '8' => "M2,-3.2 A1.7,1.4 0 1 1 2,-6 A1.7,1.4 0 1 1 2,-3.2 A2,1.6 0 1 0 2,0 A2,1.6 0 1 0 2,-3.2",
+            '9' => "M0.5,0 C2.5,-0.5 4,-2 4,-4 A2,2 0 1 0 0,-4 A2,2 0 1 0 4,-4",
+            _ => return None,
+        };
+        Some(strokes)
+    }
+
+    /// text drawn as strokes, centred on x and sitting on the baseline, a glyph unit being scale
+    /// pixels. Characters without a glyph are left as a gap.
+    fn lettering(text: &str, x: f64, baseline: f64, scale: f64, colour: &str) -> String {
+        let width = text.chars().count() as f64 * ADVANCE - (ADVANCE - 4.0);
+        let left = x - width * scale / 2.0;
+        text.chars().enumerate()
+            .filter_map(|(i, c)| glyph(c).map(|strokes| (i, strokes)))
+            .map(|(i, strokes)| format!("<path transform=\"translate({},{}) scale({})\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"0.8\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n",
+                                        left + i as f64 * ADVANCE * scale, baseline, scale, strokes, colour))
+            .collect()
+    }
+}
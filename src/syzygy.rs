@@ -0,0 +1,1078 @@
+pub mod syzygy {
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+    use std::fmt::{Debug, Formatter};
+    use std::fs::{self, File};
+    use std::io::{self, Read, Seek, SeekFrom};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use crate::board::board::Board;
+    use crate::chess::{Piece, Color};
+    use crate::chess_move::chess_move::Move;
+    use crate::variant::variant::{Variant, Outcome};
+
+    const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+    const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+    // the flags each part of a table starts with, all but the last only used by DTZ tables
+    const STM: u8 = 1;
+    const MAPPED: u8 = 2;
+    const WIN_PLIES: u8 = 4;
+    const LOSS_PLIES: u8 = 8;
+    const WIDE: u8 = 16;
+    const SINGLE_VALUE: u8 = 128;
+
+    /// the most pieces any table has
+    const MAX_PIECES: usize = 7;
+
+    /// how a DTZ table's values map onto the win, loss, cursed win and blessed loss maps
+    const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+    /// the result of a position with perfect play, from the side to move's point of view
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Wdl {
+        Loss,
+        /// lost, but the fifty move rule saves the game
+        BlessedLoss,
+        Draw,
+        /// won, but not before the fifty move rule draws it
+        CursedWin,
+        Win,
+    }
+
+    impl Wdl {
+        fn from_value(value: i32) -> Wdl {
+            match value {
+                v if v <= -2 => Wdl::Loss,
+                -1 => Wdl::BlessedLoss,
+                0 => Wdl::Draw,
+                1 => Wdl::CursedWin,
+                _ => Wdl::Win,
+            }
+        }
+
+        fn value(self) -> i32 {
+            self as i32 - 2
+        }
+    }
+
+    /// how many of each piece a side has, as pawns, knights, bishops, rooks, queens and kings
+    type Material = [[u8; 6]; 2];
+
+    /// the tables are named with the stronger side first, as in KRPvKR
+    fn table_name(material: &Material) -> String {
+        let side = |counts: &[u8; 6]| {
+            (0..6).rev().map(|kind| "PNBRQK".chars().nth(kind).unwrap().to_string().repeat(counts[kind] as usize)).collect::<String>()
+        };
+        format!("{}v{}", side(&material[0]), side(&material[1]))
+    }
+
+    fn material_from_name(name: &str) -> Option<Material> {
+        let mut material = [[0; 6]; 2];
+        let mut sides = name.split('v');
+        for counts in material.iter_mut() {
+            for letter in sides.next()?.chars() {
+                counts["PNBRQK".find(letter)?] += 1;
+            }
+        }
+        if sides.next().is_some() || material[0][5] != 1 || material[1][5] != 1 {
+            return None;
+        }
+        Some(material)
+    }
+
+    /// the pieces on the board by square, a1 being 0 and h8 63, numbered as the tables number
+    /// them: 1 to 6 for a white pawn to king and 8 more for black
+    struct Position {
+        pieces: Vec<(usize, u8)>,
+        material: Material,
+        turn: Color,
+    }
+
+    impl Position {
+        /// only positions of standard chess on an 8x8 board without castling rights are in the tables
+        fn from_board(board: &Board) -> Option<Position> {
+            if board.variant() != Variant::Standard || board.width() != 8 || board.height() != 8 {
+                return None;
+            }
+            let mut pieces = Vec::new();
+            let mut material = [[0; 6]; 2];
+            for (square, piece) in board.pieces() {
+                let kind = match piece {
+                    Piece::Pawn(_) => 0,
+                    Piece::Knight(_) => 1,
+                    Piece::Bishop(_) => 2,
+                    Piece::Rook(_) => 3,
+                    Piece::Queen(_) => 4,
+                    Piece::King(_) => 5,
+                    Piece::Fairy(..) => return None,
+                };
+                let color = *piece.color() as usize;
+                material[color][kind] += 1;
+                pieces.push(((square.y * 8 + square.x) as usize, kind as u8 + 1 + 8 * color as u8));
+            }
+            if pieces.len() > MAX_PIECES || !board.castling_rights().is_empty() {
+                return None;
+            }
+            pieces.sort_unstable();
+            Some(Position { pieces, material, turn: board.turn() })
+        }
+    }
+
+    /// the tables the pieces of a position are numbered into an index with
+    struct Indexing {
+        binomial: [[u64; 64]; 7],
+        map_pawns: [u64; 64],
+        lead_pawn_idx: [[u64; 64]; 6],
+        lead_pawns_size: [[u64; 4]; 6],
+        map_a1d1d4: [u64; 64],
+        map_b1h1h7: [u64; 64],
+        map_kk: [[u64; 64]; 10],
+    }
+
+    fn file_of(square: usize) -> usize {
+        square & 7
+    }
+
+    fn rank_of(square: usize) -> usize {
+        square >> 3
+    }
+
+    /// how far a square is off the a1-h8 diagonal, positive above it
+    fn off_diagonal(square: usize) -> i32 {
+        rank_of(square) as i32 - file_of(square) as i32
+    }
+
+    fn flip_diagonal(square: usize) -> usize {
+        ((square >> 3) | (square << 3)) & 63
+    }
+
+    impl Indexing {
+        fn new() -> Self {
+            let mut indexing = Indexing {
+                binomial: [[0; 64]; 7],
+                map_pawns: [0; 64],
+                lead_pawn_idx: [[0; 64]; 6],
+                lead_pawns_size: [[0; 4]; 6],
+                map_a1d1d4: [0; 64],
+                map_b1h1h7: [0; 64],
+                map_kk: [[0; 64]; 10],
+            };
+
+            // the squares below the diagonal in the a1-d1-d4 triangle, then those on it
+            let mut code = 0;
+            for square in 0..64 {
+                if off_diagonal(square) < 0 {
+                    indexing.map_b1h1h7[square] = code;
+                    code += 1;
+                }
+            }
+            let mut code = 0;
+            let mut on_diagonal = Vec::new();
+            for square in [0, 1, 2, 3, 9, 10, 11, 18, 19, 27] {
+                if off_diagonal(square) < 0 {
+                    indexing.map_a1d1d4[square] = code;
+                    code += 1;
+                } else if off_diagonal(square) == 0 {
+                    on_diagonal.push(square);
+                }
+            }
+            for square in on_diagonal {
+                indexing.map_a1d1d4[square] = code;
+                code += 1;
+            }
+
+            // the two kings, the first in the triangle where b1 has the code 0 that the squares
+            // outside it have too, numbered so that placements with both on the diagonal come last
+            let mut both_on_diagonal = Vec::new();
+            let mut code = 0;
+            for first in 0..10 {
+                for s1 in 0..64 {
+                    if indexing.map_a1d1d4[s1] != first || (first == 0 && s1 != 1) {
+                        continue;
+                    }
+                    for s2 in 0..64 {
+                        let touching = (file_of(s1) as i32 - file_of(s2) as i32).abs() <= 1
+                            && (rank_of(s1) as i32 - rank_of(s2) as i32).abs() <= 1;
+                        if touching || (off_diagonal(s1) == 0 && off_diagonal(s2) > 0) {
+                            continue;
+                        } else if off_diagonal(s1) == 0 && off_diagonal(s2) == 0 {
+                            both_on_diagonal.push((first, s2));
+                        } else {
+                            indexing.map_kk[first as usize][s2] = code;
+                            code += 1;
+                        }
+                    }
+                }
+            }
+            for (first, s2) in both_on_diagonal {
+                indexing.map_kk[first as usize][s2] = code;
+                code += 1;
+            }
+
+            indexing.binomial[0] = [1; 64];
+            for k in 1..7 {
+                for n in 1..64 {
+                    indexing.binomial[k][n] = indexing.binomial[k - 1][n - 1] + indexing.binomial[k][n - 1];
+                }
+            }
+
+            // the leading pawn is the one furthest towards the middle files and the seventh rank
+            let mut available = 47;
+            for lead_pawns in 1..6 {
+                for file in 0..4 {
+                    let mut idx = 0;
+                    for rank in 1..7 {
+                        let square = rank * 8 + file;
+                        if lead_pawns == 1 {
+                            indexing.map_pawns[square] = available;
+                            indexing.map_pawns[square ^ 7] = available - 1;
+                            available = available.saturating_sub(2);
+                        }
+                        indexing.lead_pawn_idx[lead_pawns][square] = idx;
+                        idx += indexing.binomial[lead_pawns - 1][indexing.map_pawns[square] as usize];
+                    }
+                    indexing.lead_pawns_size[lead_pawns][file] = idx;
+                }
+            }
+            indexing
+        }
+    }
+
+    /// the layout of one side to move, and with pawns one file of the leading pawn, of a table
+    #[derive(Clone, Default)]
+    struct Part {
+        flags: u8,
+        /// the pieces in the order the index is built from them
+        pieces: [u8; MAX_PIECES],
+        group_len: [usize; MAX_PIECES + 1],
+        group_idx: [u64; MAX_PIECES + 1],
+        block_size: u64,
+        span: u64,
+        blocks: u64,
+        /// the shortest code length, or the value of every position for a single valued part
+        min_sym_len: u8,
+        /// for each code length from the shortest, the first symbol of that length
+        lowest_sym: Vec<u16>,
+        base64: Vec<u64>,
+        /// how many values less one each symbol stands for
+        symlen: Vec<u32>,
+        /// the two symbols each symbol stands for in turn
+        btree: Vec<(u16, u16)>,
+        /// where the sparse index, block lengths and value maps are in the header
+        sparse_index: usize,
+        sparse_index_size: u64,
+        block_lengths: usize,
+        block_lengths_size: u64,
+        map: [usize; 4],
+        /// where the compressed values start in the file
+        data: u64,
+    }
+
+    #[derive(Clone, Copy)]
+    enum ParseError {
+        /// more of the file is needed
+        Short,
+        Invalid(&'static str),
+    }
+
+    struct Bytes<'a> {
+        data: &'a [u8],
+        complete: bool,
+    }
+
+    impl Bytes<'_> {
+        fn u8(&self, at: usize) -> Result<u8, ParseError> {
+            match self.data.get(at) {
+                Some(byte) => Ok(*byte),
+                None if self.complete => Err(ParseError::Invalid("the table ends too soon")),
+                None => Err(ParseError::Short),
+            }
+        }
+
+        fn u16(&self, at: usize) -> Result<u16, ParseError> {
+            Ok(u16::from_le_bytes([self.u8(at)?, self.u8(at + 1)?]))
+        }
+
+        fn u32(&self, at: usize) -> Result<u32, ParseError> {
+            Ok(u32::from_le_bytes([self.u8(at)?, self.u8(at + 1)?, self.u8(at + 2)?, self.u8(at + 3)?]))
+        }
+    }
+
+    fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+        Some(u16::from_le_bytes([*data.get(at)?, *data.get(at + 1)?]))
+    }
+
+    fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+        Some(u32::from_le_bytes([*data.get(at)?, *data.get(at + 1)?, *data.get(at + 2)?, *data.get(at + 3)?]))
+    }
+
+    /// a WDL or DTZ table, of which only the header is read up front and the compressed values
+    /// as they are needed
+    struct Table {
+        file: Mutex<File>,
+        header: Vec<u8>,
+        dtz: bool,
+        /// the pieces of the side the file is named after first, then of the other
+        material: Material,
+        symmetric: bool,
+        has_pawns: bool,
+        has_unique_pieces: bool,
+        /// the pawns of the side the leading pawn belongs to, then of the other
+        pawn_count: [u8; 2],
+        piece_count: usize,
+        /// for each side to move and then each file the leading pawn is on
+        parts: Vec<Vec<Part>>,
+    }
+
+    impl Table {
+        fn open(path: &Path, dtz: bool, material: Material, indexing: &Indexing) -> io::Result<Table> {
+            let mut file = File::open(path)?;
+            let length = file.metadata()?.len() as usize;
+            let mut header = Vec::new();
+            let mut wanted = length.min(1 << 16);
+            let mut table = Table {
+                file: Mutex::new(file.try_clone()?),
+                header: Vec::new(),
+                dtz,
+                material,
+                symmetric: material[0] == material[1],
+                has_pawns: material[0][0] + material[1][0] > 0,
+                has_unique_pieces: material.iter().any(|counts| counts[..5].contains(&1)),
+                pawn_count: [0; 2],
+                piece_count: material.iter().map(|counts| counts.iter().map(|count| *count as usize).sum::<usize>()).sum(),
+                parts: Vec::new(),
+            };
+            let white_leads = material[1][0] == 0 || (material[0][0] > 0 && material[1][0] >= material[0][0]);
+            table.pawn_count = if white_leads { [material[0][0], material[1][0]] } else { [material[1][0], material[0][0]] };
+            loop {
+                let start = header.len();
+                header.resize(wanted, 0);
+                file.read_exact(&mut header[start..])?;
+                let bytes = Bytes { data: &header, complete: header.len() == length };
+                match table.parse(&bytes, indexing) {
+                    Ok(parts) => {
+                        table.parts = parts;
+                        break;
+                    }
+                    Err(ParseError::Short) => wanted = length.min(wanted * 2),
+                    Err(ParseError::Invalid(reason)) => {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), reason)));
+                    }
+                }
+            }
+            table.header = header;
+            Ok(table)
+        }
+
+        fn parse(&self, bytes: &Bytes, indexing: &Indexing) -> Result<Vec<Vec<Part>>, ParseError> {
+            let magic = if self.dtz { DTZ_MAGIC } else { WDL_MAGIC };
+            if (0..4).map(|i| bytes.u8(i)).collect::<Result<Vec<u8>, ParseError>>()? != magic {
+                return Err(ParseError::Invalid("not a syzygy table"));
+            }
+            let flags = bytes.u8(4)?;
+            if (flags & 2 != 0) != self.has_pawns || (flags & 1 != 0) == self.symmetric {
+                return Err(ParseError::Invalid("the table does not have the pieces its name says"));
+            }
+
+            let sides = if self.dtz || self.symmetric { 1 } else { 2 };
+            let files = if self.has_pawns { 4 } else { 1 };
+            let mut parts = vec!(vec!(Part::default(); files); sides);
+            let both_have_pawns = self.has_pawns && self.pawn_count[1] > 0;
+            let mut at = 5;
+            for file in 0..files {
+                let first = bytes.u8(at)?;
+                let second = if both_have_pawns { bytes.u8(at + 1)? } else { 0xff };
+                let order = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+                at += 1 + both_have_pawns as usize;
+                for k in 0..self.piece_count {
+                    let byte = bytes.u8(at)?;
+                    for (side, part) in parts.iter_mut().enumerate() {
+                        part[file].pieces[k] = if side == 0 { byte & 0xf } else { byte >> 4 };
+                    }
+                    at += 1;
+                }
+                for (side, part) in parts.iter_mut().enumerate() {
+                    self.set_groups(&mut part[file], order[side], file, indexing)?;
+                }
+            }
+            at += at & 1;
+
+            for file in 0..files {
+                for part in parts.iter_mut() {
+                    at = set_sizes(&mut part[file], bytes, at)?;
+                }
+            }
+            if self.dtz {
+                for part in parts[0].iter_mut() {
+                    if part.flags & MAPPED == 0 {
+                        continue;
+                    }
+                    if part.flags & WIDE != 0 {
+                        at += at & 1;
+                        for map in part.map.iter_mut() {
+                            *map = at + 2;
+                            at += 2 * bytes.u16(at)? as usize + 2;
+                        }
+                    } else {
+                        for map in part.map.iter_mut() {
+                            *map = at + 1;
+                            at += bytes.u8(at)? as usize + 1;
+                        }
+                    }
+                }
+                at += at & 1;
+            }
+            for file in 0..files {
+                for part in parts.iter_mut() {
+                    part[file].sparse_index = at;
+                    at += part[file].sparse_index_size as usize * 6;
+                }
+            }
+            for file in 0..files {
+                for part in parts.iter_mut() {
+                    part[file].block_lengths = at;
+                    at += part[file].block_lengths_size as usize * 2;
+                }
+            }
+            // all of the header has to be read before the values can be found
+            if at > 0 {
+                bytes.u8(at - 1)?;
+            }
+            let mut data = at as u64;
+            for file in 0..files {
+                for part in parts.iter_mut() {
+                    data = (data + 63) & !63;
+                    part[file].data = data;
+                    data += part[file].blocks * part[file].block_size;
+                }
+            }
+            Ok(parts)
+        }
+
+        /// splits the pieces into the groups that are indexed together and works out how many
+        /// positions each group multiplies the index by
+        fn set_groups(&self, part: &mut Part, order: [u8; 2], file: usize, indexing: &Indexing) -> Result<(), ParseError> {
+            let invalid = ParseError::Invalid("the pieces of the table do not fit together");
+            let mut n = 0;
+            let mut first_len: i32 = if self.has_pawns { 0 } else if self.has_unique_pieces { 3 } else { 2 };
+            part.group_len[0] = 1;
+            for i in 1..self.piece_count {
+                first_len -= 1;
+                if first_len > 0 || part.pieces[i] == part.pieces[i - 1] {
+                    part.group_len[n] += 1;
+                } else {
+                    n += 1;
+                    part.group_len[n] = 1;
+                }
+            }
+            n += 1;
+            part.group_len[n] = 0;
+
+            let both_have_pawns = self.has_pawns && self.pawn_count[1] > 0;
+            let mut next = if both_have_pawns { 2 } else { 1 };
+            let mut free = 64 - part.group_len[0] - if both_have_pawns { part.group_len[1] } else { 0 };
+            let mut idx: u64 = 1;
+            let mut k = 0;
+            while next < n || k == order[0] as usize || k == order[1] as usize {
+                if k == order[0] as usize {
+                    part.group_idx[0] = idx;
+                    idx *= if self.has_pawns {
+                        *indexing.lead_pawns_size.get(part.group_len[0]).ok_or(invalid)?.get(file).unwrap_or(&0)
+                    } else if self.has_unique_pieces {
+                        31332
+                    } else {
+                        462
+                    };
+                } else if k == order[1] as usize {
+                    part.group_idx[1] = idx;
+                    idx *= indexing.binomial.get(part.group_len[1]).ok_or(invalid)?[48 - part.group_len[0]];
+                } else {
+                    if next >= n || part.group_len[next] > free || part.group_len[next] >= 7 {
+                        return Err(invalid);
+                    }
+                    part.group_idx[next] = idx;
+                    idx *= indexing.binomial[part.group_len[next]][free];
+                    free -= part.group_len[next];
+                    next += 1;
+                }
+                k += 1;
+            }
+            part.group_idx[n] = idx;
+            Ok(())
+        }
+
+        /// the value a table has for a position with the side to move and the pieces as they
+        /// stand, or None when it has to be looked up with the other side to move, which is
+        /// how DTZ tables stored for only one of them work
+        fn probe(&self, position: &Position, wdl: Wdl, indexing: &Indexing) -> Option<Option<i32>> {
+            let black_to_move = position.turn == Color::Black;
+            let black_stronger = position.material != self.material;
+            let flip = (self.symmetric && black_to_move) || black_stronger;
+            let flip_color = if flip { 8 } else { 0 };
+            let flip_squares = if flip { 56 } else { 0 };
+            let stm = (flip != black_to_move) as usize;
+
+            let mut squares = Vec::new();
+            let mut pieces = Vec::new();
+            let mut file = 0;
+            let mut lead_count = 0;
+            if self.has_pawns {
+                // the pawns the table numbers first, of the color they are in this position
+                let lead = self.parts[0][0].pieces[0] ^ flip_color;
+                for (square, piece) in &position.pieces {
+                    if *piece == lead {
+                        squares.push(square ^ flip_squares);
+                        pieces.push(*piece ^ flip_color);
+                    }
+                }
+                lead_count = squares.len();
+                let furthest = (0..lead_count).fold(0, |best, i| {
+                    if indexing.map_pawns[squares[i]] > indexing.map_pawns[squares[best]] { i } else { best }
+                });
+                squares.swap(0, furthest);
+                file = file_of(squares[0]).min(7 - file_of(squares[0]));
+            }
+
+            if self.dtz {
+                let flags = self.parts[0][file].flags;
+                if (flags & STM) as usize != stm && (self.has_pawns || !self.symmetric) {
+                    return Some(None);
+                }
+            }
+
+            for (square, piece) in &position.pieces {
+                if !self.has_pawns || *piece != self.parts[0][0].pieces[0] ^ flip_color {
+                    squares.push(square ^ flip_squares);
+                    pieces.push(*piece ^ flip_color);
+                }
+            }
+            let size = squares.len();
+            let part = &self.parts[if self.dtz { 0 } else { stm % self.parts.len() }][file];
+
+            // put the pieces in the order the table has them
+            for i in lead_count..size.saturating_sub(1) {
+                for j in i + 1..size {
+                    if part.pieces[i] == pieces[j] {
+                        pieces.swap(i, j);
+                        squares.swap(i, j);
+                        break;
+                    }
+                }
+            }
+
+            if file_of(squares[0]) > 3 {
+                for square in squares.iter_mut() {
+                    *square ^= 7;
+                }
+            }
+
+            let mut idx;
+            if self.has_pawns {
+                idx = indexing.lead_pawn_idx[lead_count][squares[0]];
+                squares[1..lead_count].sort_by_key(|square| indexing.map_pawns[*square]);
+                for (i, square) in squares[..lead_count].iter().enumerate().skip(1) {
+                    idx += indexing.binomial[i][indexing.map_pawns[*square] as usize];
+                }
+            } else {
+                if rank_of(squares[0]) > 3 {
+                    for square in squares.iter_mut() {
+                        *square ^= 56;
+                    }
+                }
+                for i in 0..part.group_len[0] {
+                    let off = off_diagonal(squares[i]);
+                    if off == 0 {
+                        continue;
+                    }
+                    if off > 0 {
+                        for square in squares[i..].iter_mut() {
+                            *square = flip_diagonal(*square);
+                        }
+                    }
+                    break;
+                }
+
+                if self.has_unique_pieces {
+                    let s = &squares;
+                    let adjust1 = (s[1] > s[0]) as u64;
+                    let adjust2 = (s[2] > s[0]) as u64 + (s[2] > s[1]) as u64;
+                    let rank = |square: usize| rank_of(square) as u64;
+                    idx = if off_diagonal(s[0]) != 0 {
+                        (indexing.map_a1d1d4[s[0]] * 63 + (s[1] as u64 - adjust1)) * 62 + s[2] as u64 - adjust2
+                    } else if off_diagonal(s[1]) != 0 {
+                        (6 * 63 + rank(s[0]) * 28 + indexing.map_b1h1h7[s[1]]) * 62 + s[2] as u64 - adjust2
+                    } else if off_diagonal(s[2]) != 0 {
+                        6 * 63 * 62 + 4 * 28 * 62 + rank(s[0]) * 7 * 28 + (rank(s[1]) - adjust1) * 28 + indexing.map_b1h1h7[s[2]]
+                    } else {
+                        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(s[0]) * 7 * 6 + (rank(s[1]) - adjust1) * 6 + (rank(s[2]) - adjust2)
+                    };
+                } else {
+                    idx = indexing.map_kk[indexing.map_a1d1d4[squares[0]] as usize][squares[1]];
+                }
+            }
+            idx *= part.group_idx[0];
+
+            let mut group_start = part.group_len[0];
+            let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+            let mut next = 1;
+            while part.group_len[next] != 0 {
+                let len = part.group_len[next];
+                squares[group_start..group_start + len].sort_unstable();
+                let mut n = 0;
+                for i in 0..len {
+                    let square = squares[group_start + i];
+                    let below = squares[..group_start].iter().filter(|other| square > **other).count();
+                    let free = square.checked_sub(below + if remaining_pawns { 8 } else { 0 })?;
+                    n += indexing.binomial[i + 1][free];
+                }
+                remaining_pawns = false;
+                idx += n * part.group_idx[next];
+                group_start += len;
+                next += 1;
+            }
+
+            let value = self.decompress(part, idx)?;
+            if !self.dtz {
+                return Some(Some(value as i32 - 2));
+            }
+            let mut value = value as i32;
+            if part.flags & MAPPED != 0 {
+                let map = part.map[WDL_MAP[(wdl.value() + 2) as usize]];
+                value = if part.flags & WIDE != 0 {
+                    read_u16(&self.header, map + 2 * value as usize)? as i32
+                } else {
+                    *self.header.get(map + value as usize)? as i32
+                };
+            }
+            // values are in moves unless the table says they are in plies, and the fifty move
+            // rule makes them count in plies for cursed wins and blessed losses
+            let plies = match wdl {
+                Wdl::Win => part.flags & WIN_PLIES != 0,
+                Wdl::Loss => part.flags & LOSS_PLIES != 0,
+                _ => false,
+            };
+            if !plies {
+                value *= 2;
+            }
+            Some(Some(value + 1))
+        }
+
+        /// the value with this index, found through the sparse index, the block lengths and the
+        /// canonical Huffman code of the part
+        fn decompress(&self, part: &Part, idx: u64) -> Option<u32> {
+            if part.flags & SINGLE_VALUE != 0 {
+                return Some(part.min_sym_len as u32);
+            }
+            let entry = part.sparse_index + 6 * (idx / part.span) as usize;
+            let mut block = read_u32(&self.header, entry)? as i64;
+            let mut offset = read_u16(&self.header, entry + 4)? as i64 + (idx % part.span) as i64 - (part.span / 2) as i64;
+            let block_length = |block: i64| -> Option<i64> {
+                if block < 0 || block as u64 >= part.block_lengths_size {
+                    return None;
+                }
+                read_u16(&self.header, part.block_lengths + 2 * block as usize).map(|length| length as i64)
+            };
+            while offset < 0 {
+                block -= 1;
+                offset += block_length(block)? + 1;
+            }
+            while offset > block_length(block)? {
+                offset -= block_length(block)? + 1;
+                block += 1;
+            }
+
+            // the end of a block can be past the end of the file, and reading runs a few bytes ahead
+            let mut data = vec!(0u8; part.block_size as usize + 8);
+            {
+                let mut file = self.file.lock().unwrap();
+                file.seek(SeekFrom::Start(part.data + block as u64 * part.block_size)).ok()?;
+                let mut read = 0;
+                while read < part.block_size as usize {
+                    match file.read(&mut data[read..part.block_size as usize]) {
+                        Ok(0) => break,
+                        Ok(n) => read += n,
+                        Err(_) => return None,
+                    }
+                }
+            }
+
+            let mut offset = offset as u64;
+            let mut buffer = u64::from_be_bytes(data[..8].try_into().ok()?);
+            let mut next = 8;
+            let mut buffered = 64;
+            let mut symbol;
+            loop {
+                let mut len = 0;
+                while buffer < *part.base64.get(len)? {
+                    len += 1;
+                }
+                symbol = (((buffer - part.base64[len]) >> (64 - len - part.min_sym_len as usize)) as u16)
+                    .wrapping_add(part.lowest_sym[len]) as usize;
+                let count = *part.symlen.get(symbol)? as u64 + 1;
+                if offset < count {
+                    break;
+                }
+                offset -= count;
+                len += part.min_sym_len as usize;
+                buffer = if len >= 64 { 0 } else { buffer << len };
+                buffered -= len as i32;
+                if buffered <= 32 {
+                    buffered += 32;
+                    let word = u32::from_be_bytes(data.get(next..next + 4)?.try_into().ok()?);
+                    buffer |= (word as u64) << (64 - buffered);
+                    next += 4;
+                }
+            }
+            while part.symlen[symbol] != 0 {
+                let (left, right) = part.btree[symbol];
+                let count = *part.symlen.get(left as usize)? as u64 + 1;
+                if offset < count {
+                    symbol = left as usize;
+                } else {
+                    offset -= count;
+                    symbol = right as usize;
+                }
+                part.symlen.get(symbol)?;
+            }
+            Some(part.btree[symbol].0 as u32)
+        }
+    }
+
+    /// reads how the values of a part are compressed, returning where the next part starts
+    fn set_sizes(part: &mut Part, bytes: &Bytes, mut at: usize) -> Result<usize, ParseError> {
+        part.flags = bytes.u8(at)?;
+        at += 1;
+        if part.flags & SINGLE_VALUE != 0 {
+            part.min_sym_len = bytes.u8(at)?;
+            return Ok(at + 1);
+        }
+        let tb_size = part.group_idx[part.group_len.iter().position(|len| *len == 0).unwrap_or(0)];
+        let (block_shift, span_shift) = (bytes.u8(at)?, bytes.u8(at + 1)?);
+        if block_shift >= 32 || span_shift >= 32 {
+            return Err(ParseError::Invalid("the blocks of the table are too big"));
+        }
+        part.block_size = 1 << block_shift;
+        part.span = 1 << span_shift;
+        part.sparse_index_size = tb_size.div_ceil(part.span);
+        let padding = bytes.u8(at + 2)? as u64;
+        part.blocks = bytes.u32(at + 3)? as u64;
+        part.block_lengths_size = part.blocks + padding;
+        at += 7;
+
+        let max_sym_len = bytes.u8(at)?;
+        part.min_sym_len = bytes.u8(at + 1)?;
+        at += 2;
+        if part.min_sym_len == 0 || max_sym_len < part.min_sym_len || max_sym_len > 32 {
+            return Err(ParseError::Invalid("the table's code lengths make no sense"));
+        }
+        let lengths = (max_sym_len - part.min_sym_len + 1) as usize;
+        part.lowest_sym = (0..lengths).map(|i| bytes.u16(at + 2 * i)).collect::<Result<_, _>>()?;
+        at += 2 * lengths;
+
+        // the lowest code of each length, left aligned, so a code's length is found by comparing
+        part.base64 = vec!(0; lengths);
+        for i in (0..lengths - 1).rev() {
+            part.base64[i] = part.base64[i + 1]
+                .wrapping_add(part.lowest_sym[i] as u64)
+                .wrapping_sub(part.lowest_sym[i + 1] as u64) / 2;
+        }
+        for (i, base) in part.base64.iter_mut().enumerate() {
+            *base <<= 64 - i - part.min_sym_len as usize;
+        }
+
+        let symbols = bytes.u16(at)? as usize;
+        at += 2;
+        part.btree = (0..symbols).map(|s| {
+            let (b0, b1, b2) = (bytes.u8(at + 3 * s)? as u16, bytes.u8(at + 3 * s + 1)? as u16, bytes.u8(at + 3 * s + 2)? as u16);
+            Ok((((b1 & 0xf) << 8) | b0, (b2 << 4) | (b1 >> 4)))
+        }).collect::<Result<_, ParseError>>()?;
+        part.symlen = vec!(0; symbols);
+        let mut visited = vec!(false; symbols);
+        for symbol in 0..symbols {
+            set_symlen(part, symbol, &mut visited)?;
+        }
+        Ok(at + 3 * symbols + (symbols & 1))
+    }
+
+    /// a symbol stands for one value if it is a leaf of the tree, otherwise for all of those of
+    /// the two it is made of
+    fn set_symlen(part: &mut Part, symbol: usize, visited: &mut [bool]) -> Result<(), ParseError> {
+        if visited[symbol] {
+            return Ok(());
+        }
+        visited[symbol] = true;
+        let (left, right) = part.btree[symbol];
+        if right == 0xfff {
+            return Ok(());
+        }
+        let (left, right) = (left as usize, right as usize);
+        if left >= part.symlen.len() || right >= part.symlen.len() {
+            return Err(ParseError::Invalid("the table's code refers to a symbol it does not have"));
+        }
+        set_symlen(part, left, visited)?;
+        set_symlen(part, right, visited)?;
+        part.symlen[symbol] = part.symlen[left] + part.symlen[right] + 1;
+        Ok(())
+    }
+
+    /// the DTZ of a position where the best move captures or pushes a pawn, which resets the
+    /// fifty move counter
+    fn dtz_before_zeroing(wdl: i32) -> i32 {
+        match wdl {
+            2 => 1,
+            1 => 101,
+            -1 => -101,
+            -2 => -1,
+            _ => 0,
+        }
+    }
+
+    fn is_zeroing(board: &Board, m: &Move) -> bool {
+        board.is_capture(m) || matches!(board.get_piece_from(&m.from), Some(Piece::Pawn(_)))
+    }
+
+    type Cache = Mutex<HashMap<String, Option<Arc<Table>>>>;
+
+    /// Syzygy tables found in one or more directories. Tables are opened the first time a
+    /// position needs them and kept open from then on.
+    pub struct Tablebase {
+        wdl_files: HashMap<String, PathBuf>,
+        dtz_files: HashMap<String, PathBuf>,
+        max_pieces: usize,
+        indexing: Indexing,
+        wdl: Cache,
+        dtz: Cache,
+    }
+
+    impl Debug for Tablebase {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Tablebase")
+                .field("wdl_tables", &self.wdl_files.len())
+                .field("dtz_tables", &self.dtz_files.len())
+                .field("max_pieces", &self.max_pieces)
+                .finish()
+        }
+    }
+
+    impl Default for Tablebase {
+        fn default() -> Self {
+            Tablebase::new()
+        }
+    }
+
+    impl Tablebase {
+        /// a tablebase without any tables, which still knows king against king is drawn
+        pub fn new() -> Self {
+            Tablebase {
+                wdl_files: HashMap::new(),
+                dtz_files: HashMap::new(),
+                max_pieces: 0,
+                indexing: Indexing::new(),
+                wdl: Mutex::new(HashMap::new()),
+                dtz: Mutex::new(HashMap::new()),
+            }
+        }
+
+        /// the tables in a directory, which need not have any
+        pub fn open<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+            let mut tablebase = Tablebase::new();
+            tablebase.add_directory(directory)?;
+            Ok(tablebase)
+        }
+
+        /// adds the .rtbw and .rtbz files of a directory, returning how many there were
+        pub fn add_directory<P: AsRef<Path>>(&mut self, directory: P) -> io::Result<usize> {
+            let mut found = 0;
+            for entry in fs::read_dir(directory)? {
+                let path = entry?.path();
+                let (name, extension) = match (path.file_stem().and_then(|s| s.to_str()), path.extension().and_then(|s| s.to_str())) {
+                    (Some(name), Some(extension)) => (name.to_string(), extension),
+                    _ => continue,
+                };
+                let material = match material_from_name(&name) {
+                    Some(material) => material,
+                    None => continue,
+                };
+                let files = match extension {
+                    "rtbw" => &mut self.wdl_files,
+                    "rtbz" => &mut self.dtz_files,
+                    _ => continue,
+                };
+                let pieces = material.iter().flatten().map(|count| *count as usize).sum::<usize>();
+                if pieces > MAX_PIECES {
+                    continue;
+                }
+                files.insert(name, path.clone());
+                self.max_pieces = self.max_pieces.max(pieces);
+                found += 1;
+            }
+            Ok(found)
+        }
+
+        /// the most pieces of any table found
+        pub fn max_pieces(&self) -> usize {
+            self.max_pieces
+        }
+
+        /// whether the position could be in the tables: standard chess without castling rights
+        /// and no more pieces than the biggest table has
+        pub fn covers(&self, board: &Board) -> bool {
+            let pieces = board.pieces().count();
+            (pieces <= self.max_pieces || pieces == 2) && Position::from_board(board).is_some()
+        }
+
+        fn table(&self, dtz: bool, position: &Position) -> Option<Arc<Table>> {
+            let (files, cache) = if dtz { (&self.dtz_files, &self.dtz) } else { (&self.wdl_files, &self.wdl) };
+            let reversed = [position.material[1], position.material[0]];
+            let (name, material) = [position.material, reversed].iter()
+                .map(|material| (table_name(material), *material))
+                .find(|(name, _)| files.contains_key(name))?;
+            let mut cache = cache.lock().unwrap();
+            cache.entry(name.clone())
+                .or_insert_with(|| Table::open(&files[&name], dtz, material, &self.indexing).ok().map(Arc::new))
+                .clone()
+        }
+
+        fn probe_wdl_table(&self, board: &Board) -> Option<i32> {
+            let position = Position::from_board(board)?;
+            if position.pieces.len() == 2 {
+                return Some(0);
+            }
+            self.table(false, &position)?.probe(&position, Wdl::Draw, &self.indexing)?
+        }
+
+        /// looks at the captures, and the pawn moves as well when zeroing is set, since the
+        /// tables leave out positions where a capture is best. Returns the value and whether
+        /// the best move is one that resets the fifty move counter.
+        fn search(&self, board: &mut Board, zeroing: bool) -> Option<(i32, bool)> {
+            let moves = board.legal_moves();
+            let mut best = -2;
+            let mut looked_at = 0;
+            for m in &moves {
+                if !board.is_capture(m) && (!zeroing || !is_zeroing(board, m)) {
+                    continue;
+                }
+                looked_at += 1;
+                board.play(*m);
+                let value = self.search(board, false).map(|(value, _)| -value);
+                board.undo_last_move();
+                let value = value?;
+                if value > best {
+                    best = value;
+                    if value >= 2 {
+                        return Some((value, true));
+                    }
+                }
+            }
+            let all_looked_at = looked_at > 0 && looked_at == moves.len();
+            let value = if all_looked_at { best } else { self.probe_wdl_table(board)? };
+            if best >= value {
+                return Some((best, best > 0 || all_looked_at));
+            }
+            Some((value, false))
+        }
+
+        fn dtz(&self, board: &mut Board) -> Option<i32> {
+            let (wdl, zeroing) = self.search(board, true)?;
+            if wdl == 0 {
+                return Some(0);
+            }
+            if zeroing {
+                return Some(dtz_before_zeroing(wdl));
+            }
+            let position = Position::from_board(board)?;
+            if let Some(dtz) = self.table(true, &position)?.probe(&position, Wdl::from_value(wdl), &self.indexing)? {
+                return Some((dtz + if wdl.abs() == 1 { 100 } else { 0 }) * wdl.signum());
+            }
+
+            // the table only has the other side to move, so look one move ahead
+            let mut best = i32::MAX;
+            for m in board.legal_moves() {
+                let zeroing = is_zeroing(board, &m);
+                board.play(m);
+                let dtz = if zeroing {
+                    self.search(board, false).map(|(value, _)| -dtz_before_zeroing(value))
+                } else {
+                    self.dtz(board).map(|dtz| -dtz)
+                };
+                let mates = dtz == Some(1) && board.legal_moves().is_empty() && board.is_in_check(&board.turn());
+                board.undo_last_move();
+                let mut dtz = dtz?;
+                if mates {
+                    best = 1;
+                }
+                if !zeroing {
+                    dtz += dtz.signum();
+                }
+                if dtz < best && dtz.signum() == wdl.signum() {
+                    best = dtz;
+                }
+            }
+            Some(if best == i32::MAX { -1 } else { best })
+        }
+
+        /// whether the side to move wins, draws or loses with perfect play
+        pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+            if !self.covers(board) {
+                return None;
+            }
+            self.search(&mut board.clone(), false).map(|(value, _)| Wdl::from_value(value))
+        }
+
+        /// how many plies it takes to the next capture or pawn move with perfect play, positive
+        /// when the side to move wins and negative when it loses. Over 100 means the fifty move
+        /// rule comes first, and 0 is a draw.
+        pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+            if !self.covers(board) {
+                return None;
+            }
+            self.dtz(&mut board.clone())
+        }
+
+        /// the move that keeps the best result, winning as fast as possible towards the next
+        /// capture or pawn move within the fifty move rule and holding out as long as possible
+        /// when lost. Also gives the DTZ after the move, counted from this position.
+        pub fn best_move(&self, board: &Board) -> Option<(Move, i32)> {
+            if !self.covers(board) {
+                return None;
+            }
+            let halfmove_clock = board.halfmove_clock() as i32;
+            let mut board = board.clone();
+            let mut best: Option<(Move, i32, (i32, i32))> = None;
+            for m in board.legal_moves() {
+                let zeroing = is_zeroing(&board, &m);
+                board.play(m);
+                let dtz = if zeroing {
+                    self.search(&mut board, false).map(|(value, _)| dtz_before_zeroing(-value))
+                } else {
+                    self.dtz(&mut board).map(|dtz| -dtz + (-dtz).signum())
+                };
+                let mates = board.legal_moves().is_empty() && board.is_in_check(&board.turn());
+                board.undo_last_move();
+                let dtz = if mates { 1 } else { dtz? };
+                let rank = if dtz > 0 {
+                    (if dtz + halfmove_clock <= 99 { 4 } else { 3 }, -dtz)
+                } else if dtz == 0 {
+                    (2, 0)
+                } else {
+                    (1, -dtz)
+                };
+                if best.is_none_or(|(_, _, best)| rank > best) {
+                    best = Some((m, dtz, rank));
+                }
+            }
+            best.map(|(m, dtz, _)| (m, dtz))
+        }
+
+        /// the result of the game with perfect play, a win the fifty move rule takes away
+        /// counting as a draw
+        pub fn outcome(&self, board: &Board) -> Option<Outcome> {
+            Some(match self.probe_wdl(board)? {
+                Wdl::Win => Outcome::Win(board.turn()),
+                Wdl::Loss => Outcome::Win(board.turn().opposite()),
+                _ => Outcome::Draw,
+            })
+        }
+    }
+}
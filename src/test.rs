@@ -1,8 +1,28 @@
 mod test {
-    use crate::board::board::Board;
+    use crate::board::board::{Board, STARTING_FEN};
     use crate::chess::{Piece, Color, Movable};
     use crate::location::location::Location;
-    use crate::chess_move::chess_move::Move;
+    use crate::chess_move::chess_move::{Move, encode_moves, decode_moves};
+    use crate::engine::engine::{Searcher, SearchLimits, Score, search_parallel, analyse_parallel};
+    use crate::transposition::transposition::{TranspositionTable, TableEntry, Bound};
+    use crate::uci::uci::Uci;
+    use crate::xboard::xboard::XBoard;
+    use crate::time_manager::time_manager::{MockClock, TimeControl, TimeManager};
+    use crate::variant::variant::{Variant, Outcome};
+    use crate::render::render::{render, TextStyle};
+    use crate::svg::svg::{render_svg, SvgStyle};
+    use crate::pgn::pgn::{to_san, parse_san, write_pgn, write_game, read_pgn};
+    use crate::game::game::Game;
+    use crate::tui::tui::{Tui, Input, parse_input};
+    use crate::clock::clock::{ChessClock, Control, Stage, Delay, TimedGame};
+    use crate::eco::eco::Openings;
+    use crate::syzygy::syzygy::{Tablebase, Wdl};
+    use crate::polyglot::polyglot::{Book, BookEntry, BookBuilder, MoveStats, polyglot_key, encode_move};
+    use crate::fairy::fairy::{FairyPiece, Range};
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc;
+    use std::time::Duration;
 
     #[test]
     fn test_create_game() {
@@ -230,11 +250,12 @@ mod test {
 
         board.place(piece, starting_attacker).unwrap();
         board.place(target, starting_target).unwrap();
+        Tester::place_kings(&mut board);
 
         board.make_move(Move::new(starting_target, ending_target)).unwrap();
         board.make_move(Move::new(starting_attacker, ending_attacker)).unwrap();
 
-        board.get_piece_from(&ending_target).unwrap_none();
+        assert!(board.get_piece_from(&ending_target).is_none());
     }
 
     #[test]
@@ -248,6 +269,7 @@ mod test {
 
         board.place(piece, starting_attacker).unwrap();
         board.place(target, starting_target).unwrap();
+        Tester::place_kings(&mut board);
 
         board.make_move(Move::new(starting_attacker, ending_attacker)).unwrap_err();
     }
@@ -300,6 +322,7 @@ mod test {
         let rook = Piece::Rook(Color::White);
         board.place(rook, Location { x: 7, y: 0 }).unwrap();
         board.place(king, Location { x: 4, y: 0 }).unwrap();
+        board.place(Piece::King(Color::Black), Location { x: 4, y: 7 }).unwrap();
         board.make_move(Move::new(Location { x: 4, y: 0 }, Location { x: 6, y: 0 })).unwrap();
 
         match board.get_piece_from(&Location { x: 6, y: 0 }).unwrap() {
@@ -320,6 +343,7 @@ mod test {
         let rook = Piece::Rook(Color::White);
         board.place(rook, Location { x: 7, y: 0 }).unwrap();
         board.place(king, Location { x: 4, y: 0 }).unwrap();
+        board.place(Piece::King(Color::Black), Location { x: 4, y: 7 }).unwrap();
 
         board.make_move(Move::new(Location { x: 4, y: 0 }, Location { x: 5, y: 0 })).unwrap();
 
@@ -335,6 +359,7 @@ mod test {
         let rook = Piece::Rook(Color::Black);
         board.place(rook, Location { x: 0, y: 7 }).unwrap();
         board.place(king, Location { x: 4, y: 7 }).unwrap();
+        board.place(Piece::King(Color::White), Location { x: 4, y: 0 }).unwrap();
 
         board.make_move(Move::new(Location { x: 4, y: 7 }, Location { x: 4, y: 6 })).unwrap();
 
@@ -350,6 +375,7 @@ mod test {
         let rook = Piece::Rook(Color::Black);
         board.place(rook, Location { x: 0, y: 7 }).unwrap();
         board.place(king, Location { x: 4, y: 7 }).unwrap();
+        board.place(Piece::King(Color::White), Location { x: 4, y: 0 }).unwrap();
         board.make_move(Move::new(Location { x: 4, y: 7 }, Location { x: 2, y: 7 })).expect("should be a valid castle");
 
         match board.get_piece_from(&Location { x: 2, y: 7 }).expect("king should be here") {
@@ -370,6 +396,7 @@ mod test {
         let rook = Piece::Rook(Color::White);
         board.place(king, Location::new(0, 0)).unwrap();
         board.place(rook, Location::new(1, 1)).unwrap();
+        Tester::place_kings(&mut board);
         board.make_move(Move::new(Location::new(0, 0), Location::new(0, 1))).unwrap_err();
     }
 
@@ -380,15 +407,1278 @@ mod test {
         let rook = Piece::Rook(Color::White);
         board.place(king, Location::new(0, 0)).unwrap();
         board.place(rook, Location::new(1, 1)).unwrap();
+        Tester::place_kings(&mut board);
         board.make_move(Move::new(Location::new(0, 0), Location::new(0, 1))).unwrap_err();
     }
 
+    #[test]
+    fn test_rook_blocked_moving_backwards() {
+        let mut board = Board::new();
+        board.place(Piece::Rook(Color::White), Location::new(7, 0)).unwrap();
+        board.place(Piece::Pawn(Color::White), Location::new(3, 0)).unwrap();
+        Tester::place_kings(&mut board);
+        board.make_move(Move::new(Location::new(7, 0), Location::new(0, 0))).unwrap_err();
+    }
+
+    #[test]
+    fn test_pawn_cannot_take_forward() {
+        let piece = Piece::Pawn(Color::White);
+        let target = Piece::Pawn(Color::Black);
+        let from = Location { x: 3, y: 3 };
+        let to = Location { x: 3, y: 4 };
+        Tester::place_target_and_piece_then_move_illegal(piece, target, from, to);
+    }
+
+    #[test]
+    fn test_castle_through_check_illegal() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K1r1 w Q - 0 1").unwrap();
+        board.make_move(Move::new(Location::new(4, 0), Location::new(2, 0))).unwrap_err();
+        let mut board = Board::from_fen("4kr2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        board.make_move(Move::new(Location::new(4, 0), Location::new(6, 0))).unwrap_err();
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        for fen in &[STARTING_FEN, KIWIPETE, "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), *fen);
+        }
+        Board::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err().unwrap();
+    }
+
+    #[test]
+    fn test_fen_after_moves() {
+        let mut board = Board::starting_position();
+        board.make_move(Move::from_coordinate_notation("e2e4", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        board.make_move(Move::from_coordinate_notation("g8f6", Color::Black).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2");
+    }
+
+    #[test]
+    fn test_crazyhouse_captures_go_to_the_pocket() {
+        let mut board = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR[] w KQkq - 0 2").unwrap();
+        assert_eq!(board.variant(), Variant::Crazyhouse);
+        board.make_move(Move::from_coordinate_notation("e4d5", Color::White).unwrap()).unwrap();
+        assert_eq!(board.pocket(Color::White), vec!(Piece::Pawn(Color::White)));
+        assert_eq!(board.to_fen(), "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR[P] b KQkq - 0 2");
+        board.make_move(Move::from_coordinate_notation("d8d5", Color::Black).unwrap()).unwrap();
+        board.make_move(Move::from_coordinate_notation("P@e4", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/4P3/8/PPPP1PPP/RNBQKBNR[p] b KQkq - 0 3");
+        board.undo_last_move();
+        assert_eq!(board.to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3");
+        // there is nothing but a pawn in white's pocket, and pawns cannot go on the last rank
+        board.make_move(Move::from_coordinate_notation("N@e4", Color::White).unwrap()).unwrap_err();
+        board.make_move(Move::from_coordinate_notation("P@e8", Color::White).unwrap()).unwrap_err();
+        Board::starting_position().make_move(Move::from_coordinate_notation("P@e4", Color::White).unwrap()).unwrap_err();
+    }
+
+    #[test]
+    fn test_crazyhouse_promoted_pieces_go_back_to_pawns() {
+        let fen = "r3k3/1P6/8/8/8/8/8/4K3[] w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        board.make_move(Move::from_coordinate_notation("b7b8q", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "rQ~2k3/8/8/8/8/8/8/4K3[] b - - 0 1");
+        board.make_move(Move::from_coordinate_notation("a8b8", Color::Black).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "1r2k3/8/8/8/8/8/8/4K3[p] w - - 0 2");
+        board.undo_last_move();
+        board.undo_last_move();
+        assert_eq!(board.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_crazyhouse_drops_block_check() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/r3K3[Nn] w - - 0 1").unwrap();
+        let moves: Vec<String> = board.legal_moves().iter().map(|m| m.to_string()).collect();
+        for drop in &["N@b1", "N@c1", "N@d1"] {
+            assert!(moves.contains(&drop.to_string()), "{} should block the check", drop);
+        }
+        assert!(!moves.contains(&String::from("N@e4")));
+
+        let best = Searcher::new(Arc::new(AtomicBool::new(false)))
+            .search(&board, SearchLimits { depth: Some(2), ..SearchLimits::default() }, |_| {})
+            .unwrap();
+        assert!(board.legal_moves().contains(&best));
+    }
+
+    #[test]
+    fn test_atomic_captures_explode() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3";
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_variant(Variant::Atomic);
+        board.make_move(Move::from_coordinate_notation("f3e5", Color::White).unwrap()).unwrap();
+        // the knight and the pawn it took are gone, the pawns next to e5 survive
+        assert_eq!(board.to_fen(), "rnbqkbnr/ppp2ppp/8/3p4/4P3/8/PPPP1PPP/RNBQKB1R b KQkq - 0 3");
+        board.undo_last_move();
+        assert_eq!(board.to_fen(), fen);
+
+        let mut board = Board::from_fen("4k3/3pq3/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+        board.set_variant(Variant::Atomic);
+        board.make_move(Move::from_coordinate_notation("e1e7", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "8/3p4/8/8/8/8/8/6K1 b - - 0 1");
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+        assert!(!board.is_in_check(&Color::Black));
+        assert!(board.legal_moves().is_empty());
+        assert!(!board.is_stalemate());
+    }
+
+    #[test]
+    fn test_atomic_king_rules() {
+        // kings cannot take, and kings next to each other cannot be checked
+        let mut board = Board::from_fen("8/8/8/8/8/8/2kq4/3K4 w - - 0 1").unwrap();
+        board.set_variant(Variant::Atomic);
+        assert!(!board.is_in_check(&Color::White));
+        board.make_move(Move::from_coordinate_notation("d1d2", Color::White).unwrap()).unwrap_err();
+
+        // taking next to your own king blows it up, so that is not allowed
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/3p4/3QK3 w - - 0 1").unwrap();
+        board.set_variant(Variant::Atomic);
+        board.make_move(Move::from_coordinate_notation("d1d2", Color::White).unwrap()).unwrap_err();
+
+        // the search goes for blowing up the king
+        let mut board = Board::from_fen("3qk3/8/8/8/8/8/8/3RK3 w - - 0 1").unwrap();
+        board.set_variant(Variant::Atomic);
+        let best = Searcher::new(Arc::new(AtomicBool::new(false)))
+            .search(&board, SearchLimits { depth: Some(2), ..SearchLimits::default() }, |_| {})
+            .unwrap();
+        assert_eq!(best.to_string(), "d1d8");
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/4K3/8/8 w - - 0 1").unwrap();
+        board.set_variant(Variant::KingOfTheHill);
+        assert_eq!(board.variant_outcome(), None);
+        board.make_move(Move::from_coordinate_notation("e3d4", Color::White).unwrap()).unwrap();
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+        assert!(board.legal_moves().is_empty());
+        assert!(!board.is_stalemate());
+    }
+
+    #[test]
+    fn test_three_check() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1+3 0 3";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.variant(), Variant::ThreeCheck);
+        assert_eq!(board.checks_given(Color::White), 2);
+        assert_eq!(board.to_fen(), fen);
+        board.make_move(Move::from_coordinate_notation("f1b5", Color::White).unwrap()).unwrap();
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+        assert_eq!(board.to_fen(), "rnbqkbnr/ppp2ppp/8/1B1pp3/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 0+3 1 3");
+        board.undo_last_move();
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +2+0").unwrap().checks_given(Color::White), 2);
+    }
+
+    #[test]
+    fn test_racing_kings() {
+        let mut board = Board::from_fen(Variant::RacingKings.starting_fen()).unwrap();
+        board.set_variant(Variant::RacingKings);
+        assert_eq!(board.legal_moves().len(), 21);
+
+        // giving check is not allowed
+        let mut board = Board::from_fen("8/8/8/8/8/2k5/8/R6K w - - 0 1").unwrap();
+        board.set_variant(Variant::RacingKings);
+        board.make_move(Move::from_coordinate_notation("a1a3", Color::White).unwrap()).unwrap_err();
+        board.make_move(Move::from_coordinate_notation("a1a2", Color::White).unwrap()).unwrap();
+
+        let mut board = Board::from_fen("8/7K/8/k7/8/8/8/8 w - - 0 1").unwrap();
+        board.set_variant(Variant::RacingKings);
+        board.make_move(Move::from_coordinate_notation("h7h8", Color::White).unwrap()).unwrap();
+        // black is too far behind to catch up
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+
+        // but one rank behind, black gets a move to draw
+        let mut board = Board::from_fen("8/k6K/8/8/8/8/8/8 w - - 0 1").unwrap();
+        board.set_variant(Variant::RacingKings);
+        board.make_move(Move::from_coordinate_notation("h7h8", Color::White).unwrap()).unwrap();
+        assert_eq!(board.variant_outcome(), None);
+        board.make_move(Move::from_coordinate_notation("a7a6", Color::Black).unwrap()).unwrap();
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+        board.undo_last_move();
+        board.make_move(Move::from_coordinate_notation("a7b8", Color::Black).unwrap()).unwrap();
+        assert_eq!(board.variant_outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn test_antichess_captures_are_compulsory() {
+        let mut board = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w - - 0 2").unwrap();
+        board.set_variant(Variant::Antichess);
+        let moves: Vec<String> = board.legal_moves().iter().map(|m| m.to_string()).collect();
+        assert_eq!(moves, vec!("e4d5"));
+        assert_eq!(board.legal_moves_from(Location::new(6, 0)), Vec::new());
+        board.make_move(Move::from_coordinate_notation("g1f3", Color::White).unwrap()).unwrap_err();
+        board.make_move(Move::from_coordinate_notation("e4d5", Color::White).unwrap()).unwrap();
+        // the queen has to take back, and the king is just another piece
+        board.make_move(Move::from_coordinate_notation("d8d5", Color::Black).unwrap()).unwrap();
+        assert!(!board.is_in_check(&Color::White));
+    }
+
+    #[test]
+    fn test_antichess_win_conditions() {
+        let mut board = Board::from_fen("8/8/8/8/8/8/1p6/8 b - - 0 1").unwrap();
+        board.set_variant(Variant::Antichess);
+        board.make_move(Move::from_coordinate_notation("b2b1k", Color::Black).unwrap()).unwrap();
+        // white has nothing left, which wins
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+
+        // a blocked pawn has no moves, which also wins
+        let mut board = Board::from_fen("8/8/8/8/8/p7/P7/8 w - - 0 1").unwrap();
+        board.set_variant(Variant::Antichess);
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::White)));
+        assert!(!board.is_stalemate());
+        Board::from_fen("4k3/8/8/8/8/8/1p6/4K3 b - - 0 1").unwrap().make_move(Move::from_coordinate_notation("b2b1k", Color::Black).unwrap()).unwrap_err();
+    }
+
+    #[test]
+    #[should_panic(expected = "White has no king")]
+    fn test_missing_king() {
+        // only the horde, antichess and atomic chess go without a king, anywhere else nothing moves
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap();
+        board.clone().make_move(Move::from_coordinate_notation("a1a2", Color::White).unwrap()).unwrap_err();
+        board.is_in_check(&Color::White);
+    }
+
+    #[test]
+    fn test_horde() {
+        let mut board = Board::from_fen(Variant::Horde.starting_fen()).unwrap();
+        board.set_variant(Variant::Horde);
+        assert!(!board.is_in_check(&Color::White));
+        assert_eq!(Tester::perft(&mut board, 3), 1274);
+
+        // pawns on the first rank may move two, but only in horde
+        Board::from_fen("4k3/8/8/8/8/8/8/P6K w - - 0 1").unwrap().make_move(Move::from_coordinate_notation("a1a3", Color::White).unwrap()).unwrap_err();
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap();
+        board.set_variant(Variant::Horde);
+        board.make_move(Move::from_coordinate_notation("a1a3", Color::White).unwrap()).unwrap();
+        assert!(!board.is_in_check(&Color::White));
+
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/1q6/P7 b - - 0 1").unwrap();
+        board.set_variant(Variant::Horde);
+        assert_eq!(board.variant_outcome(), None);
+        board.make_move(Move::from_coordinate_notation("b2a1", Color::Black).unwrap()).unwrap();
+        assert_eq!(board.variant_outcome(), Some(Outcome::Win(Color::Black)));
+    }
+
+    #[test]
+    fn test_fairy_pieces() {
+        let fairy = |name| FairyPiece::from_name(name).unwrap();
+        let atoms = fairy("archbishop").atoms();
+        assert_eq!(atoms.len(), 2);
+        assert_eq!((atoms[0].step, atoms[0].range), ((1, 1), Range::Ride));
+        assert_eq!((atoms[1].step, atoms[1].range), ((2, 1), Range::Leap));
+        assert_eq!(fairy("grasshopper").atoms()[0].range, Range::Hop);
+        FairyPiece::new("nonsense", 'x', "gN", 0).unwrap_err();
+        FairyPiece::new("nothing", 'x', "", 0).unwrap_err();
+        FairyPiece::new("shouting", 'X', "W", 0).unwrap_err();
+
+        // a piece of the designer's own, known to FEN once registered
+        assert!(Board::from_fen("8/8/8/8/3Z4/8/8/8 w - - 0 1").is_err());
+        let zebra = FairyPiece::register(FairyPiece::new("zebra", 'z', "Z", 250).unwrap()).unwrap();
+        assert_eq!(FairyPiece::from_letter('Z'), Some(zebra));
+        assert_eq!(FairyPiece::register(FairyPiece::new("zebra", 'z', "Z", 250).unwrap()), Ok(zebra));
+        FairyPiece::register(FairyPiece::new("zero", 'z', "W", 100).unwrap()).unwrap_err();
+        FairyPiece::register(FairyPiece::new("nightrider", 'n', "NN", 500).unwrap()).unwrap_err();
+        assert_eq!(Board::from_fen("k7/8/8/8/3Z4/8/8/7K w - - 0 1").unwrap().legal_moves_from(Location::new(3, 3)).len(), 8);
+
+        // a bishop's thirteen squares and a knight's eight
+        assert_eq!(Board::from_fen("k7/8/8/8/3A4/8/8/7K w - - 0 1").unwrap().legal_moves_from(Location::new(3, 3)).len(), 21);
+        assert_eq!(Board::from_fen("k7/8/8/8/8/8/8/L6K w - - 0 1").unwrap().legal_moves_from(Location::new(0, 0)).len(), 2);
+        Tester::place_and_move_legal(Piece::Fairy(fairy("camel"), Color::White), Location::new(0, 0), Location::new(1, 3), None);
+        Tester::place_and_move_illegal(Piece::Fairy(fairy("camel"), Color::White), Location::new(0, 0), Location::new(2, 1));
+        Tester::place_target_and_piece_then_move_legal(Piece::Fairy(fairy("chancellor"), Color::White), Piece::Rook(Color::Black), Location::new(0, 0), Location::new(0, 7), None);
+        Tester::place_target_and_piece_then_move_illegal(Piece::Fairy(fairy("chancellor"), Color::White), Piece::Rook(Color::Black), Location::new(0, 0), Location::new(7, 7));
+
+        // the grasshopper only goes anywhere by hopping, landing right behind the pawn
+        let mut board = Board::from_fen("7k/8/8/p7/P7/8/8/G6K w - - 0 1").unwrap();
+        assert_eq!(board.legal_moves_from(Location::new(0, 0)), vec!(Move::from_coordinate_notation("a1a5", Color::White).unwrap()));
+        board.make_move(Move::from_coordinate_notation("a1a3", Color::White).unwrap()).unwrap_err();
+        board.make_move(Move::from_coordinate_notation("a1a5", Color::White).unwrap()).unwrap();
+        assert_eq!(board.get_piece_from(&Location::new(0, 4)), Some(Piece::Fairy(fairy("grasshopper"), Color::White)));
+
+        let fen = "4k3/8/8/8/8/8/8/a3C2K b - - 0 1";
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(), fen);
+        assert!(board.is_in_check(&Color::Black));
+        assert!(!board.is_in_check(&Color::White));
+        assert_eq!(board.zobrist_key(), Board::from_fen(fen).unwrap().zobrist_key());
+        assert_ne!(board.zobrist_key(), Board::from_fen("4k3/8/8/8/8/8/8/c3A2K b - - 0 1").unwrap().zobrist_key());
+    }
+
+    #[test]
+    fn test_board_dimensions() {
+        assert!(Board::with_dimensions(17, 8).is_err());
+        Board::from_fen("8/8/8 w - - 0 1").unwrap();
+        assert!(Board::from_fen("8/9/8 w - - 0 1").is_err());
+        assert_eq!(Move::from_coordinate_notation("a10a9", Color::White).unwrap(), Move::new(Location::new(0, 9), Location::new(0, 8)));
+        assert_eq!(Move::from_coordinate_notation("j9j10q", Color::White).unwrap().to_string(), "j9j10q");
+        Board::starting_position().make_move(Move::from_coordinate_notation("e2i2", Color::White).unwrap()).unwrap_err();
+
+        let capablanca = "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1";
+        let mut board = Board::from_fen(capablanca).unwrap();
+        assert_eq!((board.width(), board.height()), (10, 8));
+        assert_eq!(board.to_fen(), capablanca);
+        assert!(!board.is_chess960());
+        assert_eq!(Tester::perft(&mut board, 3), 25228);
+
+        // the king goes from the f file to the i or the c file
+        let fen = "r4k3r/10/10/10/10/10/10/R4K3R w KQkq - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        board.make_move(Move::from_coordinate_notation("f1i1", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "r4k3r/10/10/10/10/10/10/R6RK1 b kq - 1 1");
+        board.make_move(Move::from_coordinate_notation("f8c8", Color::Black).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "2kr5r/10/10/10/10/10/10/R6RK1 w - - 2 2");
+
+        // in grand chess the pawns start on the third rank and promote on the tenth
+        let mut board = Board::from_fen("r8r/1nbqkcabn1/pppppppppp/10/10/10/10/PPPPPPPPPP/1NBQKCABN1/R8R w - - 0 1").unwrap();
+        assert_eq!(board.legal_moves().len(), 65);
+        board.make_move(Move::from_coordinate_notation("e3e5", Color::White).unwrap()).unwrap();
+        board.make_move(Move::from_coordinate_notation("e8e6", Color::Black).unwrap()).unwrap();
+        let mut board = Board::from_fen("9k/P9/10/10/10/10/10/10/10/9K w - - 0 1").unwrap();
+        board.make_move(Move::from_coordinate_notation("a9a10", Color::White).unwrap()).unwrap_err();
+        board.make_move(Move::from_coordinate_notation("a9a10q", Color::White).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_render_board() {
+        let mut board = Board::starting_position();
+        assert_eq!(board.to_string(), "\
+8 r n b q k b n r
+7 p p p p p p p p
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . . . . .
+3 . . . . . . . .
+2 P P P P P P P P
+1 R N B Q K B N R
+  a b c d e f g h
+");
+        let e4 = Move::from_coordinate_notation("e2e4", Color::White).unwrap();
+        board.make_move(e4).unwrap();
+        let style = TextStyle { unicode: true, coordinates: false, orientation: Color::Black, ..TextStyle::default() };
+        assert_eq!(render(&board, &style).lines().nth(3), Some("· · · ♙ · · · ·"));
+        assert!(format!("{:#}", board).starts_with("8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜\n"));
+
+        // highlights only show up with ANSI colours
+        let style = TextStyle { last_move: Some(e4), attacked_by: Some(Color::Black), ..TextStyle::default() };
+        assert_eq!(render(&board, &style), board.to_string());
+        let rendered = render(&board, &TextStyle { ansi: true, ..style });
+        assert!(rendered.contains("4 . . . . \x1b[30;43mP\x1b[0m . . ."));
+        assert!(rendered.contains("6 \x1b[30;41m.\x1b[0m"));
+
+        let board = Board::from_fen("10/10/10/10/10/10/10/10/10/K9 w - - 0 1").unwrap();
+        assert!(board.to_string().ends_with(" 1 K . . . . . . . . .\n   a b c d e f g h i j\n"));
+    }
+
+    #[test]
+    fn test_svg_diagram() {
+        let board = Board::starting_position();
+        let svg = render_svg(&board, &SvgStyle::default());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"405\" height=\"405\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<g transform").count(), 32);
+        assert!(!svg.contains("href"));
+        // nothing relies on a font being there
+        assert!(!svg.contains("<text") && !svg.contains("font"));
+        let fairy = render_svg(&Board::from_fen("8/8/8/8/3A4/8/8/8 w - - 0 1").unwrap(), &SvgStyle::default());
+        assert!(!fairy.contains("<text") && fairy.contains("<circle"));
+        // a1 is dark, in the bottom left corner
+        assert!(svg.contains("<rect x=\"22.5\" y=\"337.5\" width=\"45\" height=\"45\" fill=\"#b58863\"/>"));
+
+        let e2 = Location::new(4, 1);
+        let e4 = Location::new(4, 3);
+        let style = SvgStyle { square_size: 20, coordinates: false, orientation: Color::Black, arrows: vec!((e2, e4)), highlights: vec!(e2) };
+        let svg = render_svg(&board, &style);
+        assert!(svg.contains("width=\"160\" height=\"160\""));
+        assert!(!svg.contains("<text"));
+        assert!(svg.contains("<rect x=\"140\" y=\"0\" width=\"20\" height=\"20\" fill=\"#b58863\"/>"));
+        assert!(svg.contains("<rect x=\"60\" y=\"20\" width=\"20\" height=\"20\" fill=\"#9bc700\" fill-opacity=\"0.5\"/>"));
+        assert!(svg.contains("<line x1=\"70\" y1=\"30\" x2=\"70\" y2=\"64\""));
+    }
+
+    #[test]
+    fn test_standard_algebraic_notation() {
+        let mut board = Board::from_fen(KIWIPETE).unwrap();
+        let sans: Vec<String> = board.legal_moves().into_iter().map(|m| to_san(&board, m)).collect();
+        for san in &["O-O", "O-O-O", "Nxf7", "Bxa6", "Qxf6", "d6", "gxh3", "Nc6", "dxe6", "Nxd7"] {
+            assert!(sans.contains(&String::from(*san)), "{} is missing from {:?}", san, sans);
+        }
+        // every move reads back as itself
+        for m in board.legal_moves() {
+            assert_eq!(parse_san(&board, &to_san(&board, m)).unwrap(), m);
+        }
+        assert_eq!(parse_san(&board, "Ng6?!").unwrap(), Move::from_coordinate_notation("e5g6", Color::White).unwrap());
+        assert_eq!(parse_san(&board, "Ne5g6").unwrap(), Move::from_coordinate_notation("e5g6", Color::White).unwrap());
+        parse_san(&board, "Nd5").unwrap_err();
+        parse_san(&board, "Kd2d3").unwrap_err();
+
+        // either rook can go to d1
+        board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, Move::from_coordinate_notation("a1d1", Color::White).unwrap()), "Rad1");
+        assert_eq!(parse_san(&board, "Rhd1").unwrap(), Move::from_coordinate_notation("h1d1", Color::White).unwrap());
+        parse_san(&board, "Rd1").unwrap_err();
+        let board = Board::from_fen("R6k/8/8/8/8/8/8/R6K w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, Move::from_coordinate_notation("a1a7", Color::White).unwrap()), "R1a7#");
+        let board = Board::from_fen("8/4P2k/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, Move::from_coordinate_notation("e7e8q", Color::White).unwrap()), "e8=Q");
+        assert_eq!(parse_san(&board, "e8N").unwrap(), Move::from_coordinate_notation("e7e8n", Color::White).unwrap());
+    }
+
+    #[test]
+    fn test_write_pgn() {
+        let mut board = Board::starting_position();
+        let mut moves = Vec::new();
+        for san in &["f3", "e5", "g4", "Qh4#"] {
+            let m = parse_san(&board, san).unwrap();
+            board.make_move(m).unwrap();
+            moves.push(m);
+        }
+        let pgn = write_pgn(&[("White", String::from("A \"Fool\"")), ("Black", String::from("B"))], &Board::starting_position(), &moves, "0-1");
+        assert_eq!(pgn, "[White \"A \\\"Fool\\\"\"]\n[Black \"B\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n");
+
+        let start = Board::from_fen("4k3/8/8/8/8/8/8/4K2R b K - 0 30").unwrap();
+        let moves = vec!(Move::from_coordinate_notation("e8d7", Color::Black).unwrap(), Move::from_coordinate_notation("e1g1", Color::White).unwrap());
+        let pgn = write_pgn(&[], &start, &moves, "*");
+        assert!(pgn.ends_with("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R b K - 0 30\"]\n\n30... Kd7 31. O-O *\n"));
+    }
+
+    #[test]
+    fn test_tui() {
+        assert_eq!(parse_input(b"\x1b[A\x1b[Dx\r"), vec!(Input::Up, Input::Left, Input::Char('x'), Input::Select));
+        // a press is a click, the release that follows it is not
+        assert_eq!(parse_input(b"\x1b[<0;17;8M\x1b[<0;17;8m\x1b"), vec!(Input::Click { column: 17, row: 8 }, Input::Cancel));
+
+        let mut tui = Tui::new(Board::starting_position());
+        assert_eq!(tui.square_at(17, 8), Some(Location::from_algebraic("e2").unwrap()));
+        assert_eq!(tui.square_at(2, 8), None);
+        assert_eq!(tui.handle(Input::Click { column: 17, row: 8 }), None);
+        assert_eq!(tui.selected(), Location::from_algebraic("e2").ok());
+        let mut destinations = tui.destinations();
+        destinations.sort();
+        assert_eq!(destinations, vec!(Location::from_algebraic("e3").unwrap(), Location::from_algebraic("e4").unwrap()));
+        assert_eq!(tui.handle(Input::Click { column: 17, row: 6 }), Some(Move::from_coordinate_notation("e2e4", Color::White).unwrap()));
+        assert_eq!(tui.history(), &[String::from("e4")]);
+        assert!(tui.draw(24).contains("1. e4"));
+
+        // the keyboard gets there too, the cursor stays where the mouse last clicked
+        assert_eq!(tui.cursor(), Location::from_algebraic("e4").unwrap());
+        for input in &[Input::Up, Input::Up, Input::Up, Input::Select, Input::Down, Input::Down] {
+            assert_eq!(tui.handle(*input), None);
+        }
+        assert_eq!(tui.handle(Input::Select), Some(Move::from_coordinate_notation("e7e5", Color::Black).unwrap()));
+
+        tui.handle(Input::Char('f'));
+        assert_eq!(tui.square_at(17, 8), Some(Location::from_algebraic("d7").unwrap()));
+        tui.handle(Input::Char('u'));
+        assert_eq!(tui.history(), &[String::from("e4")]);
+        assert_eq!(tui.board().turn(), Color::Black);
+    }
+
+    #[test]
+    fn test_chess_clock() {
+        let minutes = |n: u64| Duration::from_secs(n * 60);
+        let source = MockClock::new();
+
+        let mut clock = ChessClock::new(Control::fischer(minutes(3), Duration::from_secs(2)), Arc::new(source.clone()));
+        clock.start(Color::White);
+        source.advance(Duration::from_secs(10));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(170));
+        assert!(clock.press());
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(172));
+        assert_eq!(clock.running(), Some(Color::Black));
+
+        // a simple delay is waited out before the clock moves, bronstein gives the time back after
+        let mut clock = ChessClock::new(Control::simple_delay(minutes(1), Duration::from_secs(5)), Arc::new(source.clone()));
+        clock.start(Color::White);
+        source.advance(Duration::from_secs(3));
+        assert_eq!(clock.remaining(Color::White), minutes(1));
+        source.advance(Duration::from_secs(4));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(58));
+        let mut clock = ChessClock::new(Control::bronstein(minutes(1), Duration::from_secs(5)), Arc::new(source.clone()));
+        clock.start(Color::White);
+        source.advance(Duration::from_secs(3));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(57));
+        clock.press();
+        assert_eq!(clock.remaining(Color::White), minutes(1));
+        source.advance(Duration::from_secs(8));
+        clock.press();
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(57));
+
+        // 40/90+30 with 30 seconds a move from the start
+        let forty = Stage { moves: Some(40), time: minutes(90), increment: Duration::from_secs(30), delay: Delay::None };
+        let rest = Stage { moves: None, time: minutes(30), increment: Duration::from_secs(30), delay: Delay::None };
+        let mut clock = ChessClock::new(Control::new(forty).then(rest), Arc::new(source.clone()));
+        clock.start(Color::White);
+        assert_eq!(clock.moves_to_go(Color::White), Some(40));
+        for _ in 0..80 {
+            source.advance(minutes(1));
+            assert!(clock.press());
+        }
+        assert_eq!(clock.moves_to_go(Color::White), None);
+        assert_eq!(clock.remaining(Color::White), minutes(100));
+
+        // a last stage with a number of moves starts over
+        let mut clock = ChessClock::new(Control::new(Stage { moves: Some(2), time: minutes(1), increment: Duration::from_secs(0), delay: Delay::None }), Arc::new(source.clone()));
+        clock.start(Color::White);
+        for _ in 0..4 {
+            source.advance(Duration::from_secs(10));
+            clock.press();
+        }
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(100));
+        assert_eq!(clock.moves_to_go(Color::White), Some(2));
+
+        let mut game = TimedGame::new(Board::starting_position(), Control::sudden_death(minutes(1)), Arc::new(source.clone()));
+        game.make_move(Move::from_coordinate_notation("e2e4", Color::White).unwrap()).unwrap();
+        // a move taken back is white's to make again, on white's time
+        source.advance(Duration::from_secs(5));
+        assert!(game.take_back());
+        assert!(!game.take_back());
+        assert_eq!(game.clock().running(), Some(Color::White));
+        assert_eq!(game.clock().remaining(Color::Black), Duration::from_secs(55));
+        game.make_move(Move::from_coordinate_notation("e2e4", Color::White).unwrap()).unwrap();
+        source.advance(minutes(2));
+        assert_eq!(game.outcome(), Some(Outcome::Win(Color::White)));
+        assert_eq!(game.flagged(), Some(Color::Black));
+        assert!(game.make_move(Move::from_coordinate_notation("e7e5", Color::Black).unwrap()).is_err());
+        assert!(!game.take_back());
+
+        // running out against a lone knight is a draw, against a rook it is not
+        for (fen, outcome) in &[("8/8/4k3/8/8/8/8/4K1N1 b - - 0 1", Outcome::Draw), ("8/8/4k3/8/8/8/8/4K2R b - - 0 1", Outcome::Win(Color::White))] {
+            let mut game = TimedGame::new(Board::from_fen(fen).unwrap(), Control::sudden_death(minutes(1)), Arc::new(source.clone()));
+            source.advance(minutes(1));
+            assert_eq!(game.outcome(), Some(*outcome));
+        }
+    }
+
+    #[test]
+    fn test_game_tree() {
+        let mv = |text: &str, color: Color| Move::from_coordinate_notation(text, color).unwrap();
+        let mut game = Game::default();
+        assert_eq!(game.header("Result"), Some("*"));
+        game.set_header("White", "Morphy");
+        game.set_header("Opening", "King's pawn");
+        assert_eq!(game.headers().len(), 8);
+        assert_eq!(game.header("White"), Some("Morphy"));
+
+        for (m, color) in &[("e2e4", Color::White), ("e7e5", Color::Black), ("g1f3", Color::White)] {
+            game.play(mv(m, *color)).unwrap();
+        }
+        assert!(game.play(mv("e8e6", Color::Black)).is_err());
+        game.back();
+        game.back();
+        // a different reply is a variation, the same one follows what is there
+        game.play(mv("d7d5", Color::Black)).unwrap();
+        assert!(!game.is_mainline());
+        assert_eq!(game.board().to_fen(), "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2");
+        {
+            let node = game.current_mut().unwrap();
+            node.comment = String::from("the Scandinavian");
+            node.nags.push(2);
+        }
+        game.back();
+        assert_eq!(game.continuations().len(), 2);
+        game.play(mv("e7e5", Color::Black)).unwrap();
+        assert_eq!(game.continuations().len(), 1);
+        game.back();
+        game.back();
+        assert!(game.current().is_none());
+        game.forward();
+        {
+            let node = game.current_mut().unwrap();
+            node.clock = Some(Duration::from_secs(5400));
+            node.eval = Some(Score::Centipawns(30));
+        }
+        assert!(write_game(&game).replace('\n', " ").ends_with("  1. e4 {[%clk 1:30:00] [%eval 0.30]} 1... e5 (1... d5 $2 {the Scandinavian}) 2. Nf3 * "));
+
+        game.enter_variation(1);
+        assert!(game.promote_variation());
+        assert!(game.is_mainline());
+        assert!(!game.promote_variation());
+        assert_eq!(game.mainline(), vec!(mv("e2e4", Color::White), mv("d7d5", Color::Black)));
+        assert!(write_game(&game).replace('\n', " ").ends_with("  1. e4 {[%clk 1:30:00] [%eval 0.30]} 1... d5 $2 {the Scandinavian} (1... e5 2. Nf3) * "));
+
+        game.delete_subtree();
+        assert_eq!(game.path(), vec!(mv("e2e4", Color::White)));
+        game.go_to_end();
+        assert_eq!(game.path(), vec!(mv("e2e4", Color::White), mv("e7e5", Color::Black), mv("g1f3", Color::White)));
+        assert_eq!(game.board().turn(), Color::Black);
+
+        // a line deep in a variation brought all the way up
+        game.go_to_start();
+        assert_eq!(game.board().to_fen(), STARTING_FEN);
+        game.play(mv("d2d4", Color::White)).unwrap();
+        game.play(mv("d7d5", Color::Black)).unwrap();
+        game.promote_to_mainline();
+        assert!(game.is_mainline());
+        assert_eq!(game.mainline(), vec!(mv("d2d4", Color::White), mv("d7d5", Color::Black)));
+        assert_eq!(game.moves()[1].mainline().len(), 3);
+        assert_eq!(game.board().to_fen(), "rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq d6 0 2");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Position {
+            #[serde(with = "crate::serialize::serialize::square_map")]
+            board: Board,
+        }
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Fen {
+            #[serde(with = "crate::serialize::serialize::fen")]
+            board: Board,
+        }
+
+        let e4 = Location::from_algebraic("e4").unwrap();
+        assert_eq!(serde_json::to_string(&e4).unwrap(), "\"e4\"");
+        assert_eq!(serde_json::from_str::<Location>("\"e4\"").unwrap(), e4);
+        assert!(serde_json::from_str::<Location>("\"e\"").is_err());
+        for piece in &[Piece::Knight(Color::White), Piece::Pawn(Color::Black), Piece::Fairy(FairyPiece::from_name("archbishop").unwrap(), Color::Black)] {
+            assert_eq!(serde_json::from_str::<Piece>(&serde_json::to_string(piece).unwrap()).unwrap(), *piece);
+        }
+        assert!(serde_json::from_str::<Piece>("\"Nb\"").is_err());
+        assert_eq!(serde_json::to_string(&Color::Black).unwrap(), "\"black\"");
+        let m = Move::from_coordinate_notation("e7e8q", Color::White).unwrap();
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(json, "{\"from\":\"e7\",\"to\":\"e8\",\"promoted\":\"Q\",\"dropped\":null}");
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), m);
+
+        let board = Board::from_fen(KIWIPETE).unwrap();
+        assert_eq!(serde_json::to_string(&board).unwrap(), format!("{{\"fen\":\"{}\",\"variant\":\"chess\",\"chess960\":false}}", KIWIPETE));
+        assert_eq!(serde_json::from_str::<Board>(&format!("{{\"fen\":\"{}\"}}", KIWIPETE)).unwrap().to_fen(), KIWIPETE);
+        assert!(serde_json::from_str::<Board>("{\"fen\":\"not a fen\"}").is_err());
+        assert!(serde_json::from_str::<Board>(&format!("{{\"fen\":\"{}\",\"variant\":\"nonsense\"}}", KIWIPETE)).is_err());
+        // the bare fen is there for those who ask for it
+        assert_eq!(serde_json::to_string(&Fen { board: board.clone() }).unwrap(), format!("{{\"board\":\"{}\"}}", KIWIPETE));
+        assert_eq!(serde_json::from_str::<Fen>(&format!("{{\"board\":\"{}\"}}", KIWIPETE)).unwrap().board.to_fen(), KIWIPETE);
+
+        let mut atomic = Board::starting_position();
+        atomic.set_variant(Variant::Atomic);
+        let boards = vec!(
+            board,
+            atomic,
+            Board::chess960(0).unwrap(),
+            Board::from_fen("r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[NPbp] b KQkq - 0 8").unwrap(),
+            Board::from_fen("rnbqkbnr/ppp1pppp/8/1B1p4/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 2+3 1 2").unwrap(),
+            Board::from_fen("rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1").unwrap(),
+        );
+        for board in boards {
+            let json = serde_json::to_string(&Position { board: board.clone() }).unwrap();
+            assert!(json.contains("\"squares\":{\"a1\":"));
+            let read = serde_json::from_str::<Position>(&json).unwrap().board;
+            assert_eq!(read.to_fen(), board.to_fen());
+            assert_eq!(read.variant(), board.variant());
+            assert_eq!(read.is_chess960(), board.is_chess960());
+            // the default form keeps them too
+            let read = serde_json::from_str::<Board>(&serde_json::to_string(&board).unwrap()).unwrap();
+            assert_eq!(read.to_fen(), board.to_fen());
+            assert_eq!(read.variant(), board.variant());
+            assert_eq!(read.is_chess960(), board.is_chess960());
+        }
+        assert!(serde_json::from_str::<Position>("{\"board\":{\"width\":8,\"height\":8,\"squares\":{\"j9\":\"K\"},\"turn\":\"white\",\"castling\":\"-\",\"en_passant\":null,\"halfmove_clock\":0,\"fullmove_number\":1,\"variant\":\"standard\"}}").is_err());
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        // every 16 bit value that reads as a move writes back the same
+        for bits in 0..=u16::MAX {
+            if let Ok(m) = Move::from_u16(bits, Color::Black) {
+                assert_eq!(m.to_u16().unwrap(), bits);
+            }
+        }
+        assert!(Move::from_u16(0, Color::White).is_err());
+        assert!(Move::new(Location::new(0, 0), Location::new(9, 0)).to_u16().is_err());
+        let drop = Move::from_coordinate_notation("N@f3", Color::White).unwrap();
+        assert_eq!(Move::from_u16(drop.to_u16().unwrap(), Color::White).unwrap(), drop);
+
+        // random games from a few starting points, checking every move and position on the way
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let starts = [STARTING_FEN, KIWIPETE, "1r2k2r/8/8/3pP3/8/8/8/R3K1R1 w Qk d6 0 40"];
+        for game in 0..30 {
+            let mut board = Board::from_fen(starts[game % starts.len()]).unwrap();
+            if game % 5 == 4 {
+                board = Board::chess960(random() as u32 % 960).unwrap();
+            }
+            let mut moves = Vec::new();
+            let start = board.clone();
+            for _ in 0..80 {
+                let encoded = board.to_compact().unwrap();
+                assert!(encoded.len() <= 28);
+                let decoded = Board::from_compact(&encoded).unwrap();
+                assert_eq!(decoded.to_fen(), board.to_fen());
+                assert_eq!(decoded.is_chess960(), board.is_chess960());
+                let legal = board.legal_moves();
+                if legal.is_empty() {
+                    break;
+                }
+                let m = legal[random() as usize % legal.len()];
+                assert_eq!(Move::from_u16(m.to_u16().unwrap(), board.turn()).unwrap(), m);
+                board.make_move(m).unwrap();
+                moves.push(m);
+            }
+            let bytes = encode_moves(&moves).unwrap();
+            assert_eq!(bytes.len(), moves.len() * 2);
+            assert_eq!(decode_moves(&bytes, start.turn()).unwrap(), moves);
+        }
+
+        // rubbish in gives an error rather than a panic, whatever the length. Rubbish of the
+        // right length for its occupancy gets past the length check to the pieces themselves,
+        // and mostly without a 15 in it to stop at.
+        for length in 0..12 {
+            let bytes: Vec<u8> = (0..length).map(|_| random() as u8).collect();
+            assert!(Board::from_compact(&bytes).is_err());
+        }
+        for i in 0..2000 {
+            let occupied = random() >> (random() % 64);
+            let count = occupied.count_ones() as usize;
+            let mut bytes = occupied.to_le_bytes().to_vec();
+            bytes.extend((0..count.div_ceil(2)).map(|_| if i % 4 == 0 { random() as u8 } else { (random() % 15) as u8 | ((random() % 15) as u8) << 4 }));
+            bytes.extend((0..4).map(|_| random() as u8));
+            if let Ok(board) = Board::from_compact(&bytes) {
+                let _ = board.to_fen();
+                let _ = board.to_compact();
+            }
+            bytes.push(0);
+            assert!(Board::from_compact(&bytes).is_err());
+        }
+        assert!(decode_moves(&[1, 2, 3], Color::White).is_err());
+        assert!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1").unwrap().to_compact().is_err());
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        let insufficient = |fen: &str, color: Color| Board::from_fen(fen).unwrap().has_insufficient_material(color);
+        assert!(insufficient("8/8/4k3/8/8/8/8/4K3 w - - 0 1", Color::White));
+        assert!(insufficient("8/8/4k3/8/8/8/8/4K1N1 w - - 0 1", Color::White));
+        assert!(insufficient("8/8/4k3/3q4/8/8/8/4K1N1 w - - 0 1", Color::White));
+        assert!(!insufficient("8/8/4k3/3p4/8/8/8/4K1N1 w - - 0 1", Color::White));
+        assert!(!insufficient("8/8/4k3/8/8/8/8/4KNN1 w - - 0 1", Color::White));
+        // bishops on one colour of square cannot mate, on both they can
+        assert!(insufficient("8/8/4k3/8/8/4B3/8/2B1K3 w - - 0 1", Color::White));
+        assert!(!insufficient("8/8/4k3/8/8/8/8/2B1KB2 w - - 0 1", Color::White));
+        // a rook of theirs can block its own king in
+        assert!(!insufficient("kr6/8/1K6/8/4B3/8/8/8 w - - 0 1", Color::White));
+        assert!(!insufficient("8/8/4k3/8/8/8/8/4K2R w - - 0 1", Color::White));
+        assert!(!insufficient("8/8/4k3/8/8/8/4P3/4K3 w - - 0 1", Color::White));
+    }
+
+    #[test]
+    fn test_polyglot_book() {
+        // the keys given with the polyglot book format
+        let mut board = Board::starting_position();
+        assert_eq!(polyglot_key(&board), 0x463b96181691fc9c);
+        let keys = [0x823c9b50fd114196, 0x0756b94461c50fb0, 0x662fafb965db29d4, 0x22a48b5a8e47ff78, 0x652a607ca3f242c1, 0x00fdd303c946bdd9];
+        for (m, key) in ["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"].iter().zip(keys.iter()) {
+            board.make_move(Move::from_coordinate_notation(m, board.turn()).unwrap()).unwrap();
+            assert_eq!(polyglot_key(&board), *key, "after {}", m);
+        }
+        let key = |fen: &str| polyglot_key(&Board::from_fen(fen).unwrap());
+        assert_eq!(key("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3"), 0x3c8123ea7b067637);
+        assert_eq!(key("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 1 4"), 0x5c3f9b829b279560);
+
+        let start = Board::starting_position();
+        let castling = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let entry = |board: &Board, m: &str, weight: u16| BookEntry {
+            key: polyglot_key(board),
+            raw_move: encode_move(board, &Move::from_coordinate_notation(m, board.turn()).unwrap()).unwrap(),
+            weight,
+            learn: 0,
+        };
+        let short_castle = entry(&castling, "e1g1", 1);
+        // castling is written as the king taking the rook
+        assert_eq!(short_castle.raw_move, 7 | 4 << 6);
+        let book = Book::from(vec!(entry(&start, "e2e4", 3), short_castle, entry(&start, "d2d4", 1), entry(&start, "e2e5", 5)));
+        let book = Book::from_bytes(&book.to_bytes()).unwrap();
+        assert_eq!(book.len(), 4);
+        // e2e5 is not legal so is left out
+        let e4 = Move::new(Location::new(4, 1), Location::new(4, 3));
+        let d4 = Move::new(Location::new(3, 1), Location::new(3, 3));
+        assert_eq!(book.moves(&start), vec!((e4, 3), (d4, 1)));
+        assert_eq!((0..4).map(|random| book.choose(&start, random).unwrap()).collect::<Vec<_>>(), vec!(e4, e4, e4, d4));
+        assert_eq!(book.moves(&castling), vec!((Move::new(Location::new(4, 0), Location::new(6, 0)), 1)));
+        assert_eq!(book.choose(&Board::from_fen(KIWIPETE).unwrap(), 0), None);
+        assert!(Book::from_bytes(&[0; 15]).is_err());
+
+        let path = std::env::temp_dir().join(format!("chess-test-{}.bin", std::process::id()));
+        std::fs::write(&path, Book::from(vec!(entry(&start, "g1f3", 1))).to_bytes()).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let mut uci = Uci::new(sender);
+        assert!(uci.handle(&format!("setoption name BookFile value {}", path.display())));
+        assert!(uci.handle("setoption name OwnBook value true"));
+        assert!(uci.handle("position startpos"));
+        assert!(uci.handle("go movetime 1000"));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(receiver.iter().find(|line| line.starts_with("bestmove")), Some(String::from("bestmove g1f3")));
+    }
+
+    #[test]
+    fn test_read_pgn() {
+        let pgn = "[Event \"Club \\\"blitz\\\"\"]\n[White \"Anderssen\"]\n[Black \"Kieseritzky\"]\n[Result \"*\"]\n\n\
+            {A quick one} 1. e4 e5 2.Nf3!? {[%clk 0:04:58] [%eval 0.25] developing}\n\
+            ( 2. f4 exf4 (2... d5) 3. Bc4 $1 ) 2... Nc6 ; the usual\n\
+            3. Bb5 1-0\n\n\
+            [Event \"broken\"]\n\n1. e4 e4 *\n\n\
+            [FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n[SetUp \"1\"]\n\n40... Kd7 41. e4 1/2-1/2\n";
+        let games = read_pgn(pgn);
+        assert_eq!(games.len(), 3);
+        assert!(games[1].is_err());
+
+        let game = games[0].as_ref().unwrap();
+        assert_eq!(game.header("Event"), Some("Club \"blitz\""));
+        assert_eq!(game.header("Result"), Some("1-0"));
+        assert_eq!(game.comment(), "A quick one");
+        assert_eq!(game.mainline().iter().map(|m| m.to_string()).collect::<Vec<_>>(), vec!("e2e4", "e7e5", "g1f3", "b8c6", "f1b5"));
+        let nf3 = &game.moves()[0].children()[0].children()[0];
+        assert_eq!(nf3.nags, vec!(5));
+        assert_eq!(nf3.comment, "developing");
+        assert_eq!(nf3.clock, Some(Duration::from_secs(298)));
+        assert_eq!(nf3.eval, Some(Score::Centipawns(25)));
+        assert_eq!(nf3.children()[0].comment, "the usual");
+        let f4 = &game.moves()[0].children()[0].children()[1];
+        assert_eq!(f4.m().to_string(), "f2f4");
+        assert_eq!(f4.children().len(), 2);
+        assert_eq!(f4.children()[0].children()[0].nags, vec!(1));
+        // writing a game out and reading it back changes nothing
+        let written = write_game(game);
+        assert_eq!(write_game(read_pgn(&written)[0].as_ref().unwrap()), written);
+
+        let endgame = games[2].as_ref().unwrap();
+        assert_eq!(endgame.start().turn(), Color::Black);
+        assert_eq!(endgame.mainline().len(), 2);
+    }
+
+    #[test]
+    fn test_book_builder() {
+        let pgn = "[Result \"1-0\"]\n[WhiteElo \"2400\"]\n[BlackElo \"1500\"]\n\n1. e4 e5 2. Nf3 1-0\n\n\
+            [Result \"0-1\"]\n[WhiteElo \"2300\"]\n[BlackElo \"2200\"]\n\n1. e4 c5 0-1\n\n\
+            [Result \"1/2-1/2\"]\n[WhiteElo \"2500\"]\n[BlackElo \"2500\"]\n\n1. d4 d5 1/2-1/2\n\n\
+            [Result \"*\"]\n\n1. c4 *\n";
+        let games: Vec<Game> = read_pgn(pgn).into_iter().map(|game| game.unwrap()).collect();
+        let mut builder = BookBuilder::new().max_plies(2).min_rating(2000);
+        assert_eq!(games.iter().filter(|game| builder.add_game(game)).count(), 3);
+        assert_eq!(builder.games(), 3);
+
+        let start = Board::starting_position();
+        let e4 = Move::from_coordinate_notation("e2e4", Color::White).unwrap();
+        let d4 = Move::from_coordinate_notation("d2d4", Color::White).unwrap();
+        assert_eq!(builder.stats(&start), vec!(
+            (e4, MoveStats { wins: 1, draws: 0, losses: 1 }),
+            (d4, MoveStats { wins: 0, draws: 1, losses: 0 }),
+        ));
+        let mut after_e4 = start.clone();
+        after_e4.make_move(e4).unwrap();
+        // the 1500 player's e5 is left out, and Nf3 is past the ply limit
+        assert_eq!(builder.stats(&after_e4).len(), 1);
+        assert_eq!(builder.stats(&after_e4)[0].1, MoveStats { wins: 1, draws: 0, losses: 0 });
+
+        let book = Book::from_bytes(&builder.build().to_bytes()).unwrap();
+        assert_eq!(book.len(), 4);
+        let weights: Vec<u16> = book.moves(&start).iter().map(|(_, weight)| *weight).collect();
+        assert_eq!(book.moves(&start)[0].0, e4);
+        assert_eq!(weights[0], 2 * weights[1]);
+        let book = BookBuilder::new().min_games(2);
+        assert!(book.build().is_empty());
+    }
+
+    #[test]
+    fn test_eco_classification() {
+        // every line of the table reads, so the built in one has them all
+        let openings = Openings::built_in();
+        assert_eq!(Openings::parse(include_str!("eco.tsv")).unwrap().all().len(), openings.all().len());
+        // and every code from A00 to E99 is what some line of it comes to
+        let play = |moves: &str| {
+            let mut board = Board::starting_position();
+            for san in moves.split_whitespace() {
+                board.make_move(parse_san(&board, san).unwrap()).unwrap();
+            }
+            board
+        };
+        let mut codes: Vec<&str> = openings.all().iter().filter_map(|opening| openings.at(&play(&opening.moves))).map(|opening| opening.eco.as_str()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), 500);
+        assert!(Openings::parse("C60\tRuy Lopez\te4 e5 Nf3 Nc6 Bb6").is_err());
+        assert!(Openings::parse("C60 Ruy Lopez e4 e5 Nf3 Nc6 Bb5").is_err());
+        assert!(Openings::parse("F60\tRuy Lopez\te4 e5 Nf3 Nc6 Bb5").is_err());
+        assert_eq!(Openings::parse("# a comment\n\nC60\tRuy Lopez\te4 e5 Nf3 Nc6 Bb5\n").unwrap().all().len(), 1);
+        // a table read at run time can be dropped once it has been parsed
+        let table = format!("C60\t{}\te4 e5 Nf3 Nc6 Bb5", "Ruy Lopez");
+        let read = Openings::parse(&table).unwrap();
+        drop(table);
+        assert_eq!(read.all()[0].to_string(), "C60 Ruy Lopez");
+        let berlin = play("e4 e5 Nf3 Nc6 Bb5 Nf6");
+        assert_eq!(openings.at(&berlin).unwrap().to_string(), "C65 Ruy Lopez: Berlin Defence");
+        // out of the table the game is still named after the last position that was in it
+        let later = play("e4 e5 Nf3 Nc6 Bb5 Nf6 d3 Bc5");
+        assert!(openings.at(&later).is_none());
+        assert_eq!(openings.classify(&later).unwrap().eco, "C65");
+        // transpositions are found by position
+        assert_eq!(openings.classify(&play("c4 e6 d4 d5")).unwrap().name, "Queen's Gambit Declined");
+        assert_eq!(openings.classify(&play("c4 Nf6 Nc3 g6 d4 Bg7")).unwrap().eco, "E61");
+        assert!(openings.classify(&Board::starting_position()).is_none());
+
+        let mut game = Game::default();
+        for san in "d4 d5 c4 c6 Nf3 Nf6".split_whitespace() {
+            let m = parse_san(game.board(), san).unwrap();
+            game.play(m).unwrap();
+        }
+        assert!(openings.tag_game(&mut game));
+        assert_eq!(game.header("ECO"), Some("D11"));
+        assert!(write_game(&game).contains("[Opening \"Slav Defence: Modern Line\"]\n"));
+        let start = Board::starting_position();
+        assert_eq!(openings.classify_moves(&start, &game.mainline()).unwrap().eco, "D11");
+    }
+
+    #[test]
+    fn test_undo_restores_position() {
+        for fen in &[KIWIPETE, "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[NPbp] b KQkq - 0 8"] {
+            let mut board = Board::from_fen(fen).unwrap();
+            for m in board.legal_moves() {
+                board.play(m);
+                board.undo_last_move();
+                assert_eq!(board.to_fen(), *fen, "undoing {} did not restore the position", m);
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_starting_position() {
+        let mut board = Board::starting_position();
+        assert_eq!(Tester::perft(&mut board, 1), 20);
+        assert_eq!(Tester::perft(&mut board, 2), 400);
+        assert_eq!(Tester::perft(&mut board, 3), 8902);
+    }
+
+    #[test]
+    fn test_perft_tricky_positions() {
+        assert_eq!(Tester::perft(&mut Board::from_fen(KIWIPETE).unwrap(), 2), 2039);
+        assert_eq!(Tester::perft(&mut Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap(), 3), 2812);
+        assert_eq!(Tester::perft(&mut Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap(), 2), 264);
+    }
+
+    #[test]
+    fn test_chess960_start_positions() {
+        assert_eq!(Board::chess960(518).unwrap().to_fen(), STARTING_FEN);
+        assert_eq!(Board::chess960(0).unwrap().to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+        assert!(Board::chess960(0).unwrap().is_chess960());
+        Board::chess960(960).err().unwrap();
+    }
+
+    #[test]
+    fn test_chess960_fen() {
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let board = Board::from_fen(fen).unwrap();
+        assert!(board.is_chess960());
+        // each rook is the outermost on its side of the king, so X-FEN can use the usual letters
+        assert_eq!(board.to_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+        let inner_rook = "4k3/8/8/8/8/8/8/RR2K3 w B - 0 1";
+        assert_eq!(Board::from_fen(inner_rook).unwrap().to_fen(), inner_rook);
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // castling is the king taking its own rook, the king on b1 would pass through e1 to get to g1
+        let fen = "4r1k1/8/8/8/8/8/8/RK5R w KQ - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(board.is_chess960());
+        board.make_move(Move::from_coordinate_notation("b1h1", Color::White).unwrap()).unwrap_err();
+        board.make_move(Move::from_coordinate_notation("b1a1", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "4r1k1/8/8/8/8/8/8/2KR3R b - - 1 1");
+        board.undo_last_move();
+        assert_eq!(board.to_fen(), fen);
+
+        let mut board = Board::from_fen("6k1/8/8/8/8/8/8/RK5R w KQ - 0 1").unwrap();
+        board.make_move(Move::from_coordinate_notation("b1h1", Color::White).unwrap()).unwrap();
+        assert_eq!(board.to_fen(), "6k1/8/8/8/8/8/8/R4RK1 b - - 1 1");
+    }
+
+    #[test]
+    fn test_perft_chess960() {
+        assert_eq!(Tester::perft(&mut Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap(), 3), 12189);
+        assert_eq!(Tester::perft(&mut Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap(), 2), 807);
+    }
+
+    #[test]
+    fn test_checkmate_and_stalemate() {
+        assert!(Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap().is_checkmate());
+        assert!(Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap().is_stalemate());
+    }
+
+    #[test]
+    fn test_search_finds_mate_in_one() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mut last_score = None;
+        let best = Searcher::new(Arc::new(AtomicBool::new(false)))
+            .search(&board, SearchLimits { depth: Some(2), ..SearchLimits::default() }, |info| last_score = Some(info.score))
+            .unwrap();
+        assert_eq!(best.to_string(), "a1a8");
+        assert_eq!(last_score, Some(Score::Mate(1)));
+    }
+
+    #[test]
+    fn test_search_takes_hanging_queen() {
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let best = Searcher::new(Arc::new(AtomicBool::new(false)))
+            .search(&board, SearchLimits { depth: Some(2), ..SearchLimits::default() }, |_| {})
+            .unwrap();
+        assert_eq!(best.to_string(), "d2d5");
+    }
+
+    #[test]
+    fn test_uci_session() {
+        let (sender, receiver) = mpsc::channel();
+        let mut uci = Uci::new(sender);
+        assert!(uci.handle("uci"));
+        assert!(uci.handle("isready"));
+        assert!(uci.handle("position startpos moves e2e4 e7e5 d1h5 b8c6 f1c4 g8f6"));
+        assert!(uci.handle("go depth 2"));
+        let lines: Vec<String> = receiver.iter().take_while(|line| !line.starts_with("bestmove")).collect();
+        assert!(lines.contains(&String::from("uciok")));
+        assert!(lines.contains(&String::from("readyok")));
+        assert!(lines.iter().any(|line| line.starts_with("info depth 2 multipv 1 score mate 1")));
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)).ok(), None);
+
+        assert!(uci.handle("position startpos moves e2e4 e7e5 d1h5 b8c6 f1c4 g8f6"));
+        assert!(uci.handle("go infinite"));
+        assert!(uci.handle("stop"));
+        assert!(receiver.iter().any(|line| line.starts_with("bestmove")));
+        assert!(!uci.handle("quit"));
+    }
+
+    #[test]
+    fn test_undo_special_moves() {
+        let fen = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        for notation in &["e5d6", "b7a8q", "b7b8n", "e1g1", "e1c1", "a1a8"] {
+            board.make_move(Move::from_coordinate_notation(notation, Color::White).unwrap()).unwrap();
+            assert_ne!(board.to_fen(), fen);
+            board.undo_last_move();
+            assert_eq!(board.to_fen(), fen, "undoing {} did not restore the position", notation);
+        }
+    }
+
+    #[test]
+    fn test_xboard_session() {
+        let (sender, receiver) = mpsc::channel();
+        let mut xboard = XBoard::new(sender);
+        assert!(xboard.handle("xboard"));
+        assert!(xboard.handle("protover 2"));
+        assert!(receiver.recv().unwrap().ends_with("done=1"));
+
+        assert!(xboard.handle("new"));
+        assert!(xboard.handle("force"));
+        assert!(xboard.handle("usermove e2e4"));
+        assert!(xboard.handle("usermove e7e5"));
+        assert!(xboard.handle("usermove e1e3"));
+        assert_eq!(receiver.recv().unwrap(), "Illegal move (ImpossibleMove(\"invalid move for a king\")): e1e3");
+        assert!(xboard.handle("remove"));
+        assert!(xboard.handle("undo"));
+        assert!(xboard.handle("undo"));
+
+        assert!(xboard.handle("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"));
+        assert!(xboard.handle("sd 2"));
+        assert!(xboard.handle("go"));
+        assert_eq!(receiver.recv().unwrap(), "move a1a8");
+        // the result follows the move without waiting for another command
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)).unwrap(), "1-0 {White mates}");
+        assert!(xboard.handle("ping 7"));
+        assert_eq!(receiver.recv().unwrap(), "pong 7");
+        assert!(!xboard.handle("quit"));
+    }
+
+    #[test]
+    fn test_time_manager_limits() {
+        let clock = MockClock::new();
+        let control = TimeControl { time_left: Duration::from_secs(60), ..TimeControl::default() };
+        let time_manager = TimeManager::new(&control, Arc::new(clock.clone()));
+        assert_eq!(time_manager.hard_limit(), Duration::from_secs(6));
+        assert!(time_manager.soft_limit() < time_manager.hard_limit());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(time_manager.should_continue());
+        assert!(!time_manager.should_stop());
+        clock.advance(Duration::from_secs(5));
+        assert!(!time_manager.should_continue());
+        assert!(time_manager.should_stop());
+    }
+
+    #[test]
+    fn test_time_manager_never_oversteps_the_clock() {
+        let control = TimeControl {
+            time_left: Duration::from_millis(500),
+            increment: Duration::from_secs(2),
+            moves_to_go: Some(1),
+            overhead: Duration::from_millis(100),
+        };
+        let time_manager = TimeManager::new(&control, Arc::new(MockClock::new()));
+        assert!(time_manager.hard_limit() <= Duration::from_millis(400));
+        assert!(time_manager.soft_limit() <= time_manager.hard_limit());
+    }
+
+    #[test]
+    fn test_time_manager_stability() {
+        let control = TimeControl { time_left: Duration::from_secs(300), increment: Duration::from_secs(3), ..TimeControl::default() };
+        let e4 = Move::from_coordinate_notation("e2e4", Color::White).unwrap();
+        let d4 = Move::from_coordinate_notation("d2d4", Color::White).unwrap();
+
+        let mut settled = TimeManager::new(&control, Arc::new(MockClock::new()));
+        let mut unsure = TimeManager::new(&control, Arc::new(MockClock::new()));
+        let mut falling = TimeManager::new(&control, Arc::new(MockClock::new()));
+        for depth in 0..6 {
+            settled.on_iteration(e4, 30);
+            unsure.on_iteration(if depth % 2 == 0 { e4 } else { d4 }, 30);
+            falling.on_iteration(e4, 30 - depth * 60);
+        }
+        assert!(settled.soft_limit() < unsure.soft_limit());
+        assert!(settled.soft_limit() < falling.soft_limit());
+        assert!(unsure.soft_limit() <= unsure.hard_limit());
+    }
+
+    #[test]
+    fn test_search_under_time_control() {
+        let board = Board::starting_position();
+        let limits = SearchLimits {
+            time_control: Some(TimeControl { time_left: Duration::from_millis(300), ..TimeControl::default() }),
+            ..SearchLimits::default()
+        };
+        let started = std::time::Instant::now();
+        Searcher::new(Arc::new(AtomicBool::new(false))).search(&board, limits, |_| {}).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_zobrist_key_transpositions() {
+        let play = |moves: &[&str]| {
+            let mut board = Board::starting_position();
+            for m in moves {
+                board.make_move(Move::from_coordinate_notation(m, board.turn()).unwrap()).unwrap();
+            }
+            board.zobrist_key()
+        };
+        assert_eq!(play(&["g1f3", "g8f6", "b1c3"]), play(&["b1c3", "g8f6", "g1f3"]));
+        assert_ne!(play(&["g1f3", "g8f6", "f3g1", "f6g8"]), play(&["g1f3", "g8f6", "f3g1"]));
+        assert_ne!(play(&["e2e4"]), play(&["e2e3", "a7a6", "e3e4"]));
+        assert_eq!(play(&["g1f3", "g8f6", "f3g1", "f6g8"]), Board::starting_position().zobrist_key());
+    }
+
+    #[test]
+    fn test_transposition_table_store_and_probe() {
+        let table = TranspositionTable::new(1);
+        let entry = TableEntry {
+            depth: 7,
+            score: -MATE_IN_THREE,
+            bound: Bound::Lower,
+            best: Some(Move::from_coordinate_notation("b7b8n", Color::Black).unwrap()),
+        };
+        table.store(42, entry);
+        assert_eq!(table.probe(42, Color::Black), Some(entry));
+        assert_eq!(table.probe(43, Color::Black), None);
+
+        table.store(42, TableEntry { depth: 3, ..entry });
+        assert_eq!(table.probe(42, Color::Black).unwrap().depth, 7);
+        table.clear();
+        assert_eq!(table.probe(42, Color::Black), None);
+    }
+
+    #[test]
+    fn test_single_threaded_search_is_deterministic() {
+        let board = Board::from_fen(KIWIPETE).unwrap();
+        let run = || {
+            let mut infos = Vec::new();
+            let table = Arc::new(TranspositionTable::new(1));
+            let limits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+            search_parallel(&board, limits, 1, table, Arc::new(AtomicBool::new(false)), |info| infos.push((info.nodes, info.score, info.pv.clone())));
+            infos
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_parallel_search_finds_mate() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let table = Arc::new(TranspositionTable::new(4));
+        let limits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+        let best = search_parallel(&board, limits, 4, table, Arc::new(AtomicBool::new(false)), |_| {});
+        assert_eq!(best.unwrap().to_string(), "a1a8");
+    }
+
+    #[test]
+    fn test_multi_pv_ranks_candidates() {
+        // white can take the queen, the rook or a pawn, in that order of value
+        let board = Board::from_fen("3qk3/8/8/3r4/2P1p3/8/3Q4/4K3 w - - 0 1").unwrap();
+        let limits = SearchLimits { depth: Some(2), multi_pv: 3, ..SearchLimits::default() };
+        let mut reported = Vec::new();
+        let lines = Searcher::new(Arc::new(AtomicBool::new(false))).analyse(&board, limits, |info| reported.push((info.depth, info.multipv)));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].m.to_string(), "c4d5");
+        assert_eq!(lines[0].pv[0], lines[0].m);
+        let values: Vec<i32> = lines.iter().map(|line| match line.score {
+            Score::Centipawns(cp) => cp,
+            Score::Mate(_) => panic!("nobody is getting mated"),
+        }).collect();
+        assert!(values.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(reported, vec!((1, 1), (1, 2), (1, 3), (2, 1), (2, 2), (2, 3)));
+    }
+
+    #[test]
+    fn test_multi_pv_more_lines_than_moves() {
+        let board = Board::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let limits = SearchLimits { depth: Some(1), multi_pv: 10, ..SearchLimits::default() };
+        let lines = analyse_parallel(&board, limits, 1, Arc::new(TranspositionTable::new(1)), Arc::new(AtomicBool::new(false)), |_| {});
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_uci_multi_pv() {
+        let (sender, receiver) = mpsc::channel();
+        let mut uci = Uci::new(sender);
+        assert!(uci.handle("setoption name MultiPV value 2"));
+        assert!(uci.handle("position fen 7k/8/8/8/8/8/8/K7 w - - 0 1"));
+        assert!(uci.handle("go depth 1"));
+        let lines: Vec<String> = receiver.iter().take_while(|line| !line.starts_with("bestmove")).collect();
+        assert!(lines[0].starts_with("info depth 1 multipv 1 "));
+        assert!(lines[1].starts_with("info depth 1 multipv 2 "));
+        assert_eq!(lines.len(), 2);
+    }
+
+    const MATE_IN_THREE: i32 = 99_995;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
     struct Tester;
 
     impl Tester {
+        fn perft(board: &mut Board, depth: u32) -> u64 {
+            let moves = board.legal_moves();
+            if depth == 1 {
+                return moves.len() as u64;
+            }
+            moves.iter().map(|m| {
+                board.play(*m);
+                let nodes = Tester::perft(board, depth - 1);
+                board.undo_last_move();
+                nodes
+            }).sum()
+        }
+
+        /// puts in whichever king is missing, on the h-file out of the way of the piece under test
+        fn place_kings(board: &mut Board) {
+            for (color, y) in [(Color::White, 3), (Color::Black, 6)] {
+                if !board.pieces().any(|(_, piece)| piece == Piece::King(color)) {
+                    board.place(Piece::King(color), Location { x: 7, y }).unwrap();
+                }
+            }
+        }
+
         fn place_and_move_legal(piece: Piece, from: Location, to: Location, promotion: Option<Piece>) {
             let mut board = Board::new();
             board.place(piece, from).unwrap();
+            Tester::place_kings(&mut board);
             if let Some(promotion) = promotion {
                 board.make_move(Move::new_with_piece(from, to, promotion)).unwrap();
                 assert_eq!(board.get_piece_from(&to).unwrap(), promotion);
@@ -401,6 +1691,7 @@ mod test {
         fn place_and_move_illegal(piece: Piece, from: Location, to: Location) {
             let mut board = Board::new();
             board.place(piece, from).unwrap();
+            Tester::place_kings(&mut board);
             board.make_move(Move::new(from, to)).unwrap_err();
         }
 
@@ -424,6 +1715,7 @@ mod test {
             let mut board = Board::new();
             board.place(piece, from).unwrap();
             board.place(target, to).unwrap();
+            Tester::place_kings(&mut board);
             board.make_move(Move::new_with_opt_piece(from, to, promotion)).unwrap();
             board.get_piece_from(&to).unwrap();
         }
@@ -432,7 +1724,342 @@ mod test {
             let mut board = Board::new();
             board.place(piece, from).unwrap();
             board.place(target, to).unwrap();
+            Tester::place_kings(&mut board);
             board.make_move(Move::new(from, to)).unwrap_err();
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_syzygy() {
+        let directory = std::env::temp_dir().join(format!("chess-syzygy-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let empty = Tablebase::open(&directory).unwrap();
+        assert_eq!(empty.max_pieces(), 0);
+        assert!(Tablebase::open(directory.join("missing")).is_err());
+        // king against king needs no table
+        let kings = Board::from_fen("8/8/3k4/8/8/4K3/8/8 w - - 0 1").unwrap();
+        assert_eq!(empty.probe_wdl(&kings), Some(Wdl::Draw));
+        assert_eq!(empty.probe_dtz(&kings), Some(0));
+        let queen = Board::from_fen("8/8/8/4k3/8/8/8/K6Q w - - 0 1").unwrap();
+        assert!(!empty.covers(&queen));
+        assert_eq!(empty.probe_wdl(&queen), None);
+        assert!(!empty.covers(&Board::starting_position()));
+
+        // tables giving every position the same value: a white win with white to move, a black
+        // win with black to move and five moves to the next capture or pawn move
+        let wdl_magic = [0x71, 0xe8, 0x23, 0x5d];
+        let dtz_magic = [0xd7, 0x66, 0x0c, 0xa5];
+        let mut wdl = wdl_magic.to_vec();
+        wdl.extend(&[0x01, 0x00, 0x66, 0x55, 0xee, 0x00, 0x80, 4, 0x80, 0]);
+        std::fs::write(directory.join("KQvK.rtbw"), &wdl).unwrap();
+        let mut dtz = dtz_magic.to_vec();
+        dtz.extend(&[0x01, 0x00, 0x06, 0x05, 0x0e, 0x00, 0x80, 5]);
+        std::fs::write(directory.join("KQvK.rtbz"), &dtz).unwrap();
+        // one coded with a bit a position, a win for 0 and a draw for 1
+        let mut huffman = wdl_magic.to_vec();
+        huffman.extend(&[0x01, 0x00, 0x66, 0x44, 0xee, 0x00]);
+        huffman.extend(&[0x00, 13, 15, 0, 1, 0, 0, 0, 1, 1, 0, 0, 2, 0, 4, 0xf0, 0xff, 2, 0xf0, 0xff]);
+        huffman.extend(&[0x80, 0]);
+        huffman.extend(&[0, 0, 0, 0, 0x00, 0x40, 0x63, 0x7a]);
+        huffman.resize(64, 0);
+        let bit = |i: u64| (i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 40) & 1 == 1;
+        huffman.extend((0..8192u64).map(|byte| (0..8).fold(0u8, |acc, i| acc << 1 | bit(byte * 8 + i) as u8)));
+        std::fs::write(directory.join("KRvK.rtbw"), &huffman).unwrap();
+        std::fs::write(directory.join("KBvK.rtbw"), [0, 1, 2, 3, 4, 5]).unwrap();
+
+        let tablebase = Tablebase::open(&directory).unwrap();
+        assert_eq!(tablebase.max_pieces(), 3);
+        assert!(tablebase.covers(&queen));
+        assert!(!tablebase.covers(&Board::from_fen("4k2r/8/8/8/8/8/8/4K3 w k - 0 1").unwrap()));
+        assert_eq!(tablebase.probe_wdl(&queen), Some(Wdl::Win));
+        assert_eq!(tablebase.probe_dtz(&queen), Some(11));
+        let defending = Board::from_fen("8/8/8/4k3/8/8/8/K6Q b - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_wdl(&defending), Some(Wdl::Loss));
+        assert_eq!(tablebase.probe_dtz(&defending), Some(-12));
+        // taking the queen draws, whatever the table says
+        let hanging = Board::from_fen("8/8/8/4k3/4Q3/8/8/K7 b - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_wdl(&hanging), Some(Wdl::Draw));
+        let black_queen = Board::from_fen("8/8/8/4K3/8/8/8/k6q w - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_wdl(&black_queen), Some(Wdl::Loss));
+        assert_eq!(tablebase.outcome(&black_queen), Some(Outcome::Win(Color::Black)));
+        // a bad table is left out rather than guessed at
+        assert_eq!(tablebase.probe_wdl(&Board::from_fen("8/8/8/4k3/8/8/8/K6B w - - 0 1").unwrap()), None);
+
+        let mate = Board::from_fen("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1").unwrap();
+        let (m, dtz) = tablebase.best_move(&mate).unwrap();
+        assert_eq!(dtz, 1);
+        let mut mated = mate.clone();
+        mated.make_move(m).unwrap();
+        assert!(mated.is_checkmate());
+
+        let mut game = TimedGame::new(queen.clone(), Control::sudden_death(Duration::from_secs(60)), Arc::new(MockClock::new()));
+        assert_eq!(game.adjudicate(&tablebase), Some(Outcome::Win(Color::White)));
+        assert_eq!(game.clock().running(), None);
+        assert_eq!(game.adjudicate(&empty), Some(Outcome::Win(Color::White)));
+
+        // the search looks the position up once the rook is taken, and rates it below any mate
+        let rook = Board::from_fen("8/8/8/4k3/8/8/8/K3r2Q w - - 0 1").unwrap();
+        let mut score = None;
+        let limits = SearchLimits { depth: Some(2), tablebase: Some(Arc::new(Tablebase::open(&directory).unwrap())), ..SearchLimits::default() };
+        let best = Searcher::new(Arc::new(AtomicBool::new(false))).search(&rook, limits, |info| score = Some(info.score));
+        assert_eq!(best.unwrap().to_string(), "h1e1");
+        match score {
+            Some(Score::Centipawns(cp)) => assert!(cp > 90_000, "{}", cp),
+            other => panic!("expected a tablebase win, not {:?}", other),
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut uci = Uci::new(sender);
+        assert!(uci.handle(&format!("setoption name SyzygyPath value {}", directory.display())));
+        assert_eq!(receiver.recv().unwrap(), "info string found 4 tablebase files");
+        assert!(uci.handle("position fen k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"));
+        assert!(uci.handle("go movetime 1000"));
+        assert_eq!(receiver.recv().unwrap(), "info string tablebase move, dtz 1");
+        assert_eq!(receiver.recv().unwrap(), format!("bestmove {}", m));
+        assert!(uci.handle("setoption name SyzygyPath value <empty>"));
+
+        // positions the same but for turning the board around or swapping the colors index the
+        // same value
+        let fen = |pieces: &[(i32, i32, char)], turn: char| {
+            let mut board = Board::with_dimensions(8, 8).unwrap();
+            for (x, y, letter) in pieces {
+                board.place(Piece::from_char(*letter).unwrap(), Location::new(*x, *y)).unwrap();
+            }
+            let placement = board.to_fen().split(' ').next().unwrap().to_string();
+            Board::from_fen(&format!("{} {} - - 0 1", placement, turn)).unwrap()
+        };
+        let mut seed = 12345u64;
+        let mut seen = Vec::new();
+        while seen.len() < 40 {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let squares = [(seed % 64) as i32, (seed / 64 % 64) as i32, (seed / 4096 % 64) as i32];
+            let [(kx, ky), (rx, ry), (ox, oy)] = [0, 1, 2].map(|i| (squares[i] % 8, squares[i] / 8));
+            let touching = (kx - ox).abs() <= 1 && (ky - oy).abs() <= 1;
+            if squares[0] == squares[1] || squares[1] == squares[2] || squares[0] == squares[2]
+                || touching || rx == ox || ry == oy {
+                continue;
+            }
+            let mut values = Vec::new();
+            for symmetry in 0..8 {
+                let turn = |(x, y): (i32, i32)| {
+                    let (x, y) = if symmetry & 4 != 0 { (y, x) } else { (x, y) };
+                    (if symmetry & 1 != 0 { 7 - x } else { x }, if symmetry & 2 != 0 { 7 - y } else { y })
+                };
+                let (k, r, o) = (turn((kx, ky)), turn((rx, ry)), turn((ox, oy)));
+                values.push(tablebase.probe_wdl(&fen(&[(k.0, k.1, 'K'), (r.0, r.1, 'R'), (o.0, o.1, 'k')], 'w')));
+                values.push(tablebase.probe_wdl(&fen(&[(k.0, 7 - k.1, 'k'), (r.0, 7 - r.1, 'r'), (o.0, 7 - o.1, 'K')], 'b')));
+            }
+            assert!(values[0].is_some());
+            assert!(values.iter().all(|value| *value == values[0]), "{:?}", values);
+            seen.push(values[0].unwrap());
+        }
+        assert!(seen.contains(&Wdl::Win) && seen.contains(&Wdl::Draw));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    // the published tables are not kept in the repository, so this runs only when asked for with
+    // cargo test --release -- --ignored and SYZYGY_PATH naming a directory with KQvK, KRvK and
+    // KPvK in it
+    #[test]
+    #[ignore = "needs the published KQvK, KRvK and KPvK tables in SYZYGY_PATH"]
+    fn test_syzygy_published_tables() {
+        // squares run from 0 for a1 to 63 for h8. A value is the number of plies to the next
+        // capture, pawn move or mate with perfect play, positive for white to move and winning
+        // and zero or below for black to move and losing, with None a draw.
+        fn touching(a: i32, b: i32) -> bool {
+            (a % 8 - b % 8).abs() <= 1 && (a / 8 - b / 8).abs() <= 1
+        }
+        fn king_steps(from: i32) -> Vec<i32> {
+            (0..64).filter(|to| *to != from && touching(from, *to)).collect()
+        }
+        fn rays(kind: char, from: i32, blocker: i32) -> Vec<i32> {
+            let directions: &[(i32, i32)] = if kind == 'R' {
+                &[(1, 0), (-1, 0), (0, 1), (0, -1)]
+            } else {
+                &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)]
+            };
+            let mut squares = Vec::new();
+            for (dx, dy) in directions {
+                let (mut x, mut y) = (from % 8 + dx, from / 8 + dy);
+                while (0..8).contains(&x) && (0..8).contains(&y) && y * 8 + x != blocker {
+                    squares.push(y * 8 + x);
+                    x += dx;
+                    y += dy;
+                }
+            }
+            squares
+        }
+        fn attacks(kind: char, from: i32, to: i32, blocker: i32) -> bool {
+            match kind {
+                'P' => (from % 8 - to % 8).abs() == 1 && to / 8 == from / 8 + 1,
+                _ => rays(kind, from, blocker).contains(&to),
+            }
+        }
+        fn index(white_king: i32, piece: i32, black_king: i32, black: bool) -> usize {
+            (((white_king * 64 + piece) * 64 + black_king) * 2) as usize + black as usize
+        }
+        fn legal(kind: char, white_king: i32, piece: i32, black_king: i32, black: bool) -> bool {
+            white_king != piece && piece != black_king && !touching(white_king, black_king)
+                && (kind != 'P' || (1..7).contains(&(piece / 8)))
+                && (black || !attacks(kind, piece, black_king, white_king))
+        }
+        // the moves as the table the position after is in, D for a draw, its index there and
+        // whether the move resets the fifty move counter
+        fn moves(kind: char, white_king: i32, piece: i32, black_king: i32, black: bool) -> Vec<(char, usize, bool)> {
+            let mut moves = Vec::new();
+            if black {
+                for to in king_steps(black_king) {
+                    if touching(to, white_king) {
+                        continue;
+                    }
+                    if to == piece {
+                        moves.push(('D', 0, true));
+                    } else if !attacks(kind, piece, to, white_king) {
+                        moves.push((kind, index(white_king, piece, to, false), false));
+                    }
+                }
+                return moves;
+            }
+            for to in king_steps(white_king) {
+                if to != piece && !touching(to, black_king) {
+                    moves.push((kind, index(to, piece, black_king, true), false));
+                }
+            }
+            if kind != 'P' {
+                for to in rays(kind, piece, white_king) {
+                    if to == black_king {
+                        continue;
+                    }
+                    moves.push((kind, index(white_king, to, black_king, true), false));
+                }
+                return moves;
+            }
+            let pushes = if piece / 8 == 1 { vec!(piece + 8, piece + 16) } else { vec!(piece + 8) };
+            for to in pushes {
+                if to == white_king || to == black_king {
+                    break;
+                }
+                if to / 8 == 7 {
+                    moves.push(('Q', index(white_king, to, black_king, true), true));
+                    moves.push(('R', index(white_king, to, black_king, true), true));
+                    moves.push(('D', 0, true));
+                } else {
+                    moves.push(('P', index(white_king, to, black_king, true), true));
+                }
+            }
+            moves
+        }
+        // works back from the mates a ply at a time over the positions with the piece on one of
+        // the given squares, which can only reset the counter into tables already solved
+        fn solve(kind: char, squares: &[i32], values: &mut [Option<i32>], solved: &[(char, &[Option<i32>])]) {
+            let mut plies = 0;
+            loop {
+                let mut changed = false;
+                for black in [false, true] {
+                    for white_king in 0..64 {
+                        for piece in squares.iter().cloned() {
+                            for black_king in 0..64 {
+                                let i = index(white_king, piece, black_king, black);
+                                if !legal(kind, white_king, piece, black_king, black) || values[i].is_some() {
+                                    continue;
+                                }
+                                let moves = moves(kind, white_king, piece, black_king, black);
+                                let value = |table: char, i: usize| match table {
+                                    'D' => None,
+                                    _ if table == kind => values[i],
+                                    _ => solved.iter().find(|(solved, _)| *solved == table).unwrap().1[i],
+                                };
+                                let found = if black {
+                                    let replies: Vec<Option<i32>> = moves.iter().map(|(table, i, _)| value(*table, *i)).collect();
+                                    if moves.is_empty() {
+                                        plies == 0 && attacks(kind, piece, black_king, white_king)
+                                    } else {
+                                        replies.iter().all(|reply| reply.is_some()) && replies.iter().max() == Some(&Some(plies - 1))
+                                    }
+                                } else {
+                                    moves.iter().any(|(table, i, zeroing)| match (value(*table, *i), zeroing) {
+                                        (Some(_), true) => plies == 1,
+                                        (Some(reply), false) => reply == 1 - plies,
+                                        (None, _) => false,
+                                    })
+                                };
+                                if found {
+                                    values[i] = Some(if black { -plies } else { plies });
+                                    changed = true;
+                                }
+                            }
+                        }
+                    }
+                }
+                if !changed && plies > 0 {
+                    break;
+                }
+                plies += 1;
+            }
+        }
+
+        let directory = std::env::var_os("SYZYGY_PATH").expect("SYZYGY_PATH names a directory of syzygy tables");
+        let tablebase = Tablebase::open(directory).unwrap();
+        let all: Vec<i32> = (0..64).collect();
+        let mut queens = vec!(None; 64 * 64 * 64 * 2);
+        solve('Q', &all, &mut queens, &[]);
+        let mut rooks = vec!(None; 64 * 64 * 64 * 2);
+        solve('R', &all, &mut rooks, &[]);
+        let mut pawns = vec!(None; 64 * 64 * 64 * 2);
+        for rank in (1..7).rev() {
+            let squares: Vec<i32> = (rank * 8..rank * 8 + 8).collect();
+            solve('P', &squares, &mut pawns, &[('Q', &queens[..]), ('R', &rooks[..])]);
+        }
+        // the longest wins the queen and the rook take, to the ply
+        assert_eq!(queens.iter().flatten().max(), Some(&19));
+        assert_eq!(rooks.iter().flatten().max(), Some(&31));
+
+        let board = |pieces: &[(i32, char)], black: bool| {
+            let mut board = Board::with_dimensions(8, 8).unwrap();
+            for (square, letter) in pieces {
+                board.place(Piece::from_char(*letter).unwrap(), Location::new(square % 8, square / 8)).unwrap();
+            }
+            let placement = board.to_fen().split(' ').next().unwrap().to_string();
+            Board::from_fen(&format!("{} {} - - 0 1", placement, if black { 'b' } else { 'w' })).unwrap()
+        };
+        for (kind, values) in [('Q', &queens), ('R', &rooks), ('P', &pawns)] {
+            let mut checked = 0;
+            for white_king in 0..64 {
+                for piece in 0..64 {
+                    for black_king in 0..64 {
+                        for black in [false, true] {
+                            let i = index(white_king, piece, black_king, black);
+                            if !i.is_multiple_of(13) || !legal(kind, white_king, piece, black_king, black) {
+                                continue;
+                            }
+                            let lower = kind.to_ascii_lowercase();
+                            // and the same with the colors swapped and the board turned around
+                            let positions = [
+                                board(&[(white_king, 'K'), (piece, kind), (black_king, 'k')], black),
+                                board(&[(white_king ^ 56, 'k'), (piece ^ 56, lower), (black_king ^ 56, 'K')], !black),
+                            ];
+                            for position in positions.iter() {
+                                let (wdl, dtz) = (tablebase.probe_wdl(position), tablebase.probe_dtz(position).unwrap());
+                                match values[i] {
+                                    None => assert_eq!((wdl, dtz), (Some(Wdl::Draw), 0), "{}", position.to_fen()),
+                                    Some(value) if value > 0 => {
+                                        assert_eq!(wdl, Some(Wdl::Win), "{}", position.to_fen());
+                                        assert!(dtz == value || dtz == value + 1, "{} has dtz {} not {}", position.to_fen(), dtz, value);
+                                    }
+                                    Some(value) => {
+                                        assert_eq!(wdl, Some(Wdl::Loss), "{}", position.to_fen());
+                                        assert!(value == 0 || dtz == value || dtz == value - 1, "{} has dtz {} not {}", position.to_fen(), dtz, value);
+                                    }
+                                }
+                            }
+                            checked += 1;
+                        }
+                    }
+                }
+            }
+            assert!(checked > 10_000, "only {} positions checked", checked);
+        }
+    }
+}
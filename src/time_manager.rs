@@ -0,0 +1,135 @@
+pub mod time_manager {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use crate::chess_move::chess_move::Move;
+
+    /// where the time manager gets the time from, so tests can move time along by hand
+    pub trait Clock: Send + Sync {
+        /// time since some fixed point, only differences between calls mean anything
+        fn now(&self) -> Duration;
+    }
+
+    pub struct SystemClock {
+        epoch: Instant,
+    }
+
+    impl Default for SystemClock {
+        fn default() -> Self {
+            SystemClock::new()
+        }
+    }
+
+    impl SystemClock {
+        pub fn new() -> Self {
+            SystemClock { epoch: Instant::now() }
+        }
+    }
+
+    impl Clock for SystemClock {
+        fn now(&self) -> Duration {
+            self.epoch.elapsed()
+        }
+    }
+
+    /// a clock that only moves when told to, clones share the same time
+    #[derive(Clone, Default)]
+    pub struct MockClock {
+        now: Arc<Mutex<Duration>>,
+    }
+
+    impl MockClock {
+        pub fn new() -> Self {
+            MockClock::default()
+        }
+
+        pub fn advance(&self, by: Duration) {
+            *self.now.lock().expect("mock clock poisoned") += by;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Duration {
+            *self.now.lock().expect("mock clock poisoned")
+        }
+    }
+
+    /// the state of our clock when a search starts
+    #[derive(Clone, Debug, Default)]
+    pub struct TimeControl {
+        pub time_left: Duration,
+        pub increment: Duration,
+        /// moves until the next time control, None for sudden death
+        pub moves_to_go: Option<u32>,
+        /// held back for the time lost talking to the GUI
+        pub overhead: Duration,
+    }
+
+    /// decides how long a search may run. The soft limit is when we'd like to stop, checked between
+    /// iterations and stretched when the search is unsure of itself. The hard limit is when we must
+    /// stop, even in the middle of an iteration.
+    pub struct TimeManager {
+        clock: Arc<dyn Clock>,
+        started: Duration,
+        soft: Duration,
+        hard: Duration,
+        last_iteration: Option<(Move, i32)>,
+        best_move_changes: f64,
+        score_drop: i32,
+    }
+
+    impl TimeManager {
+        pub fn new(control: &TimeControl, clock: Arc<dyn Clock>) -> Self {
+            let available = control.time_left.checked_sub(control.overhead).unwrap_or_default();
+            let moves_to_go = control.moves_to_go.unwrap_or(30).clamp(1, 50);
+            let soft = (available / moves_to_go + control.increment * 3 / 4).min(available);
+            let hard = (soft * 3).min(available * 3 / 4).max(soft).min(available);
+            TimeManager {
+                started: clock.now(),
+                clock,
+                soft,
+                hard,
+                last_iteration: None,
+                best_move_changes: 0.0,
+                score_drop: 0,
+            }
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            self.clock.now() - self.started
+        }
+
+        /// tells the time manager what the latest finished iteration came up with, score in centipawns
+        pub fn on_iteration(&mut self, best: Move, score: i32) {
+            self.best_move_changes /= 2.0;
+            if let Some((previous_best, previous_score)) = self.last_iteration {
+                if previous_best != best {
+                    self.best_move_changes += 1.0;
+                }
+                self.score_drop = (previous_score - score).max(0);
+            }
+            self.last_iteration = Some((best, score));
+        }
+
+        /// the soft limit after accounting for how settled the search is. A best move that keeps
+        /// changing or a score that is falling buys more time, a settled search gives some back.
+        pub fn soft_limit(&self) -> Duration {
+            let instability = 0.7 + self.best_move_changes;
+            let falling = 1.0 + (self.score_drop as f64 / 100.0).min(1.0);
+            self.soft.mul_f64((instability * falling).clamp(0.5, 3.0)).min(self.hard)
+        }
+
+        pub fn hard_limit(&self) -> Duration {
+            self.hard
+        }
+
+        /// whether another iteration is worth starting
+        pub fn should_continue(&self) -> bool {
+            self.elapsed() < self.soft_limit()
+        }
+
+        /// whether the search has to stop right now
+        pub fn should_stop(&self) -> bool {
+            self.elapsed() >= self.hard
+        }
+    }
+}
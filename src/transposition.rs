@@ -0,0 +1,140 @@
+pub mod transposition {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use crate::chess::{Piece, Color};
+    use crate::chess_move::chess_move::Move;
+    use crate::location::location::{Location, MAX_SIZE};
+
+    pub const DEFAULT_HASH_MB: usize = 16;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum Bound {
+        Exact,
+        /// the score is at least this, the search failed high
+        Lower,
+        /// the score is at most this, the search failed low
+        Upper,
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct TableEntry {
+        pub depth: u32,
+        pub score: i32,
+        pub bound: Bound,
+        /// best move with any promotion given to the side to move
+        pub best: Option<Move>,
+    }
+
+    struct Slot {
+        /// the position key xor'd with data, so a torn read from two threads writing at once
+        /// shows up as a different key instead of a wrong answer
+        check: AtomicU64,
+        data: AtomicU64,
+    }
+
+    /// a hash table of search results that any number of threads can read and write without
+    /// locking. Writes can be lost when threads race, which only costs a little search time.
+    pub struct TranspositionTable {
+        slots: Vec<Slot>,
+    }
+
+    impl TranspositionTable {
+        pub fn new(megabytes: usize) -> Self {
+            let wanted = megabytes.max(1) * 1024 * 1024 / std::mem::size_of::<Slot>();
+            // a power of two so a slot can be picked with a mask
+            let len = 1 << (63 - (wanted as u64).leading_zeros());
+            TranspositionTable {
+                slots: (0..len).map(|_| Slot { check: AtomicU64::new(0), data: AtomicU64::new(0) }).collect(),
+            }
+        }
+
+        fn slot(&self, key: u64) -> &Slot {
+            &self.slots[(key as usize) & (self.slots.len() - 1)]
+        }
+
+        pub fn probe(&self, key: u64, turn: Color) -> Option<TableEntry> {
+            let slot = self.slot(key);
+            let data = slot.data.load(Ordering::Relaxed);
+            if data == 0 || slot.check.load(Ordering::Relaxed) ^ data != key {
+                return None;
+            }
+            Some(TableEntry {
+                best: unpack_move(data as u32 & 0xf_ffff, turn),
+                depth: (data >> 20) as u8 as u32,
+                bound: match (data >> 28) & 3 {
+                    0 => Bound::Exact,
+                    1 => Bound::Lower,
+                    _ => Bound::Upper,
+                },
+                score: (data >> 32) as u32 as i32,
+            })
+        }
+
+        pub fn store(&self, key: u64, entry: TableEntry) {
+            let slot = self.slot(key);
+            let existing = slot.data.load(Ordering::Relaxed);
+            let same_position = slot.check.load(Ordering::Relaxed) ^ existing == key;
+            // keep deeper results for the same position, they cost more to come by
+            if same_position && ((existing >> 20) as u8 as u32) > entry.depth && entry.bound != Bound::Exact {
+                return;
+            }
+            let bound = match entry.bound {
+                Bound::Exact => 0,
+                Bound::Lower => 1,
+                Bound::Upper => 2,
+            };
+            let data = entry.best.map_or(0, pack_move) as u64
+                | (entry.depth.min(255) as u64) << 20
+                | bound << 28
+                | (entry.score as u32 as u64) << 32;
+            slot.check.store(key ^ data, Ordering::Relaxed);
+            slot.data.store(data, Ordering::Relaxed);
+        }
+
+        pub fn clear(&self) {
+            for slot in &self.slots {
+                slot.check.store(0, Ordering::Relaxed);
+                slot.data.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// from and to as 8 bit square numbers, enough for a 16x16 board, then 3 bits of promotion. A
+    /// drop has from and to the same, which no other move does, and the piece dropped in place of
+    /// the promotion.
+    fn pack_move(m: Move) -> u32 {
+        let square = |location: Location| (location.x + MAX_SIZE * location.y) as u32;
+        let piece = match m.promoted.or(m.dropped) {
+            None => 0,
+            Some(Piece::Knight(_)) => 1,
+            Some(Piece::Bishop(_)) => 2,
+            Some(Piece::Rook(_)) => 3,
+            Some(Piece::Queen(_)) => 4,
+            Some(Piece::Pawn(_)) => 5,
+            Some(Piece::King(_)) => 6,
+            // there is no room left for these, the move just won't match when it comes back out
+            Some(Piece::Fairy(..)) => 0,
+        };
+        square(m.from) | square(m.to) << 8 | piece << 16
+    }
+
+    fn unpack_move(packed: u32, turn: Color) -> Option<Move> {
+        if packed == 0 {
+            return None;
+        }
+        let square = |bits: u32| Location::new((bits & 15) as i32, ((bits >> 4) & 15) as i32);
+        let piece = match packed >> 16 {
+            1 => Some(Piece::Knight(turn)),
+            2 => Some(Piece::Bishop(turn)),
+            3 => Some(Piece::Rook(turn)),
+            4 => Some(Piece::Queen(turn)),
+            5 => Some(Piece::Pawn(turn)),
+            6 => Some(Piece::King(turn)),
+            _ => None,
+        };
+        let (from, to) = (square(packed), square(packed >> 8));
+        match piece {
+            Some(piece) if from == to => Some(Move::new_drop(piece, to)),
+            promoted => Some(Move::new_with_opt_piece(from, to, promoted)),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+pub mod tui {
+    use std::time::Duration;
+    use crate::board::board::Board;
+    use crate::chess::{Piece, Color};
+    use crate::chess_move::chess_move::Move;
+    use crate::location::location::Location;
+    use crate::pgn::pgn::to_san;
+    use crate::render::render::glyph;
+
+    const RESET: &str = "\x1b[0m";
+    const LIGHT: &str = "\x1b[48;5;180m";
+    const DARK: &str = "\x1b[48;5;137m";
+    const LAST_MOVE: &str = "\x1b[48;5;143m";
+    const SELECTED: &str = "\x1b[48;5;220m";
+    const DESTINATION: &str = "\x1b[48;5;114m";
+    const CURSOR: &str = "\x1b[48;5;75m";
+    const WHITE_PIECE: &str = "\x1b[1;97m";
+    const BLACK_PIECE: &str = "\x1b[1;30m";
+
+    /// the columns taken by the rank numbers left of the board
+    const LABELS: usize = 3;
+    /// the columns taken by one square
+    const SQUARE: usize = 3;
+
+    /// something the user did, as read from the terminal
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum Input {
+        Up,
+        Down,
+        Left,
+        Right,
+        /// enter or space, picks up or puts down a piece at the cursor
+        Select,
+        /// escape, drops whatever is picked up
+        Cancel,
+        Char(char),
+        /// a left click at a column and row counted from 1, the way terminals report them
+        Click { column: usize, row: usize },
+    }
+
+    /// reads keys and SGR mouse reports out of what the terminal sent in raw mode
+    pub fn parse_input(bytes: &[u8]) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+            if rest.starts_with(b"\x1b[<") {
+                // \x1b[<button;column;rowM for a press, m for a release
+                let end = match rest.iter().position(|b| *b == b'M' || *b == b'm') {
+                    Some(end) => end,
+                    None => break,
+                };
+                let numbers: Vec<usize> = String::from_utf8_lossy(&rest[3..end]).split(';').filter_map(|n| n.parse().ok()).collect();
+                if let (b'M', [0, column, row]) = (rest[end], &numbers[..]) {
+                    inputs.push(Input::Click { column: *column, row: *row });
+                }
+                i += end + 1;
+                continue;
+            }
+            let (input, length) = match rest {
+                [0x1b, b'[', b'A', ..] => (Some(Input::Up), 3),
+                [0x1b, b'[', b'B', ..] => (Some(Input::Down), 3),
+                [0x1b, b'[', b'C', ..] => (Some(Input::Right), 3),
+                [0x1b, b'[', b'D', ..] => (Some(Input::Left), 3),
+                [0x1b, b'[', ..] | [0x1b, b'O', ..] => (None, 3),
+                [0x1b, ..] => (Some(Input::Cancel), 1),
+                [b'\r', ..] | [b'\n', ..] | [b' ', ..] => (Some(Input::Select), 1),
+                [c, ..] if c.is_ascii_graphic() => (Some(Input::Char(*c as char)), 1),
+                _ => (None, 1),
+            };
+            inputs.extend(input);
+            i += length;
+        }
+        inputs
+    }
+
+    /// a full screen game on a Board: a cursor and a picked up piece on the board panel, the
+    /// moves played so far, the clocks and whatever the engine has to say about the position
+    pub struct Tui {
+        board: Board,
+        history: Vec<String>,
+        cursor: Location,
+        selected: Option<Location>,
+        orientation: Color,
+        /// what pawns promote to when they get to the last rank
+        promotion: char,
+        clocks: Option<[Duration; 2]>,
+        analysis: Vec<String>,
+        message: String,
+    }
+
+    impl Tui {
+        pub fn new(board: Board) -> Self {
+            let cursor = Location::new(board.width() / 2, 1.min(board.height() - 1));
+            Tui {
+                board,
+                history: Vec::new(),
+                cursor,
+                selected: None,
+                orientation: Color::White,
+                promotion: 'q',
+                clocks: None,
+                analysis: Vec::new(),
+                message: String::from("arrows and enter or the mouse to move, f to flip, u to undo, p to pick the promotion, q to quit"),
+            }
+        }
+
+        pub fn board(&self) -> &Board {
+            &self.board
+        }
+
+        pub fn cursor(&self) -> Location {
+            self.cursor
+        }
+
+        pub fn selected(&self) -> Option<Location> {
+            self.selected
+        }
+
+        /// the moves played so far in algebraic notation
+        pub fn history(&self) -> &[String] {
+            &self.history
+        }
+
+        pub fn set_clocks(&mut self, clocks: Option<[Duration; 2]>) {
+            self.clocks = clocks;
+        }
+
+        /// lines for the analysis pane, best first
+        pub fn set_analysis(&mut self, lines: Vec<String>) {
+            self.analysis = lines;
+        }
+
+        pub fn set_message(&mut self, message: &str) {
+            self.message = String::from(message);
+        }
+
+        /// where the piece that is picked up can go
+        pub fn destinations(&self) -> Vec<Location> {
+            match self.selected {
+                Some(from) => self.board.legal_moves_from(from).iter().map(|m| m.to).collect(),
+                None => Vec::new(),
+            }
+        }
+
+        /// plays a move that came from elsewhere, such as the engine, keeping the move list in step
+        pub fn play(&mut self, m: Move) -> bool {
+            let san = to_san(&self.board, m);
+            if self.board.make_move(m).is_err() {
+                return false;
+            }
+            self.history.push(san);
+            self.selected = None;
+            true
+        }
+
+        pub fn undo(&mut self) {
+            if self.board.last_move().is_some() {
+                self.board.undo_last_move();
+                self.history.pop();
+            }
+            self.selected = None;
+        }
+
+        /// acts on the input, returning the move if one was played
+        pub fn handle(&mut self, input: Input) -> Option<Move> {
+            let (forward, right) = match self.orientation {
+                Color::White => (1, 1),
+                Color::Black => (-1, -1),
+            };
+            match input {
+                Input::Up => self.move_cursor(0, forward),
+                Input::Down => self.move_cursor(0, -forward),
+                Input::Right => self.move_cursor(right, 0),
+                Input::Left => self.move_cursor(-right, 0),
+                Input::Select => return self.choose(self.cursor),
+                Input::Cancel => self.selected = None,
+                Input::Click { column, row } => {
+                    if let Some(square) = self.square_at(column, row) {
+                        self.cursor = square;
+                        return self.choose(square);
+                    }
+                }
+                Input::Char('f') => self.orientation = self.orientation.opposite(),
+                Input::Char('u') => self.undo(),
+                Input::Char('p') => {
+                    self.promotion = match self.promotion {
+                        'q' => 'r',
+                        'r' => 'b',
+                        'b' => 'n',
+                        _ => 'q',
+                    };
+                    self.message = format!("pawns now promote to a {}", match self.promotion {
+                        'q' => "queen",
+                        'r' => "rook",
+                        'b' => "bishop",
+                        _ => "knight",
+                    });
+                }
+                Input::Char(_) => {}
+            }
+            None
+        }
+
+        fn move_cursor(&mut self, x: i32, y: i32) {
+            let moved = self.cursor + Location::new(x, y);
+            if self.board.contains(&moved) {
+                self.cursor = moved;
+            }
+        }
+
+        /// picks up the piece on the square, or puts down the one picked up if it can go there
+        fn choose(&mut self, square: Location) -> Option<Move> {
+            if let Some(from) = self.selected {
+                let moves: Vec<Move> = self.board.legal_moves_from(from).into_iter().filter(|m| m.to == square).collect();
+                let promotion = moves.iter().find(|m| m.promoted.is_some_and(|piece| piece.to_char().to_ascii_lowercase() == self.promotion));
+                if let Some(m) = promotion.or_else(|| moves.first()).cloned() {
+                    if self.play(m) {
+                        self.message = format!("played {}", self.history.last().map_or("", |san| san.as_str()));
+                        return Some(m);
+                    }
+                }
+            }
+            self.selected = match self.board.get_piece_from(&square) {
+                Some(piece) if *piece.color() == self.board.turn() && Some(square) != self.selected => Some(square),
+                _ => None,
+            };
+            None
+        }
+
+        /// the square drawn at a column and row counted from 1, if any
+        pub fn square_at(&self, column: usize, row: usize) -> Option<Location> {
+            let (width, height) = (self.board.width() as usize, self.board.height() as usize);
+            if column <= LABELS || row < 2 || row > height + 1 {
+                return None;
+            }
+            let file = (column - 1 - LABELS) / SQUARE;
+            if file >= width {
+                return None;
+            }
+            let line = row - 2;
+            Some(match self.orientation {
+                Color::White => Location::new(file as i32, (height - 1 - line) as i32),
+                Color::Black => Location::new((width - 1 - file) as i32, line as i32),
+            })
+        }
+
+        /// the whole screen, to be written after clearing it. Lines are kept to the given height.
+        pub fn draw(&self, rows: usize) -> String {
+            let (width, height) = (self.board.width(), self.board.height());
+            let files: Vec<i32> = match self.orientation {
+                Color::White => (0..width).collect(),
+                Color::Black => (0..width).rev().collect(),
+            };
+            let ranks: Vec<i32> = match self.orientation {
+                Color::White => (0..height).rev().collect(),
+                Color::Black => (0..height).collect(),
+            };
+            let destinations = self.destinations();
+            let last_move = self.board.last_move();
+
+            // the board panel on the left, each line with how many columns it shows
+            let mut left: Vec<(String, usize)> = vec!((String::from(" chess"), 6));
+            for y in &ranks {
+                let mut line = format!("{:>2} ", y + 1);
+                for x in &files {
+                    let square = Location::new(*x, *y);
+                    let background = if square == self.cursor {
+                        CURSOR
+                    } else if Some(square) == self.selected {
+                        SELECTED
+                    } else if destinations.contains(&square) {
+                        DESTINATION
+                    } else if last_move.is_some_and(|m| m.from == square || m.to == square) {
+                        LAST_MOVE
+                    } else if (x + y) % 2 == 0 {
+                        DARK
+                    } else {
+                        LIGHT
+                    };
+                    let piece = match self.board.get_piece_from(&square) {
+                        Some(piece) => format!("{}{}", if *piece.color() == Color::White { WHITE_PIECE } else { BLACK_PIECE }, piece_symbol(piece)),
+                        None => String::from(" "),
+                    };
+                    line.push_str(&format!("{} {} {}", background, piece, RESET));
+                }
+                left.push((line, LABELS + SQUARE * width as usize));
+            }
+            let letters: String = files.iter().map(|x| format!(" {} ", (b'a' + *x as u8) as char)).collect();
+            left.push((format!("{:width$}{}", "", letters, width = LABELS), LABELS + letters.len()));
+            left.push((String::new(), 0));
+            left.push((self.message.clone(), self.message.chars().count()));
+            left.push((String::new(), 0));
+            left.push((String::from(" analysis"), 9));
+            for line in &self.analysis {
+                left.push((line.clone(), line.chars().count()));
+            }
+
+            // clocks and the moves so far on the right, scrolled to show the latest moves
+            let mut right: Vec<String> = Vec::new();
+            right.push(match self.clocks {
+                Some([white, black]) => format!("white {}   black {}", clock_text(white), clock_text(black)),
+                None => String::from("no clocks"),
+            });
+            right.push(format!("{} to move", if self.board.turn() == Color::White { "white" } else { "black" }));
+            right.push(String::new());
+            // a game set up with black to move starts with "1. ... e5"
+            let plies = self.history.len();
+            let black_first = (plies % 2 == 1) == (self.board.turn() == Color::White);
+            let black_moves = if black_first { plies.div_ceil(2) } else { plies / 2 };
+            let mut number = (self.board.fullmove_number() as usize).saturating_sub(black_moves);
+            let mut numbered: Vec<String> = Vec::new();
+            let mut sans = self.history.iter();
+            if black_first {
+                if let Some(san) = sans.next() {
+                    numbered.push(format!("{:>3}. ...     {}", number, san));
+                    number += 1;
+                }
+            }
+            while let Some(white) = sans.next() {
+                numbered.push(format!("{:>3}. {:<8}{}", number, white, sans.next().map_or("", |san| san.as_str())));
+                number += 1;
+            }
+            let room = rows.saturating_sub(right.len()).max(1);
+            right.extend(numbered.iter().skip(numbered.len().saturating_sub(room)).cloned());
+
+            let panel_width = LABELS + SQUARE * width as usize + 4;
+            let lines = left.len().max(right.len()).min(rows.max(1));
+            let mut screen = String::new();
+            for i in 0..lines {
+                let (text, shown) = left.get(i).cloned().unwrap_or_default();
+                screen.push_str(&text);
+                if let Some(side) = right.get(i) {
+                    screen.push_str(&" ".repeat(panel_width.saturating_sub(shown)));
+                    screen.push_str(side);
+                }
+                screen.push_str("\r\n");
+            }
+            screen
+        }
+    }
+
+    fn piece_symbol(piece: Piece) -> char {
+        match piece {
+            // the outlined glyphs are hard to tell apart on a coloured background, so both sides
+            // get the solid ones and are told apart by colour
+            Piece::Fairy(..) => piece.to_char(),
+            _ => glyph(piece.with_color(Color::Black)),
+        }
+    }
+
+    fn clock_text(time: Duration) -> String {
+        let seconds = time.as_secs();
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
@@ -0,0 +1,287 @@
+pub mod uci {
+    use std::env;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use crate::board::board::Board;
+    use crate::chess::{Color, FailReason};
+    use crate::chess_move::chess_move::Move;
+    use crate::engine::engine::{SearchLimits, SearchInfo, Score, search_parallel};
+    use crate::transposition::transposition::{TranspositionTable, DEFAULT_HASH_MB};
+    use crate::time_manager::time_manager::TimeControl;
+    use crate::variant::variant::Variant;
+    use crate::polyglot::polyglot::Book;
+    use crate::syzygy::syzygy::Tablebase;
+
+    /// a Universal Chess Interface front end. Commands are fed in a line at a time with handle,
+    /// everything the engine has to say is sent down the channel a line at a time.
+    pub struct Uci {
+        board: Board,
+        out: Sender<String>,
+        stop: Arc<AtomicBool>,
+        search: Option<JoinHandle<()>>,
+        table: Arc<TranspositionTable>,
+        threads: usize,
+        multi_pv: usize,
+        move_overhead: u64,
+        /// castling is sent and received as the king taking its own rook
+        chess960: bool,
+        variant: Variant,
+        book: Option<Book>,
+        /// whether to play from the book before searching
+        own_book: bool,
+        tablebase: Option<Arc<Tablebase>>,
+    }
+
+    impl Uci {
+        pub fn new(out: Sender<String>) -> Self {
+            Uci {
+                board: Board::starting_position(),
+                out,
+                stop: Arc::new(AtomicBool::new(false)),
+                search: None,
+                table: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+                threads: 1,
+                multi_pv: 1,
+                move_overhead: 10,
+                chess960: false,
+                variant: Variant::Standard,
+                book: None,
+                own_book: false,
+                tablebase: None,
+            }
+        }
+
+        /// returns false once the GUI has asked us to quit
+        pub fn handle(&mut self, line: &str) -> bool {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.split_first() {
+                Some((&"uci", _)) => {
+                    self.send(String::from("id name chess"));
+                    self.send(String::from("id author marcusdunn"));
+                    self.send(format!("option name Hash type spin default {} min 1 max 4096", DEFAULT_HASH_MB));
+                    self.send(String::from("option name Threads type spin default 1 min 1 max 256"));
+                    self.send(String::from("option name Clear Hash type button"));
+                    self.send(String::from("option name MultiPV type spin default 1 min 1 max 256"));
+                    self.send(String::from("option name Move Overhead type spin default 10 min 0 max 5000"));
+                    self.send(String::from("option name UCI_Chess960 type check default false"));
+                    self.send(String::from("option name OwnBook type check default false"));
+                    self.send(String::from("option name BookFile type string default <empty>"));
+                    self.send(String::from("option name SyzygyPath type string default <empty>"));
+                    let variants: Vec<String> = Variant::all().iter().map(|variant| format!("var {}", variant.name())).collect();
+                    self.send(format!("option name UCI_Variant type combo default {} {}", Variant::Standard.name(), variants.join(" ")));
+                    self.send(String::from("uciok"));
+                }
+                Some((&"isready", _)) => self.send(String::from("readyok")),
+                Some((&"ucinewgame", _)) => {
+                    self.stop_search();
+                    self.board = Board::starting_position();
+                    self.table.clear();
+                }
+                Some((&"position", args)) => {
+                    self.stop_search();
+                    if let Err(reason) = self.position(args) {
+                        self.send(format!("info string {:?}", reason));
+                    }
+                }
+                Some((&"go", args)) => self.go(args),
+                Some((&"stop", _)) => self.stop_search(),
+                Some((&"setoption", args)) => self.set_option(args),
+                Some((&"quit", _)) => {
+                    self.stop_search();
+                    return false;
+                }
+                // the protocol says to ignore anything we don't understand
+                _ => {}
+            }
+            true
+        }
+
+        fn send(&self, line: String) {
+            // the receiving end only goes away when the program is shutting down
+            let _ = self.out.send(line);
+        }
+
+        fn position(&mut self, args: &[&str]) -> Result<(), FailReason> {
+            let moves_at = args.iter().position(|arg| *arg == "moves").unwrap_or(args.len());
+            let mut board = match args.split_first() {
+                Some((&"startpos", _)) => Board::from_fen(self.variant.starting_fen())?,
+                Some((&"fen", fen)) => Board::from_fen(&fen[..moves_at - 1].join(" "))?,
+                _ => return Err(FailReason::ParseError(String::from("position needs to be followed by startpos or fen")))
+            };
+            if self.chess960 {
+                board.set_chess960(true);
+            }
+            if self.variant != Variant::Standard {
+                board.set_variant(self.variant);
+            }
+            for m in args.iter().skip(moves_at + 1) {
+                board.make_move(Move::from_coordinate_notation(m, board.turn())?)?;
+            }
+            self.board = board;
+            Ok(())
+        }
+
+        fn go(&mut self, args: &[&str]) {
+            self.stop_search();
+            let limits = self.limits(args);
+            let infinite = limits.infinite;
+            if let (Some(book), true, false) = (&self.book, self.own_book, infinite) {
+                // any changing number will do to vary the openings from game to game
+                let random = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos() as u64);
+                if let Some(m) = book.choose(&self.board, random) {
+                    self.send(String::from("info string book move"));
+                    self.send(format!("bestmove {}", m));
+                    return;
+                }
+            }
+            if let (Some(tablebase), false) = (&self.tablebase, infinite) {
+                if let Some((m, dtz)) = tablebase.best_move(&self.board) {
+                    self.send(format!("info string tablebase move, dtz {}", dtz));
+                    self.send(format!("bestmove {}", m));
+                    return;
+                }
+            }
+            let board = self.board.clone();
+            let stop = Arc::clone(&self.stop);
+            let table = Arc::clone(&self.table);
+            let threads = self.threads;
+            let out = self.out.clone();
+
+            self.search = Some(thread::spawn(move || {
+                let best = search_parallel(&board, limits, threads, table, Arc::clone(&stop), |info| {
+                    let _ = out.send(info_line(info));
+                });
+                // when pondering or searching infinitely the GUI expects to have the last word
+                while infinite && !stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                let _ = out.send(match best {
+                    Some(m) => format!("bestmove {}", m),
+                    None => String::from("bestmove 0000"),
+                });
+            }));
+        }
+
+        fn limits(&self, args: &[&str]) -> SearchLimits {
+            let value = |name: &str| -> Option<u64> {
+                args.iter().position(|arg| *arg == name)
+                    .and_then(|i| args.get(i + 1))
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .map(|v| v.max(0) as u64)
+            };
+            let (time, increment) = match self.board.turn() {
+                Color::White => (value("wtime"), value("winc")),
+                Color::Black => (value("btime"), value("binc")),
+            };
+
+            let mut limits = SearchLimits {
+                depth: value("depth").map(|depth| depth as u32),
+                movetime: value("movetime").map(Duration::from_millis),
+                nodes: value("nodes"),
+                infinite: args.contains(&"infinite") || args.contains(&"ponder"),
+                time_control: None,
+                multi_pv: self.multi_pv,
+                tablebase: self.tablebase.clone(),
+            };
+            if let (Some(time), None) = (time, limits.movetime) {
+                limits.time_control = Some(TimeControl {
+                    time_left: Duration::from_millis(time),
+                    increment: Duration::from_millis(increment.unwrap_or(0)),
+                    moves_to_go: value("movestogo").map(|moves| moves as u32),
+                    overhead: Duration::from_millis(self.move_overhead),
+                });
+            }
+            if limits.infinite {
+                limits.movetime = None;
+                limits.time_control = None;
+            }
+            limits
+        }
+
+        fn set_option(&mut self, args: &[&str]) {
+            let value_at = args.iter().position(|arg| *arg == "value").unwrap_or(args.len());
+            let name = args[..value_at].iter().skip_while(|arg| **arg == "name").cloned().collect::<Vec<_>>().join(" ");
+            let value = args.iter().skip(value_at + 1).cloned().collect::<Vec<_>>().join(" ");
+            self.stop_search();
+            match name.to_lowercase().as_str() {
+                "hash" => match value.parse::<usize>() {
+                    Ok(megabytes) => self.table = Arc::new(TranspositionTable::new(megabytes)),
+                    Err(_) => self.send(format!("info string \"{}\" is not a number of megabytes", value)),
+                },
+                "threads" => match value.parse::<usize>() {
+                    Ok(threads) => self.threads = threads.max(1),
+                    Err(_) => self.send(format!("info string \"{}\" is not a number of threads", value)),
+                },
+                "clear hash" => self.table.clear(),
+                "multipv" => match value.parse::<usize>() {
+                    Ok(lines) => self.multi_pv = lines.max(1),
+                    Err(_) => self.send(format!("info string \"{}\" is not a number of lines", value)),
+                },
+                "move overhead" => match value.parse() {
+                    Ok(overhead) => self.move_overhead = overhead,
+                    Err(_) => self.send(format!("info string \"{}\" is not a number of milliseconds", value)),
+                },
+                "uci_chess960" => match value.parse() {
+                    Ok(chess960) => self.chess960 = chess960,
+                    Err(_) => self.send(format!("info string \"{}\" is not true or false", value)),
+                },
+                "ownbook" => match value.parse() {
+                    Ok(own_book) => self.own_book = own_book,
+                    Err(_) => self.send(format!("info string \"{}\" is not true or false", value)),
+                },
+                "bookfile" => match value.as_str() {
+                    "" | "<empty>" => self.book = None,
+                    path => match Book::open(path) {
+                        Ok(book) => self.book = Some(book),
+                        Err(error) => {
+                            self.book = None;
+                            self.send(format!("info string could not read the book {}: {}", path, error));
+                        }
+                    },
+                },
+                "syzygypath" => match value.as_str() {
+                    "" | "<empty>" => self.tablebase = None,
+                    paths => {
+                        let mut tablebase = Tablebase::new();
+                        let mut found = 0;
+                        for directory in env::split_paths(paths) {
+                            match tablebase.add_directory(&directory) {
+                                Ok(tables) => found += tables,
+                                Err(error) => self.send(format!("info string could not read {}: {}", directory.display(), error)),
+                            }
+                        }
+                        self.send(format!("info string found {} tablebase files", found));
+                        self.tablebase = Some(Arc::new(tablebase));
+                    }
+                },
+                "uci_variant" => match Variant::from_name(&value) {
+                    Ok(variant) => self.variant = variant,
+                    Err(_) => self.send(format!("info string \"{}\" is not a variant we play", value)),
+                },
+                _ => self.send(format!("info string no option named \"{}\"", name)),
+            }
+        }
+
+        fn stop_search(&mut self) {
+            if let Some(search) = self.search.take() {
+                self.stop.store(true, Ordering::Relaxed);
+                search.join().expect("the search thread panicked");
+                self.stop.store(false, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn info_line(info: &SearchInfo) -> String {
+        let score = match info.score {
+            Score::Centipawns(cp) => format!("cp {}", cp),
+            Score::Mate(moves) => format!("mate {}", moves),
+        };
+        let millis = info.time.as_millis() as u64;
+        let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+        format!("info depth {} multipv {} score {} nodes {} nps {} time {} pv {}",
+                info.depth, info.multipv, score, info.nodes, info.nodes * 1000 / millis.max(1), millis, pv.join(" "))
+    }
+}
@@ -0,0 +1,79 @@
+pub mod variant {
+    use crate::chess::{FailReason, Color};
+    use crate::board::board::STARTING_FEN;
+
+    /// which set of rules a Board plays by. Chess960 is not here as it only changes the starting
+    /// position and how castling is written, and can be combined with any of these.
+    #[derive(Copy, Clone, Debug, PartialEq, Default)]
+    pub enum Variant {
+        #[default]
+        Standard,
+        /// captured pieces go to the capturer's pocket and can be dropped back on the board
+        Crazyhouse,
+        /// captures blow up every piece but pawns around them, and losing the king loses the game
+        Atomic,
+        /// getting the king to one of the four centre squares wins
+        KingOfTheHill,
+        /// checking the other king for the third time wins
+        ThreeCheck,
+        /// both sides start on the first two ranks and race their kings to the eighth, giving check is not allowed
+        RacingKings,
+        /// taking is compulsory, the king is an ordinary piece, and losing every piece or having no
+        /// moves wins
+        Antichess,
+        /// white has a horde of 36 pawns and no king, and loses once they are all taken
+        Horde,
+    }
+
+    /// how a game ended by a rule of its variant rather than by checkmate or stalemate
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub enum Outcome {
+        Win(Color),
+        Draw,
+    }
+
+    impl Variant {
+        /// the name used by UCI_Variant and the xboard variant command
+        pub fn name(&self) -> &'static str {
+            match self {
+                Variant::Standard => "chess",
+                Variant::Crazyhouse => "crazyhouse",
+                Variant::Atomic => "atomic",
+                Variant::KingOfTheHill => "kingofthehill",
+                Variant::ThreeCheck => "3check",
+                Variant::RacingKings => "racingkings",
+                Variant::Antichess => "antichess",
+                Variant::Horde => "horde",
+            }
+        }
+
+        pub fn from_name(name: &str) -> Result<Variant, FailReason> {
+            match name.to_lowercase().as_str() {
+                "chess" | "standard" | "normal" => Ok(Variant::Standard),
+                "crazyhouse" | "zh" => Ok(Variant::Crazyhouse),
+                "atomic" => Ok(Variant::Atomic),
+                "kingofthehill" | "koth" => Ok(Variant::KingOfTheHill),
+                "3check" | "threecheck" | "three-check" => Ok(Variant::ThreeCheck),
+                "racingkings" | "racing-kings" => Ok(Variant::RacingKings),
+                "antichess" | "giveaway" | "suicide" => Ok(Variant::Antichess),
+                "horde" => Ok(Variant::Horde),
+                other => Err(FailReason::ParseError(format!("\"{}\" is not a variant we know", other)))
+            }
+        }
+
+        pub fn all() -> &'static [Variant] {
+            &[Variant::Standard, Variant::Crazyhouse, Variant::Atomic, Variant::KingOfTheHill, Variant::ThreeCheck, Variant::RacingKings, Variant::Antichess, Variant::Horde]
+        }
+
+        pub fn starting_fen(&self) -> &'static str {
+            match self {
+                Variant::Standard | Variant::Atomic | Variant::KingOfTheHill => STARTING_FEN,
+                Variant::Crazyhouse => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+                Variant::ThreeCheck => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+                Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+                Variant::Antichess => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+                Variant::Horde => "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+            }
+        }
+    }
+}
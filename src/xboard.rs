@@ -0,0 +1,364 @@
+pub mod xboard {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+    use crate::board::board::Board;
+    use crate::chess::{Color, FailReason};
+    use crate::chess_move::chess_move::Move;
+    use crate::engine::engine::{SearchLimits, SearchInfo, Score, MATE, search_parallel};
+    use crate::transposition::transposition::{TranspositionTable, DEFAULT_HASH_MB};
+    use crate::time_manager::time_manager::TimeControl;
+    use crate::variant::variant::{Variant, Outcome};
+
+    /// a Chess Engine Communication Protocol (xboard/winboard) front end. Like the UCI one,
+    /// commands are fed in with handle and replies go down the channel a line at a time.
+    pub struct XBoard {
+        board: Board,
+        out: Sender<String>,
+        /// the side the engine plays, None while in force mode
+        engine_color: Option<Color>,
+        thinking: Option<Thinking>,
+        table: Arc<TranspositionTable>,
+        cores: usize,
+        post: bool,
+        depth: Option<u32>,
+        seconds_per_move: Option<u64>,
+        moves_per_session: Option<u32>,
+        increment: Duration,
+        time_left: Option<Duration>,
+        /// set by "variant fischerandom", castling is then written O-O and O-O-O
+        chess960: bool,
+        variant: Variant,
+    }
+
+    /// a search running in the background
+    struct Thinking {
+        handle: JoinHandle<Option<Move>>,
+        stop: Arc<AtomicBool>,
+        /// whoever swaps this first decides whether the move gets played or thrown away
+        claimed: Arc<AtomicBool>,
+    }
+
+    impl XBoard {
+        pub fn new(out: Sender<String>) -> Self {
+            XBoard {
+                board: Board::starting_position(),
+                out,
+                engine_color: Some(Color::Black),
+                thinking: None,
+                table: Arc::new(TranspositionTable::new(DEFAULT_HASH_MB)),
+                cores: 1,
+                post: false,
+                depth: None,
+                seconds_per_move: None,
+                moves_per_session: None,
+                increment: Duration::from_secs(0),
+                time_left: None,
+                chess960: false,
+                variant: Variant::Standard,
+            }
+        }
+
+        /// returns false once the GUI has asked us to quit
+        pub fn handle(&mut self, line: &str) -> bool {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.split_first() {
+                Some((&"xboard", _)) => {}
+                Some((&"protover", _)) => {
+                    self.send(String::from("feature myname=\"chess\" usermove=1 setboard=1 ping=1 playother=1 colors=0 sigint=0 sigterm=0 analyze=0 smp=1 memory=1 variants=\"normal,fischerandom,crazyhouse,atomic,kingofthehill,3check,racingkings,antichess,horde\" done=1"));
+                }
+                Some((&"new", _)) => {
+                    self.abandon_thinking();
+                    self.board = Board::starting_position();
+                    self.table.clear();
+                    self.engine_color = Some(Color::Black);
+                    self.depth = None;
+                    self.seconds_per_move = None;
+                    self.chess960 = false;
+                    self.variant = Variant::Standard;
+                }
+                Some((&"variant", [variant])) => {
+                    self.abandon_thinking();
+                    match *variant {
+                        "fischerandom" => {
+                            self.chess960 = true;
+                            self.board.set_chess960(true);
+                        }
+                        other => match Variant::from_name(other) {
+                            Ok(variant) => {
+                                self.chess960 = false;
+                                self.variant = variant;
+                                self.board = Board::from_fen(variant.starting_fen()).expect("every variant's starting position is a valid fen");
+                            }
+                            Err(_) => self.send(format!("Error (unsupported variant): {}", other)),
+                        }
+                    }
+                }
+                Some((&"force", _)) => {
+                    self.abandon_thinking();
+                    self.engine_color = None;
+                }
+                Some((&"go", _)) => {
+                    self.finish_thinking();
+                    self.engine_color = Some(self.board.turn());
+                    self.think_if_our_turn();
+                }
+                Some((&"playother", _)) => {
+                    self.finish_thinking();
+                    self.engine_color = Some(self.board.turn().opposite());
+                }
+                Some((&"usermove", [m])) => self.user_move(m),
+                Some((&"?", _)) => self.move_now(),
+                Some((&"undo", _)) => {
+                    self.abandon_thinking();
+                    self.take_back(1);
+                }
+                Some((&"remove", _)) => {
+                    self.abandon_thinking();
+                    self.take_back(2);
+                }
+                Some((&"setboard", fen)) => {
+                    self.abandon_thinking();
+                    match Board::from_fen(&fen.join(" ")) {
+                        Ok(mut board) => {
+                            board.set_chess960(self.chess960);
+                            if self.variant != Variant::Standard {
+                                board.set_variant(self.variant);
+                            }
+                            self.board = board;
+                        }
+                        Err(reason) => self.send(format!("tellusererror Illegal position: {:?}", reason)),
+                    }
+                }
+                Some((&"level", [moves, base, increment])) => self.level(moves, base, increment),
+                Some((&"st", [seconds])) => self.seconds_per_move = seconds.parse().ok(),
+                Some((&"sd", [depth])) => self.depth = depth.parse().ok(),
+                Some((&"time", [centiseconds])) => self.time_left = centiseconds.parse::<u64>().ok().map(|cs| Duration::from_millis(cs * 10)),
+                Some((&"ping", [n])) => {
+                    self.finish_thinking();
+                    self.send(format!("pong {}", n));
+                }
+                Some((&"cores", [cores])) => {
+                    self.abandon_thinking();
+                    self.cores = cores.parse::<usize>().unwrap_or(1).max(1);
+                }
+                Some((&"memory", [megabytes])) => {
+                    self.abandon_thinking();
+                    self.table = Arc::new(TranspositionTable::new(megabytes.parse().unwrap_or(DEFAULT_HASH_MB)));
+                }
+                Some((&"post", _)) => self.post = true,
+                Some((&"nopost", _)) => self.post = false,
+                Some((&"quit", _)) => {
+                    self.abandon_thinking();
+                    return false;
+                }
+                Some((&"otim", _)) | Some((&"result", _)) | Some((&"accepted", _)) | Some((&"rejected", _))
+                | Some((&"easy", _)) | Some((&"hard", _)) | Some((&"random", _)) | Some((&"computer", _))
+                | Some((&"name", _)) | Some((&"rating", _)) | Some((&"ics", _)) => {}
+                // protocol version 1 GUIs send moves without the usermove prefix
+                Some((m, [])) if self.parse_move(m).is_ok() => self.user_move(m),
+                Some(_) => self.send(format!("Error (unknown command): {}", line.trim())),
+                None => {}
+            }
+            true
+        }
+
+        fn send(&self, line: String) {
+            // the receiving end only goes away when the program is shutting down
+            let _ = self.out.send(line);
+        }
+
+        fn user_move(&mut self, notation: &str) {
+            self.finish_thinking();
+            let result = self.parse_move(notation).and_then(|m| self.board.make_move(m));
+            match result {
+                Ok(()) => {
+                    if !self.report_result() {
+                        self.think_if_our_turn();
+                    }
+                }
+                Err(reason) => self.send(format!("Illegal move ({:?}): {}", reason, notation)),
+            }
+        }
+
+        /// coordinate notation, or O-O and O-O-O for castling in chess960
+        fn parse_move(&self, notation: &str) -> Result<Move, FailReason> {
+            let kingside = match notation {
+                "O-O" | "0-0" => true,
+                "O-O-O" | "0-0-0" => false,
+                _ => return Move::from_coordinate_notation(notation, self.board.turn()),
+            };
+            self.board.legal_moves().into_iter()
+                .find(|m| self.board.castling_rook(m).is_some_and(|rook| (rook.x > m.from.x) == kingside))
+                .ok_or_else(|| FailReason::ImpossibleMove(String::from("cannot castle that way")))
+        }
+
+        fn take_back(&mut self, plies: usize) {
+            for _ in 0..plies {
+                if self.board.last_move().is_some() {
+                    self.board.undo_last_move();
+                }
+            }
+        }
+
+        /// "level 40 5 0" is 40 moves in 5 minutes, "level 0 2:30 1" is 2.5 minutes plus one second a move
+        fn level(&mut self, moves: &str, base: &str, increment: &str) {
+            self.moves_per_session = moves.parse().ok().filter(|moves| *moves > 0);
+            let mut minutes_and_seconds = base.split(':').map(|part| part.parse::<u64>().unwrap_or(0));
+            let minutes = minutes_and_seconds.next().unwrap_or(0);
+            let seconds = minutes_and_seconds.next().unwrap_or(0);
+            self.time_left = Some(Duration::from_secs(minutes * 60 + seconds));
+            self.increment = Duration::from_millis((increment.parse::<f64>().unwrap_or(0.0) * 1000.0) as u64);
+            self.seconds_per_move = None;
+        }
+
+        /// announces the end of the game if there is one, returning whether there was
+        fn report_result(&self) -> bool {
+            match game_result(&self.board) {
+                Some(result) => {
+                    self.send(String::from(result));
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn limits(&self) -> SearchLimits {
+            let time_control = match (self.seconds_per_move, self.time_left) {
+                (None, Some(time_left)) => Some(TimeControl {
+                    time_left,
+                    increment: self.increment,
+                    moves_to_go: self.moves_per_session.map(|per_session| {
+                        let played = (self.board.fullmove_number() - 1) % per_session;
+                        per_session - played
+                    }),
+                    overhead: Duration::from_millis(50),
+                }),
+                _ => None,
+            };
+            let movetime = match self.seconds_per_move {
+                Some(seconds) => Some(Duration::from_secs(seconds)),
+                // nobody has told us about a clock, don't think forever
+                None if time_control.is_none() && self.depth.is_none() => Some(Duration::from_secs(5)),
+                None => None,
+            };
+            SearchLimits {
+                depth: self.depth,
+                movetime,
+                nodes: None,
+                infinite: false,
+                time_control,
+                multi_pv: 1,
+                tablebase: None,
+            }
+        }
+
+        fn think_if_our_turn(&mut self) {
+            if self.engine_color != Some(self.board.turn()) {
+                return;
+            }
+            let board = self.board.clone();
+            let chess960 = self.chess960;
+            let limits = self.limits();
+            let stop = Arc::new(AtomicBool::new(false));
+            let claimed = Arc::new(AtomicBool::new(false));
+            let out = self.out.clone();
+            let post = self.post;
+            let (table, cores) = (Arc::clone(&self.table), self.cores);
+
+            let (search_stop, search_claimed) = (Arc::clone(&stop), Arc::clone(&claimed));
+            let handle = thread::spawn(move || {
+                let mut board = board;
+                let best = search_parallel(&board, limits, cores, table, search_stop, |info| {
+                    if post && info.multipv == 1 {
+                        let _ = out.send(thinking_line(info));
+                    }
+                });
+                if search_claimed.swap(true, Ordering::SeqCst) {
+                    return None;
+                }
+                if let Some(m) = best {
+                    let _ = out.send(format!("move {}", move_notation(&board, m, chess960)));
+                    // the GUI hears how the game ended straight away, not once it next says something
+                    board.make_move(m).expect("the search only returns legal moves");
+                    if let Some(result) = game_result(&board) {
+                        let _ = out.send(String::from(result));
+                    }
+                }
+                best
+            });
+            self.thinking = Some(Thinking { handle, stop, claimed });
+        }
+
+        fn move_now(&mut self) {
+            if let Some(thinking) = &self.thinking {
+                thinking.stop.store(true, Ordering::SeqCst);
+            }
+            self.finish_thinking();
+        }
+
+        /// waits for the search to finish and plays whatever it came up with on our board, the
+        /// search has already told the GUI about the move and any result
+        fn finish_thinking(&mut self) {
+            if let Some(thinking) = self.thinking.take() {
+                if let Some(m) = thinking.handle.join().expect("the search thread panicked") {
+                    self.board.make_move(m).expect("the search only returns legal moves");
+                }
+            }
+        }
+
+        /// stops the search without playing its move, unless the move was already sent
+        fn abandon_thinking(&mut self) {
+            if let Some(thinking) = &self.thinking {
+                if !thinking.claimed.swap(true, Ordering::SeqCst) {
+                    thinking.stop.store(true, Ordering::SeqCst);
+                }
+            }
+            self.finish_thinking();
+        }
+    }
+
+    /// the result line for a game that is over
+    fn game_result(board: &Board) -> Option<&'static str> {
+        let result = if board.is_checkmate() {
+            match board.turn() {
+                Color::White => "0-1 {Black mates}",
+                Color::Black => "1-0 {White mates}",
+            }
+        } else if let Some(outcome) = board.variant_outcome() {
+            match outcome {
+                Outcome::Win(Color::White) => "1-0 {White wins}",
+                Outcome::Win(Color::Black) => "0-1 {Black wins}",
+                Outcome::Draw => "1/2-1/2 {Draw}",
+            }
+        } else if board.is_stalemate() {
+            "1/2-1/2 {Stalemate}"
+        } else if board.halfmove_clock() >= 100 {
+            "1/2-1/2 {Fifty move rule}"
+        } else {
+            return None;
+        };
+        Some(result)
+    }
+
+    fn move_notation(board: &Board, m: Move, chess960: bool) -> String {
+        match board.castling_rook(&m) {
+            Some(rook) if chess960 && rook.x > m.from.x => String::from("O-O"),
+            Some(_) if chess960 => String::from("O-O-O"),
+            _ => m.to_string(),
+        }
+    }
+
+    /// thinking output is "depth score time nodes pv" with time in centiseconds
+    fn thinking_line(info: &SearchInfo) -> String {
+        let score = match info.score {
+            Score::Centipawns(cp) => cp,
+            Score::Mate(moves) if moves > 0 => MATE + moves,
+            Score::Mate(moves) => -MATE + moves,
+        };
+        let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+        format!("{} {} {} {} {}", info.depth, score, info.time.as_millis() / 10, info.nodes, pv.join(" "))
+    }
+}